@@ -0,0 +1,97 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::time::Duration;
+use sum_queue::{bench, ShardedSumQueue, SumQueue, Summable};
+
+const MAX_AGE: Duration = Duration::from_secs(600);
+const SIZES: [usize; 3] = [100, 10_000, 100_000];
+const SEED: u64 = 42;
+
+/// Queue with `len` values that keeps its length on each push.
+fn full_queue<T: From<u32>>(len: usize) -> SumQueue<T> {
+    let mut queue = SumQueue::builder().max_age(MAX_AGE).max_len(len).build();
+    for value in bench::values(len, SEED) {
+        queue.push(value);
+    }
+    queue
+}
+
+fn push<T: From<u32> + Copy>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("push/{}", name));
+    for &len in SIZES.iter() {
+        let values: Vec<T> = bench::values(1024, SEED);
+        group.bench_with_input(BenchmarkId::new("SumQueue", len), &len, |b, &len| {
+            let mut queue = full_queue::<T>(len);
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % values.len();
+                queue.push(black_box(values[i]))
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("prune_every", len), &len, |b, &len| {
+            let mut queue = full_queue::<T>(len).with_prune_every(64);
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % values.len();
+                queue.push(black_box(values[i]))
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("ShardedSumQueue", len), &len, |b, &len| {
+            let queue = ShardedSumQueue::new(MAX_AGE, 4);
+            for value in bench::values::<T>(len, SEED) {
+                queue.push(value);
+            }
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % values.len();
+                queue.push(black_box(values[i]))
+            });
+        });
+    }
+    group.finish();
+}
+
+fn pop<T: From<u32>>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("pop/{}", name));
+    for &len in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("SumQueue", len), &len, |b, &len| {
+            b.iter_batched_ref(
+                || bench::filled_queue::<T>(MAX_AGE, len, SEED),
+                |queue| queue.pop(),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn stats<T: From<u32> + Summable>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("stats/{}", name));
+    for &len in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("SumQueue", len), &len, |b, &len| {
+            let mut queue = bench::filled_queue::<T>(MAX_AGE, len, SEED);
+            b.iter(|| queue.stats());
+        });
+        group.bench_with_input(BenchmarkId::new("ShardedSumQueue", len), &len, |b, &len| {
+            let queue = ShardedSumQueue::new(MAX_AGE, 4);
+            for value in bench::values::<T>(len, SEED) {
+                queue.push(value);
+            }
+            b.iter(|| queue.stats());
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    push::<u32>(c, "u32");
+    push::<u64>(c, "u64");
+    push::<u128>(c, "u128");
+    pop::<u32>(c, "u32");
+    pop::<u128>(c, "u128");
+    stats::<u32>(c, "u32");
+    stats::<u64>(c, "u64");
+    stats::<u128>(c, "u128");
+}
+
+criterion_group!(queue, benches);
+criterion_main!(queue);
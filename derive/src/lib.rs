@@ -0,0 +1,123 @@
+//! Derive macros for the [`sum-queue`](https://docs.rs/sum-queue/) crate,
+//! re-exported by it with the `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Type};
+
+/// Primitive types of the fields with stats.
+const NUMERIC_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32",
+    "f64",
+];
+
+/// Derives `sum_queue::FieldStats` for a struct with named fields, so
+/// `SumQueue::field_stats()` returns the min, max and sum of each numeric
+/// field of the elements, computed in one pass.
+///
+/// The stats are returned in a struct named after the derived one plus
+/// `Stats`, eg. `MeasurementStats` for `Measurement`, with the same
+/// visibility, and with a `QueueStats` field for each field of a primitive
+/// numeric type. The other fields are ignored.
+#[proc_macro_derive(FieldStats)]
+pub fn derive_field_stats(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    field_stats(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn field_stats(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "FieldStats can't be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "FieldStats can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "FieldStats can only be derived for structs",
+            ))
+        }
+    };
+    let numeric = fields
+        .iter()
+        .filter(|field| is_numeric(&field.ty))
+        .collect::<Vec<_>>();
+    let names = numeric.iter().map(|field| &field.ident).collect::<Vec<_>>();
+    let types = numeric.iter().map(|field| &field.ty);
+    let docs = names.iter().map(|name| {
+        format!(
+            "Stats of the `{}` field.",
+            name.as_ref()
+                .map(|name| name.to_string())
+                .unwrap_or_default()
+        )
+    });
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let stats = format_ident!("{}Stats", ident);
+    let stats_doc = format!("Stats of each numeric field of [`{}`].", ident);
+    Ok(quote! {
+        #[doc = #stats_doc]
+        #[derive(Clone, Debug, PartialEq)]
+        #vis struct #stats {
+            #(
+                #[doc = #docs]
+                pub #names: ::sum_queue::QueueStats<#types>,
+            )*
+        }
+
+        impl ::sum_queue::FieldStats for #ident {
+            type Stats = #stats;
+
+            #[allow(unused_mut, unused_variables)]
+            fn field_stats<'a, I>(elements: I, len: usize) -> #stats
+            where
+                I: Iterator<Item = &'a Self>,
+                Self: 'a,
+            {
+                let mut stats = #stats {
+                    #(
+                        #names: ::sum_queue::QueueStats {
+                            min: None,
+                            max: None,
+                            sum: None,
+                            len,
+                        },
+                    )*
+                };
+                for element in elements {
+                    #(
+                        stats.#names.push_value(element.#names);
+                    )*
+                }
+                stats
+            }
+        }
+    })
+}
+
+fn is_numeric(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .map(|ident| NUMERIC_TYPES.iter().any(|t| ident == t))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{QueueStats, SumQueue, Summable};
+use std::sync::Arc;
+
+/// Function called with the stats of the queue.
+type StatsFn<T, R> = Arc<dyn Fn(&QueueStats<T>) -> R + Send + Sync>;
+
+/// Threshold registered with [`SumQueue::alarm_when()`].
+pub(crate) struct Alarm<T> {
+    /// gets the stats of the queue, the type of the elements
+    /// is only known to compute the stats when the alarm is created
+    stats: fn(&SumQueue<T>) -> QueueStats<T>,
+    condition: StatsFn<T, bool>,
+    callback: StatsFn<T, ()>,
+    /// whether the condition was met the last time it was evaluated
+    breached: bool,
+}
+
+impl<T> Clone for Alarm<T> {
+    fn clone(&self) -> Self {
+        Alarm {
+            stats: self.stats,
+            condition: self.condition.clone(),
+            callback: self.callback.clone(),
+            breached: self.breached,
+        }
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Evaluates the alarms, calling the callbacks of the
+    /// alarms whose condition started to be met.
+    pub(crate) fn check_alarms(&mut self) {
+        let Some(alarm) = self.alarms.first() else {
+            return;
+        };
+        let stats = (alarm.stats)(self);
+        for alarm in self.alarms.iter_mut() {
+            let breached = (alarm.condition)(&stats);
+            if breached && !alarm.breached {
+                (alarm.callback)(&stats);
+            }
+            alarm.breached = breached;
+        }
+    }
+}
+
+impl<T: Summable> SumQueue<T> {
+    /// Registers an alarm: after each element is pushed, the `condition`
+    /// is evaluated with the stats of the queue, and when it's met the
+    /// `callback` is called with the same stats, eg. to detect that an error
+    /// budget was burned or that a rate was exceeded.
+    ///
+    /// The `callback` is called when the condition starts to be met, and not
+    /// again until the condition is not met after a push and then is met again,
+    /// so a breach that lasts for many pushes is reported once.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    ///
+    /// let alerts = Arc::new(AtomicUsize::new(0));
+    /// let counter = alerts.clone();
+    /// let mut errors = SumQueue::new(Duration::from_secs(60));
+    /// errors.alarm_when(
+    ///     |stats| stats.sum > Some(1000),
+    ///     move |stats| {
+    ///         println!("{:?} errors in the last minute", stats.sum);
+    ///         counter.fetch_add(1, Ordering::Relaxed);
+    ///     },
+    /// );
+    /// errors.push(600);
+    /// assert_eq!(alerts.load(Ordering::Relaxed), 0);
+    /// errors.push(600);
+    /// errors.push(600);
+    /// assert_eq!(alerts.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn alarm_when<C, F>(&mut self, condition: C, callback: F)
+    where
+        C: Fn(&QueueStats<T>) -> bool + Send + Sync + 'static,
+        F: Fn(&QueueStats<T>) + Send + Sync + 'static,
+    {
+        self.alarms.push(Alarm {
+            stats: |queue| queue._stats(queue.queue.len()),
+            condition: Arc::new(condition),
+            callback: Arc::new(callback),
+            breached: false,
+        });
+    }
+
+    /// Removes all the alarms registered with [`SumQueue::alarm_when()`].
+    pub fn clear_alarms(&mut self) {
+        self.alarms.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn alarm_when() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        let high = fired.clone();
+        queue.alarm_when(
+            |stats| stats.max > Some(10),
+            move |stats| high.lock().unwrap().push(("high", stats.len)),
+        );
+        let many = fired.clone();
+        queue.alarm_when(
+            |stats| stats.len >= 3,
+            move |stats| many.lock().unwrap().push(("many", stats.len)),
+        );
+        queue.push(1);
+        queue.push(20);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(*fired.lock().unwrap(), vec![("high", 2), ("many", 3)]);
+        // the alarms are rearmed once the conditions are not met
+        thread::sleep(Duration::from_millis(150));
+        queue.push(1);
+        queue.push(30);
+        queue.push(40);
+        queue.push(50);
+        assert_eq!(
+            *fired.lock().unwrap(),
+            vec![("high", 2), ("many", 3), ("high", 2), ("many", 3)]
+        );
+        queue.clear_alarms();
+        queue.clear();
+        queue.push(100);
+        assert_eq!(fired.lock().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::now;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+/// Numeric values that can be converted to `f64` to be averaged,
+/// with a loss of precision for the 64 and 128 bits integers
+/// beyond 2^53, like with `as f64`.
+pub trait AsF64: Copy {
+    /// Converts the value to `f64`.
+    fn as_f64(self) -> f64;
+}
+
+macro_rules! impl_as_f64 {
+    ($($t:ty),*) => {
+        $(impl AsF64 for $t {
+            fn as_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_as_f64!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// Moving average of numeric values, either of the values pushed
+/// within a window of time, or of the last N values pushed.
+///
+/// It keeps a running sum of the values, so each push and each
+/// read of the average takes O(1) amortized time.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::MovingAverage;
+///
+/// // average of the values of the last minute
+/// let mut latency = MovingAverage::new(Duration::from_secs(60));
+/// assert_eq!(latency.push(100), 100.0);
+/// assert_eq!(latency.push(200), 150.0);
+///
+/// // average of the last 3 values
+/// let mut temperature = MovingAverage::last_n(3);
+/// temperature.push(20.5);
+/// temperature.push(21.0);
+/// temperature.push(21.5);
+/// assert_eq!(temperature.push(22.0), 21.5);
+/// ```
+pub struct MovingAverage<T> {
+    window: Duration,
+    max_len: Option<usize>,
+    /// values with the instant they were pushed, from the oldest to the newest
+    values: VecDeque<(Instant, T)>,
+    /// sum of the values
+    sum: f64,
+}
+
+impl<T: AsF64> MovingAverage<T> {
+    /// Creates an average of the values pushed within the last `window` of time.
+    pub fn new(window: Duration) -> MovingAverage<T> {
+        MovingAverage {
+            window,
+            max_len: None,
+            values: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+
+    /// Creates an average of the last `n` values pushed (at least one),
+    /// no matter how old they are.
+    pub fn last_n(n: usize) -> MovingAverage<T> {
+        let n = n.max(1);
+        MovingAverage {
+            window: Duration::MAX,
+            max_len: Some(n),
+            values: VecDeque::with_capacity(n),
+            sum: 0.0,
+        }
+    }
+
+    /// Pushes a value, and returns the updated average.
+    pub fn push(&mut self, item: T) -> f64 {
+        let now = now();
+        self.clear_oldest(now);
+        if self
+            .max_len
+            .is_some_and(|max_len| self.values.len() >= max_len)
+        {
+            self.pop_front();
+        }
+        self.values.push_back((now, item));
+        self.sum += item.as_f64();
+        self.sum / self.values.len() as f64
+    }
+
+    /// Returns the average of the values, or `None` if there is no value.
+    pub fn average(&mut self) -> Option<f64> {
+        self.clear_oldest(now());
+        match self.values.len() {
+            0 => None,
+            len => Some(self.sum / len as f64),
+        }
+    }
+
+    /// Returns the number of values averaged.
+    pub fn len(&mut self) -> usize {
+        self.clear_oldest(now());
+        self.values.len()
+    }
+
+    /// Checks if there is no value to average.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.sum = 0.0;
+    }
+
+    /// Drops the values pushed before the window at `now`.
+    fn clear_oldest(&mut self, now: Instant) {
+        while self
+            .values
+            .front()
+            .is_some_and(|&(at, _)| now.saturating_duration_since(at) > self.window)
+        {
+            self.pop_front();
+        }
+    }
+
+    /// Drops the oldest value, taking it out of the sum.
+    fn pop_front(&mut self) {
+        if let Some((_, value)) = self.values.pop_front() {
+            self.sum -= value.as_f64();
+        }
+        if self.values.is_empty() {
+            // so the rounding errors don't add up
+            self.sum = 0.0;
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MovingAverage<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MovingAverage")
+            .field("window", &self.window)
+            .field("max_len", &self.max_len)
+            .field(
+                "values",
+                &self
+                    .values
+                    .iter()
+                    .map(|(_, value)| value)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MovingAverage;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn time_window() {
+        let mut average: MovingAverage<i32> = MovingAverage::new(Duration::from_millis(100));
+        assert_eq!(average.average(), None);
+        assert_eq!(average.push(10), 10.0);
+        assert_eq!(average.push(-4), 3.0);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(average.average(), None);
+        assert_eq!(average.push(7), 7.0);
+        assert_eq!(average.len(), 1);
+    }
+
+    #[test]
+    fn last_n() {
+        let mut average = MovingAverage::last_n(2);
+        assert!(average.is_empty());
+        average.push(1.0f32);
+        average.push(2.0);
+        assert_eq!(average.push(4.0), 3.0);
+        assert_eq!(average.len(), 2);
+        average.clear();
+        assert_eq!(average.average(), None);
+        let mut one = MovingAverage::last_n(0);
+        one.push(1u8);
+        assert_eq!(one.push(5), 5.0);
+        // 64 bits integers, like latencies in microseconds
+        let mut latency: MovingAverage<u64> = MovingAverage::last_n(3);
+        for micros in [1_000, 3_000, 2_000, 7_000] {
+            latency.push(micros);
+        }
+        assert_eq!(latency.average(), Some(4_000.0));
+        let mut offsets = MovingAverage::last_n(2);
+        offsets.push(-5i64);
+        assert_eq!(offsets.push(1), -2.0);
+        let mut sizes = MovingAverage::last_n(2);
+        sizes.push(1usize);
+        assert_eq!(sizes.push(2), 1.5);
+    }
+}
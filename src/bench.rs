@@ -0,0 +1,70 @@
+//! Data generators for benchmarks, used by the benchmarks of the crate
+//! and reusable to measure the queues with the workloads of other crates.
+//!
+//! The values are pseudo-random but deterministic, generated from a
+//! seed, so the runs of a benchmark can be compared with each other.
+//!
+//! ```
+//! use std::time::Duration;
+//! use sum_queue::bench;
+//!
+//! let values: Vec<u64> = bench::values(1000, 42);
+//! assert_eq!(values, bench::values::<u64>(1000, 42));
+//! let mut queue = bench::filled_queue::<u64>(Duration::from_secs(60), 1000, 42);
+//! assert_eq!(queue.len(), 1000);
+//! ```
+
+use crate::SumQueue;
+use std::time::Duration;
+
+/// Pseudo-random number generator with the xorshift algorithm,
+/// fast and good enough for benchmarks, but not for cryptography.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from `seed`, any seed is valid.
+    pub fn new(seed: u64) -> Rng {
+        // the state must not be zero
+        Rng((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    /// Returns the next pseudo-random number.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x >> 32) as u32
+    }
+}
+
+/// Returns `len` pseudo-random values generated from `seed`.
+pub fn values<T: From<u32>>(len: usize, seed: u64) -> Vec<T> {
+    let mut rng = Rng::new(seed);
+    (0..len).map(|_| T::from(rng.next_u32())).collect()
+}
+
+/// Returns a queue with `len` pseudo-random values generated from
+/// `seed`, where the elements will live `max_age` at maximum.
+pub fn filled_queue<T: From<u32>>(max_age: Duration, len: usize, seed: u64) -> SumQueue<T> {
+    SumQueue::from((values(len, seed), max_age))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bench::{self, Rng};
+    use std::time::Duration;
+
+    #[test]
+    fn values() {
+        let mut rng = Rng::new(0);
+        assert_ne!(rng.next_u32(), rng.next_u32());
+        let values: Vec<u64> = bench::values(100, 7);
+        assert_eq!(values.len(), 100);
+        assert_ne!(values, bench::values::<u64>(100, 8));
+        let mut queue = bench::filled_queue::<u64>(Duration::from_secs(60), 100, 7);
+        assert_eq!(queue.to_vec(), values);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::sliding::Buckets;
+use std::collections::hash_map::RandomState;
+use std::f64::consts::LN_2;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Approximate set of the values seen within a sliding window of time,
+/// using a bloom filter per time bucket, to check if a value was "already
+/// seen recently" when there are too many distinct values to keep them all.
+///
+/// The checks never miss a value seen within the window, but they can
+/// wrongly report as seen a value that wasn't, with a probability close
+/// to the false positive rate chosen. It's the approximate complement of
+/// [`crate::SumQueue::push_unique()`], that keeps every value.
+///
+/// Like in a [`crate::SlidingSum`], the window is split into a fixed number of
+/// buckets, and the filter of each bucket is dropped when it leaves the window.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::WindowedBloom;
+///
+/// // up to 10,000 values per bucket with a 1% of false positives
+/// let mut seen: WindowedBloom<str> =
+///     WindowedBloom::new(Duration::from_secs(300), 5, 10_000, 0.01);
+/// assert!(!seen.seen_recently("alert: disk full"));
+/// assert!(seen.seen_recently("alert: disk full"));
+/// assert!(seen.contains("alert: disk full"));
+/// assert!(!seen.contains("alert: high cpu"));
+/// ```
+///
+/// The values are hashed with a [`RandomState`] by default, so the false
+/// positives can change between runs, use [`WindowedBloom::with_hasher()`]
+/// to hash them with a fixed [`BuildHasher`] instead.
+pub struct WindowedBloom<T: ?Sized, S = RandomState> {
+    /// bits of the filter of each bucket
+    buckets: Buckets<Vec<u64>>,
+    bits: usize,
+    hashes: usize,
+    hasher: S,
+    values: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> WindowedBloom<T> {
+    /// Creates an empty set of the values seen within the last `window`
+    /// of time, split in `buckets` buckets (at least one), with filters
+    /// sized to hold `capacity` values per bucket with a rate of false
+    /// positives close to `false_positive_rate`.
+    pub fn new(
+        window: Duration,
+        buckets: usize,
+        capacity: usize,
+        false_positive_rate: f64,
+    ) -> WindowedBloom<T> {
+        WindowedBloom::with_hasher(
+            window,
+            buckets,
+            capacity,
+            false_positive_rate,
+            RandomState::new(),
+        )
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> WindowedBloom<T, S> {
+    /// Creates an empty set like [`WindowedBloom::new()`],
+    /// that hashes the values with `hasher`.
+    pub fn with_hasher(
+        window: Duration,
+        buckets: usize,
+        capacity: usize,
+        false_positive_rate: f64,
+        hasher: S,
+    ) -> WindowedBloom<T, S> {
+        let capacity = capacity.max(1) as f64;
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let bits = (-capacity * rate.ln() / (LN_2 * LN_2)).ceil().max(64.0);
+        let hashes = (bits / capacity * LN_2).round().max(1.0);
+        WindowedBloom {
+            buckets: Buckets::new(window, buckets),
+            bits: bits as usize,
+            hashes: hashes as usize,
+            hasher,
+            values: PhantomData,
+        }
+    }
+
+    /// Returns the number of bits of each filter.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Returns the number of hashes computed for each value.
+    pub fn hashes(&self) -> usize {
+        self.hashes
+    }
+
+    /// Returns the positions of the bits of `item`.
+    fn positions(&self, item: &T) -> impl Iterator<Item = usize> {
+        let hash = self.hasher.hash_one(item);
+        // double hashing: the position of each bit is h1 + i * h2
+        let (h1, h2) = (hash as u32 as u64, (hash >> 32) | 1);
+        let bits = self.bits as u64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    /// Adds a value to the filter of the current bucket.
+    pub fn insert(&mut self, item: &T) {
+        let positions = self.positions(item).collect::<Vec<_>>();
+        let words = self.bits.div_ceil(64);
+        let filter = self.buckets.current(|| vec![0; words]);
+        for position in positions {
+            filter[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /// Checks if the value was probably seen within the window.
+    pub fn contains(&mut self, item: &T) -> bool {
+        let positions = self.positions(item).collect::<Vec<_>>();
+        self.buckets.iter().any(|filter| {
+            positions
+                .iter()
+                .all(|&position| filter[position / 64] & (1 << (position % 64)) != 0)
+        })
+    }
+
+    /// Checks if the value was probably seen within the window, and adds it.
+    pub fn seen_recently(&mut self, item: &T) -> bool {
+        let seen = self.contains(item);
+        self.insert(item);
+        seen
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl<T: ?Sized, S> fmt::Debug for WindowedBloom<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowedBloom")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .field("bits", &self.bits)
+            .field("hashes", &self.hashes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WindowedBloom;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn false_positives() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let mut seen = WindowedBloom::with_hasher(Duration::from_secs(60), 2, 10_000, 0.01, hasher);
+        assert_eq!(seen.bits(), 95_851);
+        assert_eq!(seen.hashes(), 7);
+        let seen_before = (0..10_000u32).filter(|i| seen.seen_recently(i)).count();
+        assert!(seen_before < 100, "seen before: {}", seen_before);
+        assert!((0..10_000u32).all(|i| seen.contains(&i)));
+        let false_positives = (10_000..20_000u32).filter(|i| seen.contains(i)).count();
+        assert!(
+            false_positives < 200,
+            "false positives: {}",
+            false_positives
+        );
+        seen.clear();
+        assert!(!seen.contains(&1));
+    }
+
+    #[test]
+    fn expire_buckets() {
+        let mut seen = WindowedBloom::new(Duration::from_millis(100), 2, 100, 0.001);
+        seen.insert(&'a');
+        thread::sleep(Duration::from_millis(60));
+        assert!(seen.seen_recently(&'a'));
+        assert!(!seen.seen_recently(&'b'));
+        thread::sleep(Duration::from_millis(60));
+        // 'a' was seen again after the first bucket
+        assert!(seen.contains(&'a'));
+        assert!(seen.contains(&'b'));
+        thread::sleep(Duration::from_millis(120));
+        assert!(!seen.contains(&'a'));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    check_max_age, Clock, DepthHistory, Epoch, ExpireCallback, NanPolicy, ResidenceHistory,
+    SumQueue, DEFAULT_MAX_AGE,
+};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builder to configure and create a [`SumQueue`].
+///
+/// This `struct` is created by [`SumQueue::builder()`], all the
+/// settings are optional:
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+///
+/// let dropped = Arc::new(AtomicUsize::new(0));
+/// let counter = dropped.clone();
+/// let mut queue = SumQueue::builder()
+///     .max_age(Duration::from_secs(60))
+///     .max_len(2)
+///     .on_expire(move |_| {
+///         counter.fetch_add(1, Ordering::Relaxed);
+///     })
+///     .build();
+/// queue.push(1);
+/// queue.push(2);
+/// queue.push(3);
+/// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &3]);
+/// assert_eq!(dropped.load(Ordering::Relaxed), 1);
+/// ```
+pub struct SumQueueBuilder<T> {
+    max_age: Duration,
+    capacity: usize,
+    max_len: Option<usize>,
+    min_age: Duration,
+    grace: Duration,
+    depth: Option<DepthHistory>,
+    residence: Option<ResidenceHistory>,
+    on_expire: Option<ExpireCallback<T>>,
+    nan_policy: NanPolicy,
+    clock: Clock,
+    prune_every: Option<usize>,
+    prune_interval: Option<Duration>,
+}
+
+impl<T> SumQueueBuilder<T> {
+    /// Creates a builder with the default settings: elements
+    /// live [`DEFAULT_MAX_AGE`] at maximum, and there is no limit
+    /// in the number of elements.
+    pub fn new() -> SumQueueBuilder<T> {
+        SumQueueBuilder {
+            max_age: DEFAULT_MAX_AGE,
+            capacity: 0,
+            max_len: None,
+            min_age: Duration::ZERO,
+            grace: Duration::ZERO,
+            depth: None,
+            residence: None,
+            on_expire: None,
+            nan_policy: NanPolicy::Propagate,
+            clock: Clock::system(),
+            prune_every: None,
+            prune_interval: None,
+        }
+    }
+
+    /// Sets the max time the elements will live in the queue,
+    /// see [`SumQueue::new()`].
+    pub fn max_age(mut self, max_age: Duration) -> SumQueueBuilder<T> {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the initial capacity of the queue,
+    /// see [`SumQueue::with_capacity()`].
+    pub fn capacity(mut self, capacity: usize) -> SumQueueBuilder<T> {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the max number of elements the queue can hold. When
+    /// a new element is pushed into a full queue, the oldest
+    /// element is dropped to make room for the new one.
+    pub fn max_len(mut self, max_len: usize) -> SumQueueBuilder<T> {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Sets the min time the elements have to be in the
+    /// queue before they can be popped, see [`SumQueue::with_min_age()`].
+    pub fn min_age(mut self, min_age: Duration) -> SumQueueBuilder<T> {
+        self.min_age = min_age;
+        self
+    }
+
+    /// Sets the time the expired elements are kept for the
+    /// queries of the past, see [`SumQueue::with_grace()`].
+    pub fn grace(mut self, grace: Duration) -> SumQueueBuilder<T> {
+        self.grace = grace;
+        self
+    }
+
+    /// Records the length of the queue within the last `window` of
+    /// time, see [`SumQueue::with_depth_history()`].
+    pub fn depth_history(mut self, window: Duration, buckets: usize) -> SumQueueBuilder<T> {
+        self.depth = Some(DepthHistory::new(window, buckets));
+        self
+    }
+
+    /// Records the time the elements popped were in the queue within
+    /// the last `window` of time, see [`SumQueue::with_residence_history()`].
+    pub fn residence_history(mut self, window: Duration, buckets: usize) -> SumQueueBuilder<T> {
+        self.residence = Some(ResidenceHistory::new(window, buckets));
+        self
+    }
+
+    /// Sets a function that is called with each element dropped by
+    /// the queue itself, either because it expired, or because
+    /// it was the oldest element in a queue that reached its max length.
+    /// The elements dropped early with [`SumQueue::keep_only_last()`]
+    /// or [`SumQueue::truncate_to_newest()`] are passed to the function as well.
+    ///
+    /// Elements removed by the caller, eg. with [`SumQueue::pop()`]
+    /// or [`SumQueue::clear()`], are not passed to the function.
+    pub fn on_expire<F>(mut self, on_expire: F) -> SumQueueBuilder<T>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.on_expire = Some(Arc::new(on_expire));
+        self
+    }
+
+    /// Sets how the `NaN` values affect the stats
+    /// of the queue, see [`SumQueue::with_nan_policy()`].
+    pub fn nan_policy(mut self, policy: NanPolicy) -> SumQueueBuilder<T> {
+        self.nan_policy = policy;
+        self
+    }
+
+    /// Sets the clock the queue reads the time
+    /// from, see [`SumQueue::with_clock()`].
+    pub fn clock(mut self, clock: Clock) -> SumQueueBuilder<T> {
+        self.clock = clock;
+        self
+    }
+
+    /// Drops the expired elements only once every `ops`
+    /// operations, see [`SumQueue::with_prune_every()`].
+    pub fn prune_every(mut self, ops: usize) -> SumQueueBuilder<T> {
+        self.prune_every = Some(ops);
+        self
+    }
+
+    /// Drops the expired elements at most once per
+    /// `interval`, see [`SumQueue::with_prune_interval()`].
+    pub fn prune_interval(mut self, interval: Duration) -> SumQueueBuilder<T> {
+        self.prune_interval = Some(interval);
+        self
+    }
+
+    /// Creates the `SumQueue` with the settings of the builder.
+    ///
+    /// It panics if the max age plus the grace period is longer
+    /// than [`crate::MAX_AGE_LIMIT`].
+    pub fn build(self) -> SumQueue<T> {
+        check_max_age(self.max_age, self.grace);
+        let mut queue = SumQueue {
+            queue: VecDeque::with_capacity(self.capacity),
+            epoch: Epoch::new(),
+            max_age: self.max_age,
+            max_len: self.max_len,
+            min_age: self.min_age,
+            grace: self.grace,
+            expired: VecDeque::new(),
+            depth: self.depth,
+            residence: self.residence,
+            min_max: None,
+            unique: None,
+            on_expire: self.on_expire,
+            alarms: Vec::new(),
+            decay: None,
+            nan_policy: self.nan_policy,
+            observers: Vec::new(),
+            clock: self.clock,
+            prune: None,
+        };
+        if let Some(ops) = self.prune_every {
+            queue = queue.with_prune_every(ops);
+        }
+        if let Some(interval) = self.prune_interval {
+            queue = queue.with_prune_interval(interval);
+        }
+        queue
+    }
+}
+
+impl<T> Default for SumQueueBuilder<T> {
+    fn default() -> Self {
+        SumQueueBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn build_defaults() {
+        let mut queue: SumQueue<i32> = SumQueue::builder().build();
+        assert_eq!(queue.max_age(), Duration::from_secs(60));
+        assert_eq!(queue.max_len(), None);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn max_len() {
+        let mut queue = SumQueue::builder().capacity(2).max_len(3).build();
+        assert!(queue.capacity() >= 2);
+        for i in 0..5 {
+            queue.push(i);
+        }
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+        let mut other = SumQueue::new(Duration::from_secs(60));
+        other.push(5);
+        assert_eq!(queue.append(&mut other), 3);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&3, &4, &5]);
+    }
+
+    #[test]
+    fn on_expire() {
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let dropped_ref = dropped.clone();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_millis(100))
+            .max_len(2)
+            .on_expire(move |value| dropped_ref.lock().unwrap().push(value))
+            .build();
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(*dropped.lock().unwrap(), vec![1]);
+        queue.pop();
+        assert_eq!(*dropped.lock().unwrap(), vec![1]);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.len(), 0);
+        assert_eq!(*dropped.lock().unwrap(), vec![1, 3]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{Instant, SumQueue};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// Source of the time of a [`SumQueue`], see [`SumQueue::with_clock()`].
+///
+/// By default the queues read the system clock with `Instant::now()` on
+/// each operation, that is measurable at millions of operations per second.
+/// A [`Clock::coarse()`] clock is read from a time cached in memory instead,
+/// trading a bit of precision in the expiration of the elements for throughput.
+/// And the time of a [`Clock::manual()`] clock only moves when it's advanced
+/// by hand, eg. to test the expiration of the elements without waiting.
+///
+/// A clock can be cloned and shared by many queues.
+#[derive(Clone, Debug, Default)]
+pub struct Clock(ClockKind);
+
+#[derive(Clone, Debug, Default)]
+enum ClockKind {
+    #[default]
+    System,
+    Coarse(Arc<SharedTime>),
+    Manual(Arc<SharedTime>),
+}
+
+/// Time kept in memory, updated by a background
+/// thread or advanced by hand.
+#[derive(Debug)]
+struct SharedTime {
+    start: Instant,
+    /// nanoseconds elapsed since `start`
+    elapsed: AtomicU64,
+}
+
+impl Clock {
+    /// Returns the clock that reads the system time on each call,
+    /// used by the queues by default.
+    pub const fn system() -> Clock {
+        Clock(ClockKind::System)
+    }
+
+    /// Returns a clock that reads a time cached in memory, updated every
+    /// `granularity` by a background thread, so it can be behind the system
+    /// time by up to `granularity`, and the elements may expire that later.
+    ///
+    /// The thread ends after the clock and all its clones are dropped.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{Clock, SumQueue};
+    /// let clock = Clock::coarse(Duration::from_millis(1));
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_clock(clock.clone());
+    /// let mut other = SumQueue::new(Duration::from_secs(60)).with_clock(clock);
+    /// queue.push(1);
+    /// other.push(2);
+    /// assert_eq!(queue.len() + other.len(), 2);
+    /// ```
+    pub fn coarse(granularity: Duration) -> Clock {
+        let time = SharedTime::new();
+        let weak: Weak<SharedTime> = Arc::downgrade(&time);
+        thread::spawn(move || loop {
+            thread::sleep(granularity);
+            match weak.upgrade() {
+                Some(time) => {
+                    let elapsed = time.start.elapsed().as_nanos() as u64;
+                    time.elapsed.store(elapsed, Ordering::Relaxed);
+                }
+                None => break,
+            }
+        });
+        Clock(ClockKind::Coarse(time))
+    }
+
+    /// Returns a clock whose time only moves when it's advanced with
+    /// [`Clock::advance()`], shared by all its clones.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{Clock, SumQueue};
+    /// let clock = Clock::manual();
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_clock(clock.clone());
+    /// queue.push(1);
+    /// clock.advance(Duration::from_secs(30));
+    /// queue.push(2);
+    /// clock.advance(Duration::from_secs(45));
+    /// assert_eq!(queue.to_vec(), vec![2]);
+    /// ```
+    pub fn manual() -> Clock {
+        Clock(ClockKind::Manual(SharedTime::new()))
+    }
+
+    /// Moves the time of a [`Clock::manual()`] clock forward by `by`.
+    /// The other clocks are not affected.
+    pub fn advance(&self, by: Duration) {
+        if let ClockKind::Manual(time) = &self.0 {
+            time.elapsed
+                .fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Instant {
+        match &self.0 {
+            ClockKind::System => Instant::now(),
+            ClockKind::Coarse(time) | ClockKind::Manual(time) => time.now(),
+        }
+    }
+}
+
+impl SharedTime {
+    fn new() -> Arc<SharedTime> {
+        Arc::new(SharedTime {
+            start: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        })
+    }
+
+    fn now(&self) -> Instant {
+        self.start + Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Sets the clock the queue reads the time from, eg. a [`Clock::coarse()`]
+    /// one to avoid reading the system clock on each operation.
+    pub fn with_clock(mut self, clock: Clock) -> SumQueue<T> {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the clock the queue reads the time from.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Returns the current time of the clock of the queue.
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, SumQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn coarse_clock() {
+        let clock = Clock::coarse(Duration::from_millis(10));
+        let start = clock.now();
+        thread::sleep(Duration::from_millis(50));
+        assert!(clock.now() - start >= Duration::from_millis(30));
+
+        let mut queue = SumQueue::new(Duration::from_millis(100)).with_clock(clock);
+        queue.push(1);
+        thread::sleep(Duration::from_millis(60));
+        queue.push(2);
+        assert_eq!(queue.len(), 2);
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2]);
+    }
+}
@@ -0,0 +1,409 @@
+//! Lock-free version of the queue.
+//!
+//! The [`SumQueue`] of this module is a linked list of elements where
+//! many threads can push and pop elements at the same time without
+//! taking any lock, based on the Michael-Scott queue algorithm, and
+//! using the epoch-based memory reclamation of `crossbeam-epoch`
+//! to free the memory of the popped elements.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use std::time::Duration;
+//! use sum_queue::concurrent::SumQueue;
+//!
+//! let queue = Arc::new(SumQueue::new(Duration::from_secs(60)));
+//! let producers = (0..4)
+//!     .map(|_| {
+//!         let queue = queue.clone();
+//!         thread::spawn(move || {
+//!             for i in 1..=100 {
+//!                 queue.push(i);
+//!             }
+//!         })
+//!     })
+//!     .collect::<Vec<_>>();
+//! for producer in producers {
+//!     producer.join().unwrap();
+//! }
+//! assert_eq!(queue.len(), 400);
+//! assert_eq!(queue.stats().sum, Some(4 * 5050));
+//! ```
+//!
+//! Unlike the queues of the [`crate::sync`] module, there is no
+//! instant where the whole queue is frozen, so while other threads are
+//! pushing or popping elements, the results of methods like
+//! [`SumQueue::len()`] or [`SumQueue::stats()`] are approximate.
+//! Also, elements pushed at the same time by different threads may
+//! be stored in a slightly different order than their push times.
+
+use crate::{now, Instant, QueueStats, Summable};
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+use crossbeam_utils::CachePadded;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Element of the queue with the time it was pushed, stored as
+/// an `Instant` since the lock-free queue can't move an epoch
+/// like [`crate::SumQueue`] does to store shorter times.
+struct Element<T> {
+    time: Instant,
+    value: T,
+}
+
+/// Node of the linked list. The first node is always a
+/// "sentinel" node, whose element is not initialized or
+/// was already popped.
+struct Node<T> {
+    element: MaybeUninit<Element<T>>,
+    next: Atomic<Node<T>>,
+}
+
+/// Lock-free queue, see the [module documentation](self) for more.
+pub struct SumQueue<T> {
+    head: CachePadded<Atomic<Node<T>>>,
+    tail: CachePadded<Atomic<Node<T>>>,
+    /// number of elements, including the expired ones not dropped yet
+    len: AtomicUsize,
+    max_age: Duration,
+}
+
+// The elements are moved between threads when popped, but
+// never shared by reference, except in the methods that require `T: Sync`.
+unsafe impl<T: Send> Send for SumQueue<T> {}
+unsafe impl<T: Send> Sync for SumQueue<T> {}
+
+impl<T> SumQueue<T> {
+    /// Creates an empty `SumQueue`, where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> SumQueue<T> {
+        let sentinel = Owned::new(Node {
+            element: MaybeUninit::uninit(),
+            next: Atomic::null(),
+        });
+        // Safety: the queue is not shared yet.
+        let sentinel = sentinel.into_shared(unsafe { epoch::unprotected() });
+        SumQueue {
+            head: CachePadded::new(Atomic::from(sentinel)),
+            tail: CachePadded::new(Atomic::from(sentinel)),
+            len: AtomicUsize::new(0),
+            max_age: max_age_duration,
+        }
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    fn is_expired(&self, el: &Element<T>, now: Instant) -> bool {
+        now.saturating_duration_since(el.time) > self.max_age
+    }
+
+    /// Pushes an item at the back of the queue.
+    ///
+    /// Unlike [`crate::SumQueue::push()`] it doesn't drop the expired
+    /// elements, nor returns the length of the queue.
+    pub fn push(&self, item: T) {
+        let guard = epoch::pin();
+        let new = Owned::new(Node {
+            element: MaybeUninit::new(Element {
+                time: now(),
+                value: item,
+            }),
+            next: Atomic::null(),
+        })
+        .into_shared(&guard);
+        // counted before the node is linked, so a concurrent
+        // pop never decrements the counter below zero
+        self.len.fetch_add(1, Ordering::Relaxed);
+        loop {
+            let tail = self.tail.load(Ordering::Acquire, &guard);
+            // Safety: the tail is never null, and it's not
+            // destroyed while the guard is alive.
+            let tail_ref = unsafe { tail.deref() };
+            let next = tail_ref.next.load(Ordering::Acquire, &guard);
+            if !next.is_null() {
+                // the tail is behind, help to move it forward
+                let _ = self.tail.compare_exchange(
+                    tail,
+                    next,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                    &guard,
+                );
+                continue;
+            }
+            if tail_ref
+                .next
+                .compare_exchange(
+                    Shared::null(),
+                    new,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                    &guard,
+                )
+                .is_ok()
+            {
+                let _ = self.tail.compare_exchange(
+                    tail,
+                    new,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                    &guard,
+                );
+                return;
+            }
+        }
+    }
+
+    /// Removes the first element if `condition` returns `true` for it.
+    fn pop_if<F>(&self, condition: F, guard: &Guard) -> Option<Element<T>>
+    where
+        F: Fn(&Element<T>) -> bool,
+    {
+        loop {
+            let head = self.head.load(Ordering::Acquire, guard);
+            // Safety: the head is never null, and it's not
+            // destroyed while the guard is alive.
+            let next = unsafe { head.deref() }.next.load(Ordering::Acquire, guard);
+            // Safety: same as above, the next node is valid while the
+            // guard is alive, and its element was initialized by the push.
+            let next_ref = unsafe { next.as_ref() }?;
+            // only the time of the element is read until the node is
+            // owned, because another thread may be moving out the value
+            if !condition(unsafe { &*next_ref.element.as_ptr() }) {
+                return None;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::Release, Ordering::Relaxed, guard)
+                .is_ok()
+            {
+                let tail = self.tail.load(Ordering::Relaxed, guard);
+                if head == tail {
+                    let _ = self.tail.compare_exchange(
+                        tail,
+                        next,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                        guard,
+                    );
+                }
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                // Safety: the old head is not reachable anymore, and
+                // the next node becomes the sentinel, so its element is
+                // read only once, here, by the thread that won the race.
+                unsafe {
+                    guard.defer_destroy(head);
+                    return Some(next_ref.element.as_ptr().read());
+                }
+            }
+        }
+    }
+
+    /// Drops the expired elements at the front of the queue.
+    fn clear_oldest(&self, now: Instant, guard: &Guard) {
+        while self.pop_if(|el| self.is_expired(el, now), guard).is_some() {}
+    }
+
+    /// Removes the first item from the queue and returns it, or `None` if it
+    /// is empty. Expired elements are dropped.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::concurrent::SumQueue;
+    /// let queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push('a');
+    /// queue.push('b');
+    /// assert_eq!(queue.pop(), Some('a'));
+    /// assert_eq!(queue.pop(), Some('b'));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        let now = now();
+        self.clear_oldest(now, &guard);
+        self.pop_if(|_| true, &guard).map(|el| el.value)
+    }
+
+    /// Drops all items.
+    pub fn clear(&self) {
+        let guard = epoch::pin();
+        while self.pop_if(|_| true, &guard).is_some() {}
+    }
+
+    /// Returns the approximate length of the queue. Before the length is
+    /// computed, it also drops the expired elements at the front of the queue.
+    pub fn len(&self) -> usize {
+        let guard = epoch::pin();
+        self.clear_oldest(now(), &guard);
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Checks if the queue is empty, dropping the expired elements first.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Summable + Sync> SumQueue<T> {
+    /// Get approximate statistics of the queue, see [`crate::SumQueue::stats()`].
+    ///
+    /// The elements are visited without locking the queue, so the
+    /// elements pushed or popped by other threads in the meantime
+    /// may or may not be included in the stats.
+    ///
+    /// Before the stats are computed, it also drops the expired
+    /// elements at the front of the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::concurrent::SumQueue;
+    /// let queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(-10);
+    /// queue.push(50);
+    /// queue.push(40);
+    /// let stats = queue.stats();
+    /// assert_eq!(stats.min, Some(-10));
+    /// assert_eq!(stats.max, Some(50));
+    /// assert_eq!(stats.sum, Some(80));
+    /// assert_eq!(stats.len, 3);
+    /// ```
+    pub fn stats(&self) -> QueueStats<T> {
+        let guard = epoch::pin();
+        let now = now();
+        self.clear_oldest(now, &guard);
+        let mut stats = QueueStats::default();
+        let head = self.head.load(Ordering::Acquire, &guard);
+        // Safety: the nodes are not destroyed while the guard is alive,
+        // and the values are `Copy`, so they stay valid even if another
+        // thread pops them in the meantime.
+        let mut node = unsafe { head.deref() }.next.load(Ordering::Acquire, &guard);
+        while let Some(node_ref) = unsafe { node.as_ref() } {
+            let el = unsafe { &*node_ref.element.as_ptr() };
+            if !self.is_expired(el, now) {
+                stats = stats.merge(QueueStats {
+                    min: Some(el.value),
+                    max: Some(el.value),
+                    sum: Some(el.value),
+                    len: 1,
+                });
+            }
+            node = node_ref.next.load(Ordering::Acquire, &guard);
+        }
+        stats
+    }
+}
+
+impl<T> Drop for SumQueue<T> {
+    fn drop(&mut self) {
+        // Safety: the queue is not shared anymore.
+        unsafe {
+            let guard = epoch::unprotected();
+            while self.pop_if(|_| true, guard).is_some() {}
+            drop(self.head.load(Ordering::Relaxed, guard).into_owned());
+        }
+    }
+}
+
+impl<T> Default for SumQueue<T> {
+    fn default() -> Self {
+        SumQueue::new(crate::DEFAULT_MAX_AGE)
+    }
+}
+
+impl<T> fmt::Debug for SumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SumQueue")
+            .field("max_age", &self.max_age)
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::concurrent::SumQueue;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn push_pop_threads() {
+        let queue = Arc::new(SumQueue::new(Duration::from_secs(60)));
+        let producers = (0..4)
+            .map(|t| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        queue.push(t * 1000 + i);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let consumers = (0..4)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for _ in 0..500 {
+                        if let Some(value) = queue.pop() {
+                            popped.push(value);
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect::<Vec<_>>();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let mut popped = Vec::new();
+        for consumer in consumers {
+            popped.extend(consumer.join().unwrap());
+        }
+        while let Some(value) = queue.pop() {
+            popped.push(value);
+        }
+        popped.sort_unstable();
+        assert_eq!(popped, (0..4000).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+        assert_eq!(queue.stats().len, 0);
+    }
+
+    #[test]
+    fn expire() {
+        let queue = SumQueue::new(Duration::from_millis(100));
+        queue.push(1);
+        queue.push(2);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(3);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.stats().sum, Some(3));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn drop_elements() {
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let queue = SumQueue::new(Duration::from_secs(60));
+        for _ in 0..3 {
+            queue.push(Counted(dropped.clone()));
+        }
+        drop(queue.pop());
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+        drop(queue);
+        assert_eq!(dropped.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::SumQueue;
+use std::time::Duration;
+
+/// Covariance and correlation of pairs of values, computed in one pass.
+#[derive(Default)]
+struct PairStats {
+    len: usize,
+    mean_x: f64,
+    mean_y: f64,
+    /// sums of the products of the deviations from the means
+    co_moment: f64,
+    moment_x: f64,
+    moment_y: f64,
+}
+
+impl PairStats {
+    fn from_pairs(pairs: impl Iterator<Item = (f64, f64)>) -> PairStats {
+        let mut stats = PairStats::default();
+        for (x, y) in pairs {
+            stats.len += 1;
+            let n = stats.len as f64;
+            let dx = x - stats.mean_x;
+            stats.mean_x += dx / n;
+            let dy = y - stats.mean_y;
+            stats.mean_y += dy / n;
+            stats.co_moment += dx * (y - stats.mean_y);
+            stats.moment_x += dx * (x - stats.mean_x);
+            stats.moment_y += dy * (y - stats.mean_y);
+        }
+        stats
+    }
+
+    fn covariance(&self) -> Option<f64> {
+        if self.len == 0 {
+            return None;
+        }
+        Some(self.co_moment / self.len as f64)
+    }
+
+    fn correlation(&self) -> Option<f64> {
+        let deviations = (self.moment_x * self.moment_y).sqrt();
+        if self.len < 2 || deviations == 0.0 {
+            return None;
+        }
+        Some(self.co_moment / deviations)
+    }
+}
+
+impl<A: Copy + Into<f64>, B: Copy + Into<f64>> SumQueue<(A, B)> {
+    /// Returns the (population) covariance of the pairs of values of
+    /// the queue, or `None` if it is empty, eg. to monitor the relationship
+    /// between the latency of the requests and the size of their payload.
+    ///
+    /// Before the covariance is computed, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push((1, 2.0));
+    /// queue.push((2, 4.0));
+    /// queue.push((3, 6.0));
+    /// assert_eq!(queue.covariance(), Some(4.0 / 3.0));
+    /// ```
+    pub fn covariance(&mut self) -> Option<f64> {
+        self.pair_stats().covariance()
+    }
+
+    /// Returns the Pearson correlation of the pairs of values of the
+    /// queue, between -1 and 1, or `None` if the queue has less than two
+    /// elements, or all the values of one of the sides are equal.
+    ///
+    /// Before the correlation is computed, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push((100, 10));
+    /// queue.push((200, 20));
+    /// queue.push((300, 30));
+    /// assert!(queue.correlation().unwrap() > 0.99);
+    /// queue.push((400, 0));
+    /// assert!(queue.correlation().unwrap() < 0.0);
+    /// ```
+    pub fn correlation(&mut self) -> Option<f64> {
+        self.pair_stats().correlation()
+    }
+
+    fn pair_stats(&mut self) -> PairStats {
+        PairStats::from_pairs(self.iter().map(|&(a, b)| (a.into(), b.into())))
+    }
+}
+
+impl<T: Copy + Into<f64>> SumQueue<T> {
+    /// Returns the (population) covariance between the values of this
+    /// queue and the values of `other` pushed at the same time, within
+    /// `tolerance`, or `None` if there are no such elements.
+    ///
+    /// Before the covariance is computed, it also drops all
+    /// expired elements of both queues.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut latencies = SumQueue::new(Duration::from_secs(60));
+    /// let mut sizes = SumQueue::new(Duration::from_secs(60));
+    /// for (latency, size) in [(10, 100), (20, 210), (30, 290)] {
+    ///     latencies.push(latency);
+    ///     sizes.push(size);
+    /// }
+    /// let tolerance = Duration::from_millis(10);
+    /// assert!(latencies.covariance_with(&mut sizes, tolerance).unwrap() > 0.0);
+    /// ```
+    pub fn covariance_with<U: Copy + Into<f64>>(
+        &mut self,
+        other: &mut SumQueue<U>,
+        tolerance: Duration,
+    ) -> Option<f64> {
+        PairStats::from_pairs(
+            self.zip_by_time(other, tolerance)
+                .map(|(&a, &b)| (a.into(), b.into())),
+        )
+        .covariance()
+    }
+
+    /// Returns the Pearson correlation between the values of this queue
+    /// and the values of `other` pushed at the same time, within `tolerance`,
+    /// or `None` if there are less than two pairs of such elements, or all
+    /// the values of one of the sides are equal.
+    ///
+    /// Before the correlation is computed, it also drops all
+    /// expired elements of both queues.
+    pub fn correlation_with<U: Copy + Into<f64>>(
+        &mut self,
+        other: &mut SumQueue<U>,
+        tolerance: Duration,
+    ) -> Option<f64> {
+        PairStats::from_pairs(
+            self.zip_by_time(other, tolerance)
+                .map(|(&a, &b)| (a.into(), b.into())),
+        )
+        .correlation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn pairs() {
+        let mut queue: SumQueue<(f32, u8)> = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.covariance(), None);
+        queue.push((1.0, 1));
+        assert_eq!(queue.covariance(), Some(0.0));
+        assert_eq!(queue.correlation(), None);
+        queue.push((2.0, 0));
+        queue.push((3.0, 0));
+        assert_eq!(queue.covariance(), Some(-1.0 / 3.0));
+        let correlation = queue.correlation().unwrap();
+        assert!((correlation + 0.866).abs() < 0.001, "{}", correlation);
+    }
+
+    #[test]
+    fn aligned_queues() {
+        let mut a = SumQueue::new(Duration::from_secs(60));
+        let mut b = SumQueue::new(Duration::from_secs(60));
+        let tolerance = Duration::from_millis(20);
+        a.push(1);
+        thread::sleep(Duration::from_millis(50));
+        // pushed at the same time
+        a.push(2);
+        b.push(20);
+        thread::sleep(Duration::from_millis(50));
+        a.push(3);
+        b.push(30);
+        thread::sleep(Duration::from_millis(50));
+        b.push(40);
+        let pairs = a.zip_by_time(&mut b, tolerance).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(&2, &20), (&3, &30)]);
+        assert_eq!(a.covariance_with(&mut b, tolerance), Some(2.5));
+        assert_eq!(a.correlation_with(&mut b, tolerance), Some(1.0));
+        let mut empty: SumQueue<f64> = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(a.correlation_with(&mut empty, tolerance), None);
+    }
+}
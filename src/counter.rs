@@ -0,0 +1,210 @@
+use crate::{now, Instant, SumQueue};
+use std::fmt;
+use std::time::Duration;
+
+/// Counter of the events that happened within a sliding window of time,
+/// eg. the requests received in the last minute.
+///
+/// Only the time of each event is stored: it's backed by a [`SumQueue`]
+/// of `()` values, that take no memory, so each event takes
+/// just the memory of its timestamp.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SlidingCounter;
+///
+/// let mut requests = SlidingCounter::new(Duration::from_secs(60));
+/// requests.incr();
+/// requests.incr();
+/// assert_eq!(requests.incr(), 3);
+/// assert_eq!(requests.count(), 3);
+/// assert_eq!(requests.rate_per_sec(), 0.05);
+/// ```
+pub struct SlidingCounter {
+    queue: SumQueue<()>,
+}
+
+impl SlidingCounter {
+    /// Creates a counter of the events within the last `window` of time.
+    pub const fn new(window: Duration) -> SlidingCounter {
+        SlidingCounter {
+            queue: SumQueue::new(window),
+        }
+    }
+
+    /// Registers a new event, and returns the number of events in the window.
+    pub fn incr(&mut self) -> usize {
+        self.queue.push(())
+    }
+
+    /// Returns the number of events in the window.
+    pub fn count(&mut self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the average number of events per second in the window,
+    /// that is the number of events divided by the length of the window.
+    ///
+    /// Until a whole window of time has passed since the first event,
+    /// the rate is underestimated, see [`RateMeter`] for
+    /// a rate that takes that into account.
+    pub fn rate_per_sec(&mut self) -> f64 {
+        let window = self.queue.max_age().as_secs_f64();
+        if window == 0.0 {
+            return 0.0;
+        }
+        self.count() as f64 / window
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.queue.max_age()
+    }
+
+    /// Forgets all the events.
+    pub fn clear(&mut self) {
+        self.queue.clear()
+    }
+}
+
+impl Default for SlidingCounter {
+    fn default() -> Self {
+        SlidingCounter::new(crate::DEFAULT_MAX_AGE)
+    }
+}
+
+impl fmt::Debug for SlidingCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlidingCounter")
+            .field("window", &self.queue.max_age())
+            .field("count", &self.queue.len_unpruned())
+            .finish()
+    }
+}
+
+/// Meter of the rate of events per second, measured over a sliding window.
+///
+/// Unlike [`SlidingCounter::rate_per_sec()`], the rate is computed
+/// over the time actually covered by the meter, that is the window, or
+/// the time since the meter was created if it's shorter, so the rate
+/// is correct at startup, when the window is only partially filled.
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::RateMeter;
+///
+/// let mut meter = RateMeter::new(Duration::from_secs(60));
+/// thread::sleep(Duration::from_millis(100));
+/// for _ in 0..10 {
+///     meter.mark();
+/// }
+/// // ~100 events per second, not 10 events / 60 seconds
+/// assert!(meter.rate() > 50.0);
+/// ```
+pub struct RateMeter {
+    counter: SlidingCounter,
+    started: Instant,
+}
+
+impl RateMeter {
+    /// Creates a meter of the rate of events within the last `window` of time.
+    pub fn new(window: Duration) -> RateMeter {
+        RateMeter {
+            counter: SlidingCounter::new(window),
+            started: now(),
+        }
+    }
+
+    /// Registers a new event, and returns the number of events in the window.
+    pub fn mark(&mut self) -> usize {
+        self.counter.incr()
+    }
+
+    /// Returns the number of events in the window.
+    pub fn count(&mut self) -> usize {
+        self.counter.count()
+    }
+
+    /// Returns the time covered by the meter, that is the window, or
+    /// the time since the meter was created or reset if it's shorter.
+    pub fn span(&self) -> Duration {
+        now()
+            .saturating_duration_since(self.started)
+            .min(self.counter.window())
+    }
+
+    /// Returns the number of events per second within [`RateMeter::span()`].
+    pub fn rate(&mut self) -> f64 {
+        let span = self.span().as_secs_f64();
+        if span == 0.0 {
+            return 0.0;
+        }
+        self.count() as f64 / span
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.counter.window()
+    }
+
+    /// Forgets all the events, and starts measuring again
+    /// as if the meter was just created.
+    pub fn reset(&mut self) {
+        self.counter.clear();
+        self.started = now();
+    }
+}
+
+impl fmt::Debug for RateMeter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateMeter")
+            .field("counter", &self.counter)
+            .field("span", &self.span())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RateMeter, SlidingCounter};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn count() {
+        let mut counter = SlidingCounter::new(Duration::from_millis(100));
+        assert_eq!(counter.window(), Duration::from_millis(100));
+        assert_eq!(counter.incr(), 1);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(counter.incr(), 2);
+        assert_eq!(counter.rate_per_sec(), 20.0);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(counter.count(), 1);
+        counter.clear();
+        assert_eq!(counter.count(), 0);
+        assert_eq!(SlidingCounter::new(Duration::ZERO).rate_per_sec(), 0.0);
+    }
+
+    #[test]
+    fn rate_meter() {
+        let mut meter = RateMeter::new(Duration::from_millis(200));
+        assert_eq!(meter.window(), Duration::from_millis(200));
+        thread::sleep(Duration::from_millis(50));
+        for _ in 0..5 {
+            meter.mark();
+        }
+        let span = meter.span();
+        assert!(span >= Duration::from_millis(50) && span < Duration::from_millis(200));
+        let rate = meter.rate();
+        assert!(rate > 5.0 / 0.2 && rate <= 100.0, "rate: {}", rate);
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(meter.span(), Duration::from_millis(200));
+        assert_eq!(meter.count(), 0);
+        meter.mark();
+        assert_eq!(meter.rate(), 5.0);
+        meter.reset();
+        assert_eq!(meter.count(), 0);
+        assert!(meter.span() < Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::sliding::Buckets;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Approximate counter of the events of each key within a sliding
+/// window of time, using a count-min sketch per time bucket.
+///
+/// Each sketch is a table of `depth` rows of `width` counters, where each
+/// key is counted in one counter per row chosen by a hash of the key. The
+/// memory used doesn't depend on the number of distinct keys, so it can
+/// count keys with a cardinality too high to keep a queue per key. The counts
+/// are never underestimated, but they can be overestimated because of
+/// hash collisions, less the wider the rows are.
+///
+/// Like in a [`crate::SlidingSum`], the window is split into a fixed number of
+/// buckets, and the sketch of each bucket is dropped when it leaves the window.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::WindowedCountMin;
+///
+/// let mut requests: WindowedCountMin<str> =
+///     WindowedCountMin::new(Duration::from_secs(60), 6, 1024, 4);
+/// for i in 0..10_000 {
+///     requests.incr(&format!("10.0.{}.{}", i % 7, i % 11));
+/// }
+/// requests.add("10.0.0.1", 500);
+/// assert!(requests.estimate("10.0.0.1") >= 500);
+/// assert_eq!(requests.total(), 10_500);
+/// ```
+///
+/// The keys are hashed with a [`RandomState`] by default, so the estimates
+/// can change between runs, use [`WindowedCountMin::with_hasher()`]
+/// to hash them with a fixed [`BuildHasher`] instead.
+pub struct WindowedCountMin<K: ?Sized, S = RandomState> {
+    /// counters of each bucket, row by row
+    buckets: Buckets<Vec<u64>>,
+    width: usize,
+    depth: usize,
+    hasher: S,
+    keys: PhantomData<fn(&K)>,
+}
+
+impl<K: Hash + ?Sized> WindowedCountMin<K> {
+    /// Creates an empty counter of the events within the last `window`
+    /// of time, split in `buckets` buckets (at least one), with sketches of
+    /// `depth` rows (at least one) of `width` counters (at least one).
+    pub fn new(
+        window: Duration,
+        buckets: usize,
+        width: usize,
+        depth: usize,
+    ) -> WindowedCountMin<K> {
+        WindowedCountMin::with_hasher(window, buckets, width, depth, RandomState::new())
+    }
+}
+
+impl<K: Hash + ?Sized, S: BuildHasher> WindowedCountMin<K, S> {
+    /// Creates an empty counter like [`WindowedCountMin::new()`],
+    /// that hashes the keys with `hasher`.
+    pub fn with_hasher(
+        window: Duration,
+        buckets: usize,
+        width: usize,
+        depth: usize,
+        hasher: S,
+    ) -> WindowedCountMin<K, S> {
+        WindowedCountMin {
+            buckets: Buckets::new(window, buckets),
+            width: width.max(1),
+            depth: depth.max(1),
+            hasher,
+            keys: PhantomData,
+        }
+    }
+
+    /// Returns the number of counters of each row of the sketches.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows of the sketches.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of buckets the window is split in.
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the index of the counter of `key` in each row.
+    fn indexes(&self, key: &K) -> impl Iterator<Item = usize> {
+        let hash = self.hasher.hash_one(key);
+        // double hashing: the index of each row is h1 + row * h2
+        let (h1, h2) = (hash as u32 as u64, (hash >> 32) | 1);
+        let width = self.width;
+        (0..self.depth as u64).map(move |row| {
+            row as usize * width + (h1.wrapping_add(row.wrapping_mul(h2)) % width as u64) as usize
+        })
+    }
+
+    /// Registers `count` events of `key` in the current bucket.
+    pub fn add(&mut self, key: &K, count: u64) {
+        let indexes = self.indexes(key).collect::<Vec<_>>();
+        let size = self.width * self.depth;
+        let counters = self.buckets.current(|| vec![0; size]);
+        for i in indexes {
+            counters[i] += count;
+        }
+    }
+
+    /// Registers an event of `key` in the current bucket.
+    pub fn incr(&mut self, key: &K) {
+        self.add(key, 1)
+    }
+
+    /// Returns the estimated number of events of `key`
+    /// within the window, never lower than the actual number.
+    pub fn estimate(&mut self, key: &K) -> u64 {
+        let indexes = self.indexes(key).collect::<Vec<_>>();
+        let buckets = self.buckets.iter().collect::<Vec<_>>();
+        indexes
+            .into_iter()
+            .map(|i| buckets.iter().map(|counters| counters[i]).sum::<u64>())
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of events of all the keys within the window.
+    pub fn total(&mut self) -> u64 {
+        let width = self.width;
+        self.buckets
+            .iter()
+            .map(|counters| counters[..width].iter().sum::<u64>())
+            .sum()
+    }
+
+    /// Drops all the events.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl<K: ?Sized, S> fmt::Debug for WindowedCountMin<K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowedCountMin")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .field("width", &self.width)
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WindowedCountMin;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn estimate() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let mut sketch = WindowedCountMin::with_hasher(Duration::from_secs(60), 4, 256, 4, hasher);
+        assert_eq!(
+            (sketch.width(), sketch.depth(), sketch.buckets()),
+            (256, 4, 4)
+        );
+        assert_eq!(sketch.estimate(&0), 0);
+        // a few heavy keys between many light ones
+        for i in 0..1000u32 {
+            sketch.incr(&i);
+            if i % 100 == 0 {
+                sketch.add(&1_000_000, 10);
+            }
+        }
+        assert_eq!(sketch.total(), 1100);
+        assert!(sketch.estimate(&1_000_000) >= 100);
+        assert!(sketch.estimate(&1_000_000) < 120);
+        for i in 0..1000u32 {
+            assert!(sketch.estimate(&i) >= 1);
+        }
+        sketch.clear();
+        assert_eq!(sketch.total(), 0);
+    }
+
+    #[test]
+    fn expire_buckets() {
+        let mut sketch: WindowedCountMin<str> =
+            WindowedCountMin::new(Duration::from_millis(100), 2, 1024, 2);
+        sketch.incr("a");
+        thread::sleep(Duration::from_millis(60));
+        sketch.incr("a");
+        sketch.incr("b");
+        assert_eq!(sketch.estimate("a"), 2);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(sketch.estimate("a"), 1);
+        assert_eq!(sketch.total(), 2);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{now, Instant};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Queue of elements scheduled to be ready at a given time (the "deadline"),
+/// useful as a lightweight timer wheel for scheduled work.
+///
+/// Unlike a [`crate::SumQueue`], where the elements are ordered by the time
+/// they were pushed, the elements are ordered by their deadline, and only
+/// the elements whose deadline has passed can be popped. Elements
+/// with the same deadline are popped in the same order they were pushed.
+///
+/// ```
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+/// use sum_queue::DeadlineQueue;
+///
+/// let mut timers = DeadlineQueue::new();
+/// let now = Instant::now();
+/// timers.push_with_deadline("later", now + Duration::from_millis(200));
+/// timers.push_with_deadline("soon", now + Duration::from_millis(100));
+/// assert_eq!(timers.pop_ready(), None);
+/// assert_eq!(timers.next_deadline(), Some(now + Duration::from_millis(100)));
+///
+/// thread::sleep(Duration::from_millis(100));
+/// assert_eq!(timers.pop_ready(), Some("soon"));
+/// assert_eq!(timers.pop_ready(), None);
+/// ```
+pub struct DeadlineQueue<T> {
+    /// the elements by deadline and sequence number, so
+    /// elements with the same deadline keep the push order
+    elements: BTreeMap<(Instant, u64), T>,
+    seq: u64,
+}
+
+impl<T> DeadlineQueue<T> {
+    /// Creates an empty `DeadlineQueue`.
+    pub fn new() -> DeadlineQueue<T> {
+        DeadlineQueue {
+            elements: BTreeMap::new(),
+            seq: 0,
+        }
+    }
+
+    /// Pushes an item that will be ready at the time `at`,
+    /// and returns the size of the queue.
+    pub fn push_with_deadline(&mut self, item: T, at: Instant) -> usize {
+        self.elements.insert((at, self.seq), item);
+        self.seq = self.seq.wrapping_add(1);
+        self.elements.len()
+    }
+
+    /// Pushes an item that will be ready after `delay`,
+    /// and returns the size of the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::DeadlineQueue;
+    /// let mut retries = DeadlineQueue::new();
+    /// retries.push_after("job-1", Duration::ZERO);
+    /// retries.push_after("job-2", Duration::from_secs(60));
+    /// assert_eq!(retries.pop_ready(), Some("job-1"));
+    /// assert_eq!(retries.pop_ready(), None);
+    /// assert_eq!(retries.len(), 1);
+    /// ```
+    pub fn push_after(&mut self, item: T, delay: Duration) -> usize {
+        self.push_with_deadline(item, now() + delay)
+    }
+
+    /// Removes the item with the earliest deadline and returns it if
+    /// the deadline has passed, otherwise returns `None`.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use sum_queue::DeadlineQueue;
+    /// let mut timers = DeadlineQueue::new();
+    /// let past = Instant::now() - Duration::from_millis(10);
+    /// timers.push_with_deadline(2, past);
+    /// timers.push_with_deadline(1, past - Duration::from_millis(10));
+    /// let mut ready = Vec::new();
+    /// while let Some(item) = timers.pop_ready() {
+    ///     ready.push(item);
+    /// }
+    /// assert_eq!(ready, vec![1, 2]);
+    /// ```
+    pub fn pop_ready(&mut self) -> Option<T> {
+        let now = now();
+        let entry = self.elements.first_entry()?;
+        if entry.key().0 > now {
+            return None;
+        }
+        Some(entry.remove())
+    }
+
+    /// Returns the earliest deadline, or `None` if the queue is empty.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.elements.keys().next().map(|(at, _)| *at)
+    }
+
+    /// Returns the time until the earliest deadline, zero if it
+    /// already passed, or `None` if the queue is empty.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::DeadlineQueue;
+    /// let mut timers = DeadlineQueue::new();
+    /// timers.push_after(1, Duration::from_millis(50));
+    /// timers.push_after(2, Duration::from_millis(100));
+    /// while let Some(wait) = timers.next_deadline_in() {
+    ///     thread::sleep(wait);
+    ///     if let Some(item) = timers.pop_ready() {
+    ///         println!("{}", item);
+    ///     }
+    /// }
+    /// ```
+    pub fn next_deadline_in(&self) -> Option<Duration> {
+        self.next_deadline()
+            .map(|at| at.saturating_duration_since(now()))
+    }
+
+    /// Returns the number of elements in the queue, ready or not.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Checks if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Drops all the elements.
+    pub fn clear(&mut self) {
+        self.elements.clear()
+    }
+}
+
+impl<T> Default for DeadlineQueue<T> {
+    fn default() -> Self {
+        DeadlineQueue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DeadlineQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = now();
+        let elements = self
+            .elements
+            .iter()
+            .map(|((at, _), value)| (value, at.saturating_duration_since(now)))
+            .collect::<Vec<_>>();
+        f.debug_struct("DeadlineQueue")
+            .field("len", &self.elements.len())
+            .field("elements", &elements)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DeadlineQueue;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn pop_ready() {
+        let mut queue = DeadlineQueue::new();
+        assert_eq!(queue.pop_ready(), None);
+        assert_eq!(queue.next_deadline_in(), None);
+        let now = Instant::now();
+        queue.push_with_deadline('c', now + Duration::from_millis(100));
+        queue.push_with_deadline('a', now);
+        queue.push_with_deadline('b', now);
+        assert_eq!(queue.push_after('d', Duration::from_secs(60)), 4);
+        assert_eq!(queue.next_deadline(), Some(now));
+        assert_eq!(queue.next_deadline_in(), Some(Duration::ZERO));
+        assert_eq!(queue.pop_ready(), Some('a'));
+        assert_eq!(queue.pop_ready(), Some('b'));
+        assert_eq!(queue.pop_ready(), None);
+        assert!(queue.next_deadline_in().unwrap() > Duration::ZERO);
+        thread::sleep(Duration::from_millis(120));
+        assert_eq!(queue.pop_ready(), Some('c'));
+        assert_eq!(queue.pop_ready(), None);
+        assert_eq!(queue.len(), 1);
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
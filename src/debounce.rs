@@ -0,0 +1,270 @@
+use crate::{now, Instant, SumQueue};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Time of the last event of each key, forgetting the keys
+/// without events within the window.
+struct KeyedWindow<K> {
+    last: BTreeMap<K, Instant>,
+    /// keys in the order they have to be checked for expiration,
+    /// each key is in the queue once
+    keys: SumQueue<K>,
+    window: Duration,
+}
+
+impl<K: Ord + Clone> KeyedWindow<K> {
+    fn new(window: Duration) -> KeyedWindow<K> {
+        KeyedWindow {
+            last: BTreeMap::new(),
+            keys: SumQueue::new(Duration::MAX),
+            window,
+        }
+    }
+
+    /// Forgets the keys without events within the window.
+    fn prune(&mut self, now: Instant) {
+        while let Some((_, age)) = self.keys.peek_with_age() {
+            if age < self.window {
+                break;
+            }
+            let key = self.keys.pop().unwrap();
+            match self.last.get(&key) {
+                Some(&last) if now - last < self.window => {
+                    // the key had events after it was queued, check it later again
+                    self.keys.push(key);
+                }
+                _ => {
+                    self.last.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Checks if the key had no events within the window.
+    fn is_quiet(&mut self, key: &K, now: Instant) -> bool {
+        self.prune(now);
+        self.last
+            .get(key)
+            .is_none_or(|&last| now - last >= self.window)
+    }
+
+    /// Registers an event of the key.
+    fn record(&mut self, key: K, now: Instant) {
+        if self.last.insert(key.clone(), now).is_none() {
+            self.keys.push(key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.last.clear();
+        self.keys.clear();
+    }
+}
+
+/// Debouncer of events by key: an event "fires" only if there was no
+/// other event of the same key within the window, so a burst of events
+/// fires only once, at its beginning, no matter how long it lasts.
+///
+/// Useful to deduplicate alerts, or to ignore repeated UI events.
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::Debouncer;
+///
+/// let mut alerts = Debouncer::new(Duration::from_millis(100));
+/// assert!(alerts.should_fire("disk-full"));
+/// assert!(!alerts.should_fire("disk-full"));
+/// assert!(alerts.should_fire("high-cpu"));
+///
+/// thread::sleep(Duration::from_millis(150));
+/// assert!(alerts.should_fire("disk-full"));
+/// ```
+pub struct Debouncer<K> {
+    events: KeyedWindow<K>,
+}
+
+impl<K: Ord + Clone> Debouncer<K> {
+    /// Creates a debouncer where an event fires only if there
+    /// was no other event of its key within the last `window` of time.
+    pub fn new(window: Duration) -> Debouncer<K> {
+        Debouncer {
+            events: KeyedWindow::new(window),
+        }
+    }
+
+    /// Registers an event of `key`, and returns `true` if there was
+    /// no other event of the key within the window, fired or not.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::Debouncer;
+    /// let mut debouncer = Debouncer::new(Duration::from_millis(100));
+    /// assert!(debouncer.should_fire(1));
+    /// for _ in 0..3 {
+    ///     thread::sleep(Duration::from_millis(60));
+    ///     // each event extends the burst
+    ///     assert!(!debouncer.should_fire(1));
+    /// }
+    /// ```
+    pub fn should_fire(&mut self, key: K) -> bool {
+        let now = now();
+        let fire = self.events.is_quiet(&key, now);
+        self.events.record(key, now);
+        fire
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.events.window
+    }
+
+    /// Returns the number of keys with events within the window.
+    pub fn len(&mut self) -> usize {
+        self.events.prune(now());
+        self.events.last.len()
+    }
+
+    /// Checks if there are no keys with events within the window.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets all the events.
+    pub fn clear(&mut self) {
+        self.events.clear()
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for Debouncer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debouncer")
+            .field("window", &self.events.window)
+            .field("keys", &self.events.last.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Throttler of events by key: an event "fires" only if no other event
+/// of the same key fired within the window, so the events of each key
+/// fire at most once per window, no matter how frequent they are.
+///
+/// Unlike the [`Debouncer`], the events that don't fire are not taken into
+/// account, so a long burst of events fires once every window.
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::Throttler;
+///
+/// let mut throttler = Throttler::new(Duration::from_millis(100));
+/// let mut fired = 0;
+/// for _ in 0..10 {
+///     if throttler.should_fire("scroll") {
+///         fired += 1;
+///     }
+///     thread::sleep(Duration::from_millis(25));
+/// }
+/// assert!(fired >= 2 && fired < 10);
+/// ```
+pub struct Throttler<K> {
+    fired: KeyedWindow<K>,
+}
+
+impl<K: Ord + Clone> Throttler<K> {
+    /// Creates a throttler where the events of each key fire
+    /// at most once within any `window` of time.
+    pub fn new(window: Duration) -> Throttler<K> {
+        Throttler {
+            fired: KeyedWindow::new(window),
+        }
+    }
+
+    /// Registers an event of `key`, and returns `true` if no
+    /// other event of the key fired within the window.
+    pub fn should_fire(&mut self, key: K) -> bool {
+        let now = now();
+        let fire = self.fired.is_quiet(&key, now);
+        if fire {
+            self.fired.record(key, now);
+        }
+        fire
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.fired.window
+    }
+
+    /// Returns the number of keys with events fired within the window.
+    pub fn len(&mut self) -> usize {
+        self.fired.prune(now());
+        self.fired.last.len()
+    }
+
+    /// Checks if there are no keys with events fired within the window.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets all the events.
+    pub fn clear(&mut self) {
+        self.fired.clear()
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for Throttler<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttler")
+            .field("window", &self.fired.window)
+            .field("keys", &self.fired.last.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Debouncer, Throttler};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn debounce() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+        assert_eq!(debouncer.window(), Duration::from_millis(100));
+        assert!(debouncer.is_empty());
+        assert!(debouncer.should_fire('a'));
+        assert!(debouncer.should_fire('b'));
+        thread::sleep(Duration::from_millis(60));
+        assert!(!debouncer.should_fire('a'));
+        thread::sleep(Duration::from_millis(60));
+        // 'a' was extended by the second event
+        assert!(!debouncer.should_fire('a'));
+        assert_eq!(debouncer.len(), 1);
+        thread::sleep(Duration::from_millis(120));
+        assert!(debouncer.is_empty());
+        assert!(debouncer.should_fire('a'));
+        debouncer.clear();
+        assert!(debouncer.should_fire('a'));
+    }
+
+    #[test]
+    fn throttle() {
+        let mut throttler = Throttler::new(Duration::from_millis(100));
+        assert!(throttler.should_fire(1));
+        thread::sleep(Duration::from_millis(60));
+        assert!(!throttler.should_fire(1));
+        assert!(throttler.should_fire(2));
+        thread::sleep(Duration::from_millis(60));
+        // the event that didn't fire is not taken into account
+        assert!(throttler.should_fire(1));
+        assert_eq!(throttler.len(), 2);
+        thread::sleep(Duration::from_millis(120));
+        assert!(throttler.is_empty());
+        throttler.should_fire(3);
+        throttler.clear();
+        assert!(throttler.should_fire(3));
+    }
+}
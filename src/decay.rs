@@ -0,0 +1,100 @@
+use crate::{Instant, QueueElement, SumQueue};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Function that computes the value of an element from its stored value and its age.
+pub(crate) type DecayFn<T> = Arc<dyn Fn(&T, Duration) -> T + Send + Sync>;
+
+impl<T> SumQueue<T> {
+    /// Sets a function that computes the value of each element from its
+    /// stored value and its age, eg. with a linear or exponential decay,
+    /// so the older elements contribute less to the stats. The stored
+    /// values are not modified.
+    ///
+    /// The decayed values are used by [`SumQueue::stats()`] and the other
+    /// methods that compute stats, and are returned by [`SumQueue::iter_decayed()`],
+    /// while the other methods, like [`SumQueue::iter()`], return the stored values.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// // the values lose a 10% of their value each 100 milliseconds
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_decay(|&value: &u64, age| {
+    ///     value * 10u64.saturating_sub(age.as_millis() as u64 / 100) / 10
+    /// });
+    /// queue.push(1000);
+    /// thread::sleep(Duration::from_millis(250));
+    /// queue.push(1000);
+    /// assert_eq!(queue.stats().sum, Some(1800));
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1000, &1000]);
+    /// ```
+    pub fn with_decay<F>(mut self, decay: F) -> SumQueue<T>
+    where
+        F: Fn(&T, Duration) -> T + Send + Sync + 'static,
+    {
+        self.decay = Some(Arc::new(decay));
+        self
+    }
+
+    /// Returns an iterator over the values in the queue with the decay set
+    /// with [`SumQueue::with_decay()`] applied, in the same order they
+    /// were pushed, or over copies of the values if there is no decay.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    /// All the ages are computed against the same instant, taken
+    /// when this method is called.
+    pub fn iter_decayed(&mut self) -> impl Iterator<Item = T> + '_
+    where
+        T: Clone,
+    {
+        let now = self.now();
+        self.clear_oldest(now);
+        let queue = &*self;
+        queue.queue.iter().map(move |el| queue.decayed(el, now))
+    }
+
+    /// Returns the value of `el` at the instant `now`.
+    pub(crate) fn decayed(&self, el: &QueueElement<T>, now: Instant) -> T
+    where
+        T: Clone,
+    {
+        match &self.decay {
+            Some(decay) => decay(&el.value, self.epoch.age(el.time, now)),
+            None => el.value.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn decay() {
+        let mut queue = SumQueue::new(Duration::from_secs(60)).with_decay(|&value: &i32, age| {
+            if age > Duration::from_millis(100) {
+                0
+            } else {
+                value
+            }
+        });
+        queue.push(5);
+        queue.push(-3);
+        assert_eq!(queue.iter_decayed().collect::<Vec<_>>(), vec![5, -3]);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(2);
+        assert_eq!(queue.iter_decayed().collect::<Vec<_>>(), vec![0, 0, 2]);
+        let stats = queue.stats();
+        assert_eq!(
+            (stats.min, stats.max, stats.sum),
+            (Some(0), Some(2), Some(2))
+        );
+        assert_eq!(stats.len, 3);
+        let mut plain: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        plain.push(7);
+        assert_eq!(plain.iter_decayed().collect::<Vec<_>>(), vec![7]);
+    }
+}
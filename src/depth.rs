@@ -0,0 +1,154 @@
+use crate::sliding::Buckets;
+use crate::SumQueue;
+use std::fmt;
+use std::time::Duration;
+
+/// Stats of the lengths a queue had within a window of time,
+/// see [`SumQueue::with_depth_history()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthStats {
+    /// min length of the queue
+    pub min: usize,
+    /// max length of the queue
+    pub max: usize,
+    /// average of the lengths sampled
+    pub avg: f64,
+    /// number of lengths sampled
+    pub samples: usize,
+}
+
+/// Aggregate of the lengths sampled within a bucket.
+#[derive(Clone)]
+struct Depths {
+    min: usize,
+    max: usize,
+    sum: usize,
+    samples: usize,
+}
+
+/// Windowed record of the lengths of a queue.
+#[derive(Clone)]
+pub(crate) struct DepthHistory {
+    buckets: Buckets<Depths>,
+}
+
+impl DepthHistory {
+    pub(crate) fn new(window: Duration, buckets: usize) -> DepthHistory {
+        DepthHistory {
+            buckets: Buckets::new(window, buckets),
+        }
+    }
+
+    /// Returns an empty history with the same window.
+    pub(crate) fn empty_like(&self) -> DepthHistory {
+        let mut history = self.clone();
+        history.buckets.clear();
+        history
+    }
+
+    fn record(&mut self, len: usize) {
+        let depths = self.buckets.current(|| Depths {
+            min: len,
+            max: len,
+            sum: 0,
+            samples: 0,
+        });
+        depths.min = depths.min.min(len);
+        depths.max = depths.max.max(len);
+        depths.sum += len;
+        depths.samples += 1;
+    }
+
+    fn stats(&mut self) -> Option<DepthStats> {
+        let mut buckets = self.buckets.iter();
+        let first = buckets.next()?.clone();
+        let depths = buckets.fold(first, |acc, depths| Depths {
+            min: acc.min.min(depths.min),
+            max: acc.max.max(depths.max),
+            sum: acc.sum + depths.sum,
+            samples: acc.samples + depths.samples,
+        });
+        Some(DepthStats {
+            min: depths.min,
+            max: depths.max,
+            avg: depths.sum as f64 / depths.samples as f64,
+            samples: depths.samples,
+        })
+    }
+}
+
+impl fmt::Debug for DepthHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DepthHistory")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .finish()
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Starts to record the length of the queue after each push and
+    /// pop, keeping the lengths of the last `window` of time split in
+    /// `buckets` buckets (at least one), like a [`crate::SlidingSum`],
+    /// so [`SumQueue::depth_stats()`] can tell whether the consumers
+    /// of the queue keep up with the producers.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60))
+    ///     .with_depth_history(Duration::from_secs(300), 10);
+    /// queue.push(1);
+    /// queue.push(2);
+    /// queue.pop();
+    /// let depth = queue.depth_stats().unwrap();
+    /// assert_eq!((depth.min, depth.max, depth.samples), (1, 2, 3));
+    /// assert_eq!(depth.avg, 4.0 / 3.0);
+    /// ```
+    pub fn with_depth_history(mut self, window: Duration, buckets: usize) -> SumQueue<T> {
+        self.depth = Some(DepthHistory::new(window, buckets));
+        self
+    }
+
+    /// Returns the stats of the lengths recorded within the window
+    /// set with [`SumQueue::with_depth_history()`], or `None` if the
+    /// lengths are not recorded or there are no lengths within the window.
+    pub fn depth_stats(&mut self) -> Option<DepthStats> {
+        self.depth.as_mut()?.stats()
+    }
+
+    /// Records the length of the queue, if the lengths are recorded.
+    pub(crate) fn record_depth(&mut self) {
+        if let Some(depth) = self.depth.as_mut() {
+            depth.record(self.queue.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn depth_stats() {
+        let mut queue = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        assert_eq!(queue.depth_stats(), None);
+        let mut queue = queue.with_depth_history(Duration::from_millis(100), 2);
+        assert_eq!(queue.depth_stats(), None);
+        queue.push(2);
+        queue.push(3);
+        queue.pop_n(2);
+        let depth = queue.depth_stats().unwrap();
+        assert_eq!((depth.min, depth.max, depth.samples), (1, 3, 3));
+        assert_eq!(depth.avg, 2.0);
+        thread::sleep(Duration::from_millis(150));
+        queue.pop();
+        let depth = queue.depth_stats().unwrap();
+        assert_eq!((depth.min, depth.max, depth.samples), (0, 0, 1));
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.depth_stats(), None);
+    }
+}
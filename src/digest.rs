@@ -0,0 +1,315 @@
+use crate::sliding::Buckets;
+use std::f64::consts::PI;
+use std::fmt;
+use std::time::Duration;
+
+/// Default compression of the digests, see [`WindowedDigest::with_compression()`].
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// Cluster of close values, summarized by their mean and their number.
+#[derive(Clone, Copy, Debug)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Merging t-digest: a sketch of a distribution of values made of a bounded
+/// number of centroids, small in the tails and bigger near the median,
+/// so the quantiles are estimated with good precision, specially the
+/// extreme ones, eg. the 99th percentile.
+#[derive(Clone)]
+struct TDigest {
+    /// centroids sorted by mean, with the values pushed since
+    /// the last compression appended at the end
+    centroids: Vec<Centroid>,
+    /// number of sorted centroids at the beginning of `centroids`
+    compressed: usize,
+    compression: f64,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    fn new(compression: f64) -> TDigest {
+        TDigest {
+            centroids: Vec::new(),
+            compressed: 0,
+            compression,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn push(&mut self, centroid: Centroid) {
+        self.min = self.min.min(centroid.mean);
+        self.max = self.max.max(centroid.mean);
+        self.centroids.push(centroid);
+        if self.centroids.len() - self.compressed > 5 * self.compression as usize {
+            self.compress();
+        }
+    }
+
+    /// Adds the values of `other` to the digest.
+    fn merge(&mut self, other: &TDigest) {
+        for &centroid in &other.centroids {
+            self.push(centroid);
+        }
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    fn count(&self) -> f64 {
+        self.centroids.iter().map(|c| c.weight).sum()
+    }
+
+    /// Sorts the centroids, and merges the adjacent ones while they
+    /// span less than one unit of the scale function `k`, so the
+    /// centroids are smaller in the tails.
+    fn compress(&mut self) {
+        if self.compressed == self.centroids.len() {
+            return;
+        }
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total = centroids.iter().map(|c| c.weight).sum::<f64>();
+        let k = |q: f64| self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin();
+        let mut merged = Vec::with_capacity(self.compression as usize);
+        let mut current = centroids[0];
+        let mut weight_before = 0.0;
+        let mut k_before = k(0.0);
+        for &next in &centroids[1..] {
+            let weight = current.weight + next.weight;
+            if k((weight_before + weight) / total) - k_before <= 1.0 {
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                k_before = k(weight_before / total);
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.compressed = merged.len();
+        self.centroids = merged;
+    }
+
+    /// Estimates the value at the quantile `q`, interpolating
+    /// between the means of the centroids around it.
+    fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress();
+        let first = self.centroids.first()?;
+        let last = self.centroids.last()?;
+        let total = self.count();
+        let target = q.clamp(0.0, 1.0) * total;
+        if target <= first.weight / 2.0 {
+            return Some(interpolate(
+                self.min,
+                first.mean,
+                target / (first.weight / 2.0),
+            ));
+        }
+        let mut center = first.weight / 2.0;
+        for pair in self.centroids.windows(2) {
+            let next_center = center + (pair[0].weight + pair[1].weight) / 2.0;
+            if target <= next_center {
+                let fraction = (target - center) / (next_center - center);
+                return Some(interpolate(pair[0].mean, pair[1].mean, fraction));
+            }
+            center = next_center;
+        }
+        let fraction = (target - center) / (total - center);
+        Some(interpolate(last.mean, self.max, fraction))
+    }
+}
+
+fn interpolate(from: f64, to: f64, fraction: f64) -> f64 {
+    if fraction.is_nan() {
+        return from;
+    }
+    from + (to - from) * fraction
+}
+
+/// Sliding window of values that answers quantile queries, eg. the
+/// 99th percentile of the latencies of the last minute, with bounded memory.
+///
+/// The window is split into a fixed number of buckets, like in a
+/// [`crate::SlidingSum`], each one with a t-digest sketch of its values,
+/// and the sketches within the window are merged to answer the queries. So
+/// the memory used doesn't depend on the number of values, and the quantiles
+/// are estimated with a small error, lower for the extreme quantiles.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::WindowedDigest;
+///
+/// let mut latencies = WindowedDigest::new(Duration::from_secs(60), 6);
+/// for latency in 1..=10_000 {
+///     latencies.push(latency as f64);
+/// }
+/// assert_eq!(latencies.len(), 10_000);
+/// let p99 = latencies.quantile(0.99).unwrap();
+/// assert!((p99 - 9900.0).abs() < 50.0);
+/// ```
+pub struct WindowedDigest {
+    buckets: Buckets<TDigest>,
+    compression: f64,
+}
+
+impl WindowedDigest {
+    /// Creates an empty window of the values pushed within the
+    /// last `window` of time, split in `buckets` buckets (at least one).
+    pub fn new(window: Duration, buckets: usize) -> WindowedDigest {
+        WindowedDigest {
+            buckets: Buckets::new(window, buckets),
+            compression: DEFAULT_COMPRESSION,
+        }
+    }
+
+    /// Sets the compression of the digests, that is roughly the max
+    /// number of centroids of each one: higher values estimate the
+    /// quantiles more accurately, but take more memory and time.
+    /// It's [`DEFAULT_COMPRESSION`] by default.
+    pub fn with_compression(mut self, compression: f64) -> WindowedDigest {
+        self.compression = compression.max(1.0);
+        self
+    }
+
+    /// Returns the number of buckets the window is split in.
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the duration covered by each bucket.
+    pub fn bucket_width(&self) -> Duration {
+        self.buckets.width()
+    }
+
+    /// Adds a value to the current bucket. NaN values are ignored.
+    pub fn push(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let compression = self.compression;
+        self.buckets
+            .current(|| TDigest::new(compression))
+            .push(Centroid {
+                mean: value,
+                weight: 1.0,
+            });
+    }
+
+    /// Merges the digests of all the buckets within the window.
+    fn merged(&mut self) -> TDigest {
+        let mut merged = TDigest::new(self.compression);
+        for digest in self.buckets.iter() {
+            merged.merge(digest);
+        }
+        merged
+    }
+
+    /// Estimates the value at the quantile `q` (between 0 and 1) of the
+    /// values within the window, or `None` if there is no value.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::WindowedDigest;
+    /// let mut window = WindowedDigest::new(Duration::from_secs(60), 1);
+    /// assert_eq!(window.quantile(0.5), None);
+    /// window.push(1.0);
+    /// window.push(2.0);
+    /// window.push(3.0);
+    /// assert_eq!(window.quantile(0.0), Some(1.0));
+    /// assert_eq!(window.quantile(0.5), Some(2.0));
+    /// assert_eq!(window.quantile(1.0), Some(3.0));
+    /// ```
+    pub fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.merged().quantile(q)
+    }
+
+    /// Estimates the values at each one of the quantiles `qs`, merging
+    /// the buckets only once, or returns `None` if there is no value.
+    pub fn quantiles(&mut self, qs: &[f64]) -> Option<Vec<f64>> {
+        let mut merged = self.merged();
+        qs.iter().map(|&q| merged.quantile(q)).collect()
+    }
+
+    /// Returns the number of values within the window.
+    pub fn len(&mut self) -> usize {
+        self.buckets
+            .iter()
+            .map(|digest| digest.count())
+            .sum::<f64>() as usize
+    }
+
+    /// Checks if there is no value within the window.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl fmt::Debug for WindowedDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowedDigest")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .field("compression", &self.compression)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WindowedDigest;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn quantiles() {
+        let mut window = WindowedDigest::new(Duration::from_secs(60), 4);
+        assert!(window.is_empty());
+        assert_eq!(window.quantiles(&[0.5]), None);
+        // values pushed in random order
+        for i in 0..100_000u64 {
+            window.push((i * 7919 % 100_000) as f64);
+        }
+        window.push(f64::NAN);
+        assert_eq!(window.len(), 100_000);
+        let quantiles = window.quantiles(&[0.0, 0.01, 0.5, 0.99, 1.0]).unwrap();
+        assert_eq!(quantiles[0], 0.0);
+        assert!((quantiles[1] - 1_000.0).abs() < 100.0, "{:?}", quantiles);
+        assert!((quantiles[2] - 50_000.0).abs() < 500.0, "{:?}", quantiles);
+        assert!((quantiles[3] - 99_000.0).abs() < 100.0, "{:?}", quantiles);
+        assert_eq!(quantiles[4], 99_999.0);
+        // the digests are bounded
+        let mut merged = window.merged();
+        merged.compress();
+        assert!(merged.centroids.len() <= 100, "{}", merged.centroids.len());
+        let mut small = WindowedDigest::new(Duration::from_secs(60), 1).with_compression(20.0);
+        for i in 0..10_000 {
+            small.push(i as f64);
+        }
+        let mut merged = small.merged();
+        merged.compress();
+        assert!(merged.centroids.len() <= 20, "{}", merged.centroids.len());
+    }
+
+    #[test]
+    fn expire_buckets() {
+        let mut window = WindowedDigest::new(Duration::from_millis(100), 2);
+        assert_eq!(window.buckets(), 2);
+        assert_eq!(window.bucket_width(), Duration::from_millis(50));
+        window.push(100.0);
+        thread::sleep(Duration::from_millis(160));
+        window.push(1.0);
+        assert_eq!(window.len(), 1);
+        assert_eq!(window.quantile(0.9), Some(1.0));
+        window.clear();
+        assert_eq!(window.quantile(0.9), None);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::sliding::Buckets;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Approximate counter of the distinct values seen within a sliding window
+/// of time, eg. the unique users of the last hour, using a HyperLogLog
+/// sketch per time bucket.
+///
+/// Each sketch takes `2^precision` bytes no matter how many values are
+/// counted, with a typical relative error of `1.04 / sqrt(2^precision)`,
+/// eg. 1.6% with the default precision of 12 (4 KiB per bucket).
+///
+/// Like in a [`crate::SlidingSum`], the window is split into a fixed number of
+/// buckets, and the sketch of each bucket is dropped when it leaves the window,
+/// the sketches within the window are merged to count the values.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::WindowedDistinct;
+///
+/// let mut users = WindowedDistinct::new(Duration::from_secs(3600), 60);
+/// for i in 0..100_000 {
+///     users.insert(&(i % 20_000));
+/// }
+/// let unique = users.count();
+/// assert!(unique > 19_000 && unique < 21_000);
+/// ```
+///
+/// The values are hashed with a [`RandomState`] by default, so the estimates
+/// can change slightly between runs, use [`WindowedDistinct::with_hasher()`]
+/// to hash them with a fixed [`BuildHasher`] instead.
+pub struct WindowedDistinct<T: ?Sized, S = RandomState> {
+    /// registers of each bucket
+    buckets: Buckets<Vec<u8>>,
+    precision: u8,
+    hasher: S,
+    values: PhantomData<fn(&T)>,
+}
+
+/// Default precision of the sketches, see [`WindowedDistinct::with_precision()`].
+pub const DEFAULT_PRECISION: u8 = 12;
+
+impl<T: Hash + ?Sized> WindowedDistinct<T> {
+    /// Creates an empty counter of the distinct values within the last `window`
+    /// of time, split in `buckets` buckets (at least one).
+    pub fn new(window: Duration, buckets: usize) -> WindowedDistinct<T> {
+        WindowedDistinct::with_hasher(window, buckets, RandomState::new())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> WindowedDistinct<T, S> {
+    /// Creates an empty counter like [`WindowedDistinct::new()`],
+    /// that hashes the values with `hasher`.
+    pub fn with_hasher(window: Duration, buckets: usize, hasher: S) -> WindowedDistinct<T, S> {
+        WindowedDistinct {
+            buckets: Buckets::new(window, buckets),
+            precision: DEFAULT_PRECISION,
+            hasher,
+            values: PhantomData,
+        }
+    }
+
+    /// Sets the precision of the sketches, between 4 and 16, each sketch
+    /// takes `2^precision` bytes. It drops the values already counted.
+    pub fn with_precision(mut self, precision: u8) -> WindowedDistinct<T, S> {
+        self.precision = precision.clamp(4, 16);
+        self.buckets.clear();
+        self
+    }
+
+    /// Returns the precision of the sketches.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Returns the number of buckets the window is split in.
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Counts a value in the current bucket.
+    pub fn insert(&mut self, item: &T) {
+        let hash = self.hasher.hash_one(item);
+        let p = self.precision;
+        let index = (hash >> (64 - p)) as usize;
+        // position of the first 1 bit after the index bits,
+        // the guard bit caps the rank for hashes full of zeros
+        let rank = ((hash << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+        let registers = self.buckets.current(|| vec![0; 1 << p]);
+        registers[index] = registers[index].max(rank);
+    }
+
+    /// Returns the estimated number of distinct values within the window.
+    pub fn count(&mut self) -> u64 {
+        let m = 1usize << self.precision;
+        let mut merged = vec![0u8; m];
+        for registers in self.buckets.iter() {
+            for (max, &rank) in merged.iter_mut().zip(registers) {
+                *max = (*max).max(rank);
+            }
+        }
+        let m = m as f64;
+        let alpha = match m as usize {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum = merged
+            .iter()
+            .map(|&rank| 2f64.powi(-(rank as i32)))
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+        let zeros = merged.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // linear counting is more accurate for small cardinalities
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl<T: ?Sized, S> fmt::Debug for WindowedDistinct<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowedDistinct")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .field("precision", &self.precision)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WindowedDistinct;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn count() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let mut distinct = WindowedDistinct::with_hasher(Duration::from_secs(60), 4, hasher);
+        assert_eq!(distinct.precision(), 12);
+        assert_eq!(distinct.count(), 0);
+        for i in 0..100u32 {
+            distinct.insert(&i);
+            distinct.insert(&i);
+        }
+        // small cardinalities are almost exact
+        let count = distinct.count();
+        assert!((98..=102).contains(&count), "count: {}", count);
+        for i in 0..1_000_000u32 {
+            distinct.insert(&i);
+        }
+        let count = distinct.count();
+        assert!(count > 950_000 && count < 1_050_000, "count: {}", count);
+        distinct.clear();
+        assert_eq!(distinct.count(), 0);
+    }
+
+    #[test]
+    fn expire_buckets() {
+        let mut distinct: WindowedDistinct<str> =
+            WindowedDistinct::new(Duration::from_millis(100), 2).with_precision(20);
+        assert_eq!(distinct.precision(), 16);
+        assert_eq!(distinct.buckets(), 2);
+        distinct.insert("a");
+        thread::sleep(Duration::from_millis(60));
+        distinct.insert("a");
+        distinct.insert("b");
+        assert_eq!(distinct.count(), 2);
+        thread::sleep(Duration::from_millis(60));
+        distinct.insert("c");
+        assert_eq!(distinct.count(), 3);
+        thread::sleep(Duration::from_millis(120));
+        assert_eq!(distinct.count(), 0);
+    }
+}
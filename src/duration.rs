@@ -0,0 +1,95 @@
+use crate::{QueueStats, SumQueue};
+use std::time::Duration;
+
+/// Stats of a queue of durations, eg. the latencies of
+/// the requests served within a window of time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DurationStats {
+    /// min duration of the queue
+    pub min: Duration,
+    /// max duration of the queue
+    pub max: Duration,
+    /// average of the durations of the queue
+    pub avg: Duration,
+    /// sum of all the durations of the queue
+    pub sum: Duration,
+    /// size of the queue, same than [`SumQueue::len()`]
+    pub len: usize,
+}
+
+impl QueueStats<Duration> {
+    /// Returns the average of the durations, or `None` if there are no values.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(Duration::from_millis(100));
+    /// queue.push(Duration::from_millis(250));
+    /// assert_eq!(queue.stats().avg(), Some(Duration::from_millis(175)));
+    /// ```
+    pub fn avg(&self) -> Option<Duration> {
+        let nanos = self.sum?.as_nanos() / self.len.max(1) as u128;
+        Some(Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        ))
+    }
+}
+
+impl SumQueue<Duration> {
+    /// Returns the min, max, average and sum of the durations in the
+    /// queue, or `None` if the queue is empty, eg. to report the latencies
+    /// within the window.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut latencies = SumQueue::new(Duration::from_secs(60));
+    /// latencies.push(Duration::from_millis(120));
+    /// latencies.push(Duration::from_millis(80));
+    /// latencies.push(Duration::from_millis(100));
+    /// let stats = latencies.stats_duration().unwrap();
+    /// assert_eq!(stats.min, Duration::from_millis(80));
+    /// assert_eq!(stats.max, Duration::from_millis(120));
+    /// assert_eq!(stats.avg, Duration::from_millis(100));
+    /// assert_eq!(stats.len, 3);
+    /// ```
+    pub fn stats_duration(&mut self) -> Option<DurationStats> {
+        let stats = self.stats();
+        Some(DurationStats {
+            avg: stats.avg()?,
+            min: stats.min?,
+            max: stats.max?,
+            sum: stats.sum?,
+            len: stats.len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn stats_duration() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        assert_eq!(queue.stats_duration(), None);
+        assert_eq!(queue.stats().avg(), None);
+        queue.push(Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(150));
+        queue.push(Duration::from_nanos(1));
+        queue.push(Duration::new(3, 2));
+        let stats = queue.stats_duration().unwrap();
+        // the expired element is not included
+        assert_eq!(stats.len, 2);
+        assert_eq!(stats.min, Duration::from_nanos(1));
+        assert_eq!(stats.max, Duration::new(3, 2));
+        assert_eq!(stats.sum, Duration::new(3, 3));
+        assert_eq!(stats.avg, Duration::new(1, 500_000_001));
+    }
+}
@@ -0,0 +1,227 @@
+//! Version of the queue for embedded targets.
+//!
+//! The [`SumQueue`] of this module stores the elements in
+//! a [`heapless::Deque`], with a capacity fixed at compile time,
+//! so it doesn't allocate memory at all. And because there is no
+//! system clock in most microcontrollers, the time is read from a
+//! [`TickSource`] provided by the user, eg. a hardware timer counter.
+//!
+//! ```
+//! use std::cell::Cell;
+//! use sum_queue::embedded::SumQueue;
+//!
+//! let ticks = Cell::new(0);
+//! // elements expire after 100 ticks, and the queue holds 8 elements at most
+//! let mut queue: SumQueue<i32, _, 8> = SumQueue::new(100, || ticks.get());
+//! queue.push(20);
+//! ticks.set(60);
+//! queue.push(22);
+//! assert_eq!(queue.stats().sum, Some(42));
+//! ticks.set(150);
+//! assert_eq!(queue.stats().sum, Some(22));
+//! ```
+//!
+//! The module itself only uses `core` and `heapless`, although the rest
+//! of the crate still depends on `std`.
+
+use crate::{QueueStats, Summable};
+use core::fmt;
+use heapless::Deque;
+
+/// Source of the time used by the embedded [`SumQueue`], in ticks
+/// of any unit, eg. milliseconds or the cycles of a hardware timer.
+///
+/// It's implemented by any function that returns the current tick,
+/// like `|| timer.count()`. The counter may wrap around, as long as
+/// the elements don't live more than a whole round of the counter.
+pub trait TickSource {
+    /// Returns the current tick.
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> TickSource for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// Queue with a fixed capacity of `N` elements, where the elements
+/// expire after `max_age` ticks of the [`TickSource`] `C`, see
+/// the [module documentation](self) for more.
+///
+/// `N` must be greater than zero.
+pub struct SumQueue<T, C, const N: usize> {
+    queue: Deque<(u64, T), N>,
+    max_age: u64,
+    clock: C,
+}
+
+impl<T, C: TickSource, const N: usize> SumQueue<T, C, N> {
+    /// Creates an empty `SumQueue`, where the elements inside
+    /// will live `max_age` ticks of `clock` at maximum.
+    pub const fn new(max_age: u64, clock: C) -> SumQueue<T, C, N> {
+        SumQueue {
+            queue: Deque::new(),
+            max_age,
+            clock,
+        }
+    }
+
+    fn clear_oldest(&mut self, now: u64) {
+        while let Some((tick, _)) = self.queue.front() {
+            if now.wrapping_sub(*tick) > self.max_age {
+                self.queue.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Pushes an item at the back of the queue, and returns the length
+    /// of the queue. If the queue is full, the oldest element is dropped
+    /// to make room for the new one.
+    ///
+    /// Before push the element, it also drops all expired elements.
+    ///
+    /// ```
+    /// use sum_queue::embedded::SumQueue;
+    /// let mut queue: SumQueue<i32, _, 2> = SumQueue::new(100, || 0);
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert_eq!(queue.push(3), 2);
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &3]);
+    /// ```
+    pub fn push(&mut self, item: T) -> usize {
+        let now = self.clock.now();
+        self.clear_oldest(now);
+        if self.queue.is_full() {
+            self.queue.pop_front();
+        }
+        // there is always room after dropping the oldest element
+        let _ = self.queue.push_back((now, item));
+        self.queue.len()
+    }
+
+    /// Removes the first item from the queue and returns it, or `None` if it
+    /// is empty. Before pop the element, it also drops all expired elements.
+    pub fn pop(&mut self) -> Option<T> {
+        self.clear_oldest(self.clock.now());
+        self.queue.pop_front().map(|(_, value)| value)
+    }
+
+    /// Returns the first item in the queue, or `None` if it is empty.
+    /// Before return the element, it also drops all expired elements.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.clear_oldest(self.clock.now());
+        self.queue.front().map(|(_, value)| value)
+    }
+
+    /// Returns the length of the queue.
+    /// Before compute the length, it also drops all expired elements.
+    pub fn len(&mut self) -> usize {
+        self.clear_oldest(self.clock.now());
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty.
+    /// Before check it, it also drops all expired elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the max number of elements the queue can hold, that is `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the max ticks the elements will live in the queue.
+    pub fn max_age(&self) -> u64 {
+        self.max_age
+    }
+
+    /// Drops all items.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Returns an iterator visiting all values in the queue, in
+    /// same order they were pushed.
+    /// Before return the iterator, it also drops all expired elements.
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        self.clear_oldest(self.clock.now());
+        self.queue.iter().map(|(_, value)| value)
+    }
+}
+
+impl<T: Summable, C: TickSource, const N: usize> SumQueue<T, C, N> {
+    /// Get statistics of the queue, see [`crate::SumQueue::stats()`].
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    pub fn stats(&mut self) -> QueueStats<T> {
+        self.iter().fold(QueueStats::default(), |stats, &value| {
+            stats.merge(QueueStats {
+                min: Some(value),
+                max: Some(value),
+                sum: Some(value),
+                len: 1,
+            })
+        })
+    }
+}
+
+impl<T: fmt::Debug, C, const N: usize> fmt::Debug for SumQueue<T, C, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SumQueue")
+            .field("max_age", &self.max_age)
+            .field("len", &self.queue.len())
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::embedded::SumQueue;
+    use std::cell::Cell;
+
+    #[test]
+    fn expire() {
+        let ticks = Cell::new(0);
+        let mut queue: SumQueue<i32, _, 4> = SumQueue::new(10, || ticks.get());
+        assert_eq!(queue.capacity(), 4);
+        queue.push(1);
+        ticks.set(5);
+        queue.push(2);
+        assert_eq!(queue.peek(), Some(&1));
+        ticks.set(11);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop(), Some(2));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full() {
+        let mut queue: SumQueue<i32, _, 3> = SumQueue::new(10, || 0);
+        for i in 0..5 {
+            queue.push(i);
+        }
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+        let stats = queue.stats();
+        assert_eq!(stats.min, Some(2));
+        assert_eq!(stats.sum, Some(9));
+        queue.clear();
+        assert_eq!(queue.stats().len, 0);
+    }
+
+    #[test]
+    fn wrapping_ticks() {
+        let ticks = Cell::new(u64::MAX - 2);
+        let mut queue: SumQueue<i32, _, 4> = SumQueue::new(5, || ticks.get());
+        queue.push(1);
+        ticks.set(1);
+        queue.push(2);
+        assert_eq!(queue.len(), 2);
+        ticks.set(3);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2]);
+    }
+}
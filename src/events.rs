@@ -0,0 +1,106 @@
+use crate::{Clock, Instant, QueueObserver, SumQueue};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Event of an element pushed, popped or expired from a
+/// queue, see [`SumQueue::events()`], with the instant it happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueueEvent<T> {
+    /// element pushed into the queue
+    Pushed { value: T, at: Instant },
+    /// element removed with [`SumQueue::pop()`] or the other pop methods
+    Popped { value: T, at: Instant },
+    /// element dropped by the queue itself, see [`QueueObserver::expired()`]
+    Expired { value: T, at: Instant },
+}
+
+/// Observer that sends the events through a channel.
+struct EventSender<T> {
+    sender: Sender<QueueEvent<T>>,
+    clock: Clock,
+}
+
+impl<T: Clone + Send> QueueObserver<T> for EventSender<T> {
+    fn pushed(&self, value: &T) {
+        let _ = self.sender.send(QueueEvent::Pushed {
+            value: value.clone(),
+            at: self.clock.now(),
+        });
+    }
+
+    fn popped(&self, value: &T) {
+        let _ = self.sender.send(QueueEvent::Popped {
+            value: value.clone(),
+            at: self.clock.now(),
+        });
+    }
+
+    fn expired(&self, value: &T) {
+        let _ = self.sender.send(QueueEvent::Expired {
+            value: value.clone(),
+            at: self.clock.now(),
+        });
+    }
+}
+
+impl<T: Clone + Send + 'static> SumQueue<T> {
+    /// Returns a channel receiving a copy of each element pushed, popped
+    /// and expired from now on, so another thread can audit or mirror the
+    /// activity of the queue, see [`QueueObserver`] to use callbacks instead.
+    ///
+    /// The channel is unbounded, so the events not received are kept
+    /// in memory until the receiver is dropped.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{QueueEvent, SumQueue};
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let events = queue.events();
+    /// queue.push("a");
+    /// queue.pop();
+    /// let values = events
+    ///     .try_iter()
+    ///     .map(|event| match event {
+    ///         QueueEvent::Pushed { value, .. } => format!("+{}", value),
+    ///         QueueEvent::Popped { value, .. } => format!("-{}", value),
+    ///         QueueEvent::Expired { value, .. } => format!("x{}", value),
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(values, vec!["+a", "-a"]);
+    /// ```
+    pub fn events(&mut self) -> Receiver<QueueEvent<T>> {
+        let (sender, receiver) = mpsc::channel();
+        let clock = self.clock().clone();
+        self.subscribe(EventSender { sender, clock });
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{QueueEvent, SumQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn events() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        queue.push(1);
+        let events = queue.events();
+        queue.push(2);
+        let receiver = thread::spawn(move || events.iter().collect::<Vec<_>>());
+        assert_eq!(queue.pop(), Some(1));
+        thread::sleep(Duration::from_millis(150));
+        assert!(queue.is_empty());
+        drop(queue);
+        let events = receiver.join().unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], QueueEvent::Pushed { value: 2, .. }));
+        assert!(matches!(events[1], QueueEvent::Popped { value: 1, .. }));
+        match (&events[1], &events[2]) {
+            (QueueEvent::Popped { at: popped, .. }, QueueEvent::Expired { value: 2, at }) => {
+                assert!(*at - *popped >= Duration::from_millis(100));
+            }
+            _ => panic!("unexpected events {:?}", events),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::SumQueue;
+
+/// Stats of each field of the elements of a queue, computed in one pass,
+/// eg. the min, max and sum of each numeric field of a struct, instead
+/// of keeping a queue per field.
+///
+/// With the `derive` feature, it can be derived for a struct with named
+/// fields, returning a struct named after the derived one plus `Stats`,
+/// with a [`crate::QueueStats`] for each field of a primitive numeric type.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use std::time::Duration;
+/// use sum_queue::{FieldStats, SumQueue};
+///
+/// #[derive(FieldStats)]
+/// struct Measurement {
+///     sensor: String,
+///     temperature: f64,
+///     humidity: u32,
+/// }
+///
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push(Measurement { sensor: "a".into(), temperature: 21.5, humidity: 40 });
+/// queue.push(Measurement { sensor: "b".into(), temperature: 19.0, humidity: 55 });
+/// let stats: MeasurementStats = queue.field_stats();
+/// assert_eq!(stats.temperature.min, Some(19.0));
+/// assert_eq!(stats.humidity.sum, Some(95));
+/// assert_eq!(stats.humidity.len, 2);
+/// # }
+/// ```
+pub trait FieldStats {
+    /// Stats of all the fields.
+    type Stats;
+
+    /// Returns the stats of the fields of the `elements`,
+    /// where `len` is the number of elements.
+    fn field_stats<'a, I>(elements: I, len: usize) -> Self::Stats
+    where
+        I: Iterator<Item = &'a Self>,
+        Self: 'a;
+}
+
+impl<T: FieldStats> SumQueue<T> {
+    /// Returns the stats of each field of the elements in the queue, see
+    /// [`FieldStats`]. The decay set with [`SumQueue::with_decay()`] is not applied.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    pub fn field_stats(&mut self) -> T::Stats {
+        self.clear_oldest(self.now());
+        T::field_stats(self.queue.iter().map(|el| &el.value), self.queue.len())
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::{FieldStats, QueueStats, SumQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(FieldStats)]
+    struct Request {
+        path: &'static str,
+        status: u16,
+        latency: f32,
+    }
+
+    #[test]
+    fn field_stats() {
+        let mut queue: SumQueue<Request> = SumQueue::new(Duration::from_millis(100));
+        let empty = queue.field_stats();
+        assert_eq!(empty.status, QueueStats::default());
+        queue.push(Request {
+            path: "/",
+            status: 200,
+            latency: 0.5,
+        });
+        thread::sleep(Duration::from_millis(150));
+        queue.push(Request {
+            path: "/login",
+            status: 500,
+            latency: 2.0,
+        });
+        queue.push(Request {
+            path: "/",
+            status: 404,
+            latency: 0.25,
+        });
+        assert_eq!(queue.iter().filter(|r| r.path == "/").count(), 1);
+        // the expired element is not included
+        let stats: RequestStats = queue.field_stats();
+        assert_eq!(
+            stats.status,
+            QueueStats {
+                min: Some(404),
+                max: Some(500),
+                sum: Some(904),
+                len: 2,
+            }
+        );
+        assert_eq!(
+            (stats.latency.min, stats.latency.max, stats.latency.sum),
+            (Some(0.25), Some(2.0), Some(2.25))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{QueueStats, SumQueue};
+use ordered_float::{FloatCore, OrderedFloat};
+
+impl<F: FloatCore> SumQueue<OrderedFloat<F>> {
+    /// Pushes a float at the back of the queue wrapped in an [`OrderedFloat`],
+    /// and returns the size of the queue, see [`SumQueue::push()`].
+    pub fn push_float(&mut self, item: F) -> usize {
+        self.push(OrderedFloat(item))
+    }
+
+    /// Get statistics of the queue like [`SumQueue::stats()`],
+    /// but with the values unwrapped from the [`OrderedFloat`].
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{OrderedFloat, SumQueue};
+    /// let mut queue: SumQueue<OrderedFloat<f64>> = SumQueue::new(Duration::from_secs(60));
+    /// queue.push_float(0.5);
+    /// queue.push_float(2.25);
+    /// let stats = queue.stats_float();
+    /// assert_eq!(stats.min, Some(0.5));
+    /// assert_eq!(stats.max, Some(2.25));
+    /// assert_eq!(stats.sum, Some(2.75));
+    /// ```
+    pub fn stats_float(&mut self) -> QueueStats<F> {
+        let stats = self.stats();
+        QueueStats {
+            min: stats.min.map(|v| v.0),
+            max: stats.max.map(|v| v.0),
+            sum: stats.sum.map(|v| v.0),
+            len: stats.len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{OrderedFloat, SumQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn stats_float() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        assert_eq!(queue.stats_float().sum, None::<f32>);
+        queue.push_float(10.0f32);
+        thread::sleep(Duration::from_millis(150));
+        queue.push_float(-1.5);
+        assert_eq!(queue.push(OrderedFloat(4.0)), 2);
+        let stats = queue.stats_float();
+        assert_eq!(
+            (stats.min, stats.max, stats.sum, stats.len),
+            (Some(-1.5), Some(4.0), Some(2.5), 2)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{QueueStats, SumQueue};
+use std::iter::Sum;
+
+impl<T: Clone + PartialOrd> SumQueue<T> {
+    /// Get statistics of the queue like [`SumQueue::stats()`], but with
+    /// the **sum** computed with `fold`, that adds a value by reference to
+    /// the sum so far, so the types that are not `Copy` or don't implement
+    /// `Add<Output = T>`, like big numbers, can be summarized as well.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(String::from("b"));
+    /// queue.push(String::from("a"));
+    /// queue.push(String::from("c"));
+    /// let stats = queue.stats_with_fold(|sum, value| sum + value);
+    /// assert_eq!(stats.min.as_deref(), Some("a"));
+    /// assert_eq!(stats.sum.as_deref(), Some("bac"));
+    /// ```
+    pub fn stats_with_fold<F>(&mut self, mut fold: F) -> QueueStats<T>
+    where
+        F: FnMut(T, &T) -> T,
+    {
+        let len = self.len();
+        let now = self.now();
+        let mut stats = QueueStats {
+            len,
+            ..QueueStats::default()
+        };
+        for value in self.queue.iter().map(|el| self.decayed(el, now)) {
+            if stats.min.as_ref().is_none_or(|min| value < *min) {
+                stats.min = Some(value.clone());
+            }
+            if stats.max.as_ref().is_none_or(|max| value > *max) {
+                stats.max = Some(value.clone());
+            }
+            stats.sum = Some(match stats.sum.take() {
+                Some(sum) => fold(sum, &value),
+                None => value,
+            });
+        }
+        stats
+    }
+
+    /// Get statistics of the queue like [`SumQueue::stats()`], but with
+    /// the **sum** computed with the [`Sum`] implementation of the values,
+    /// for the types that implement it but not `Add<Output = T>`.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::iter::Sum;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    ///
+    /// #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    /// struct Bytes(u64);
+    ///
+    /// impl<'a> Sum<&'a Bytes> for Bytes {
+    ///     fn sum<I: Iterator<Item = &'a Bytes>>(iter: I) -> Bytes {
+    ///         Bytes(iter.map(|b| b.0).sum())
+    ///     }
+    /// }
+    ///
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(Bytes(512));
+    /// queue.push(Bytes(1024));
+    /// let stats = queue.stats_with_sum();
+    /// assert_eq!(stats.max, Some(Bytes(1024)));
+    /// assert_eq!(stats.sum, Some(Bytes(1536)));
+    /// ```
+    pub fn stats_with_sum(&mut self) -> QueueStats<T>
+    where
+        T: for<'a> Sum<&'a T>,
+    {
+        self.stats_with_fold(|sum, value| [sum, value.clone()].iter().sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::iter::Sum;
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    struct Big(Vec<u32>);
+
+    impl<'a> Sum<&'a Big> for Big {
+        fn sum<I: Iterator<Item = &'a Big>>(iter: I) -> Big {
+            Big(iter.flat_map(|b| b.0.iter().copied()).collect())
+        }
+    }
+
+    #[test]
+    fn stats_with_fold() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        assert_eq!(queue.stats_with_sum().sum, None);
+        queue.push(Big(vec![9]));
+        thread::sleep(Duration::from_millis(150));
+        queue.push(Big(vec![2]));
+        queue.push(Big(vec![1, 5]));
+        queue.push(Big(vec![3]));
+        let stats = queue.stats_with_sum();
+        assert_eq!(stats.min, Some(Big(vec![1, 5])));
+        assert_eq!(stats.max, Some(Big(vec![3])));
+        assert_eq!(stats.sum, Some(Big(vec![2, 1, 5, 3])));
+        assert_eq!(stats.len, 3);
+        let stats = queue.stats_with_fold(|sum, value| Big(vec![sum.0[0] + value.0[0]]));
+        assert_eq!(stats.sum, Some(Big(vec![6])));
+    }
+}
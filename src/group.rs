@@ -0,0 +1,190 @@
+use crate::{QueueStats, SumQueue, Summable};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Set of related queues, one for each key, eg. the same metric
+/// tracked for each status code or region, where all the queues
+/// share the same max age.
+///
+/// The queue of each key ("member") is created the first time
+/// an element is pushed with that key.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::QueueGroup;
+///
+/// let mut group = QueueGroup::new(Duration::from_secs(60));
+/// group.push(200, 35);
+/// group.push(200, 40);
+/// group.push(500, 900);
+///
+/// let stats = group.stats();
+/// assert_eq!(stats.combined.len, 3);
+/// assert_eq!(stats.combined.max, Some(900));
+/// assert_eq!(stats.members[&200].sum, Some(75));
+/// assert_eq!(stats.members[&500].sum, Some(900));
+/// ```
+pub struct QueueGroup<K, T> {
+    members: BTreeMap<K, SumQueue<T>>,
+    max_age: Duration,
+}
+
+/// Stats of a [`QueueGroup`], returned by [`QueueGroup::stats()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupStats<K, T> {
+    /// stats of all the elements of all the members together
+    pub combined: QueueStats<T>,
+    /// stats of each member, by key
+    pub members: BTreeMap<K, QueueStats<T>>,
+}
+
+impl<K: Ord, T> QueueGroup<K, T> {
+    /// Creates an empty group, where the elements of
+    /// the members will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> QueueGroup<K, T> {
+        QueueGroup {
+            members: BTreeMap::new(),
+            max_age: max_age_duration,
+        }
+    }
+
+    /// Pushes an item at the back of the queue of `key`, creating
+    /// the queue if it doesn't exist, and returns the length
+    /// of the queue of `key`, see [`SumQueue::push()`].
+    pub fn push(&mut self, key: K, item: T) -> usize {
+        let max_age = self.max_age;
+        self.members
+            .entry(key)
+            .or_insert_with(|| SumQueue::new(max_age))
+            .push(item)
+    }
+
+    /// Returns the queue of `key`, or `None` if
+    /// no element was pushed with that key.
+    pub fn member(&mut self, key: &K) -> Option<&mut SumQueue<T>> {
+        self.members.get_mut(key)
+    }
+
+    /// Removes the queue of `key` from the group, and returns it.
+    pub fn remove(&mut self, key: &K) -> Option<SumQueue<T>> {
+        self.members.remove(key)
+    }
+
+    /// Returns an iterator over the keys of the members, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.members.keys()
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Checks if the group has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the max time the elements will live in the queues.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Drops the expired elements of all the members.
+    pub fn prune_all(&mut self) {
+        for queue in self.members.values_mut() {
+            queue.prune();
+        }
+    }
+
+    /// Keeps only the members for which `f` returns `true`.
+    pub(crate) fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut SumQueue<T>) -> bool,
+    {
+        self.members.retain(f)
+    }
+}
+
+impl<K: Ord + Clone, T: Summable> QueueGroup<K, T> {
+    /// Get the stats of each member, and the stats of all the members
+    /// together, in one call.
+    ///
+    /// The expired elements of all the members are dropped
+    /// considering the same instant, so the stats are coherent
+    /// with each other: `combined` is always the merge of `members`.
+    pub fn stats(&mut self) -> GroupStats<K, T> {
+        self.prune_all();
+        let members: BTreeMap<K, QueueStats<T>> = self
+            .members
+            .iter_mut()
+            .map(|(key, queue)| {
+                let len = queue.queue.len();
+                (key.clone(), queue._stats(len))
+            })
+            .collect();
+        let combined = members
+            .values()
+            .cloned()
+            .fold(QueueStats::default(), QueueStats::merge);
+        GroupStats { combined, members }
+    }
+
+    /// Get the stats of all the elements of all the members together.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::QueueGroup;
+    /// let mut group = QueueGroup::new(Duration::from_secs(60));
+    /// group.push("us-east", 10);
+    /// group.push("eu-west", 5);
+    /// assert_eq!(group.combined_stats().sum, Some(15));
+    /// ```
+    pub fn combined_stats(&mut self) -> QueueStats<T> {
+        self.stats().combined
+    }
+}
+
+impl<K: Ord, T> Default for QueueGroup<K, T> {
+    fn default() -> Self {
+        QueueGroup::new(crate::DEFAULT_MAX_AGE)
+    }
+}
+
+impl<K: fmt::Debug, T: fmt::Debug> fmt::Debug for QueueGroup<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.members.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QueueGroup;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn group() {
+        let mut group = QueueGroup::new(Duration::from_millis(100));
+        assert!(group.is_empty());
+        assert_eq!(group.push("a", 1), 1);
+        assert_eq!(group.push("a", 2), 2);
+        thread::sleep(Duration::from_millis(150));
+        group.push("b", 10);
+        group.push("c", -3);
+        assert_eq!(group.keys().collect::<Vec<_>>(), vec![&"a", &"b", &"c"]);
+        let stats = group.stats();
+        assert_eq!(stats.members[&"a"].len, 0);
+        assert_eq!(stats.members[&"a"].sum, None);
+        assert_eq!(stats.members[&"b"].sum, Some(10));
+        assert_eq!(stats.combined.len, 2);
+        assert_eq!(stats.combined.min, Some(-3));
+        assert_eq!(group.combined_stats().sum, Some(7));
+        assert_eq!(group.member(&"b").unwrap().pop(), Some(10));
+        assert!(group.member(&"d").is_none());
+        assert!(group.remove(&"c").is_some());
+        assert_eq!(group.len(), 2);
+        assert_eq!(group.combined_stats().len, 0);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::SumQueue;
+use hdrhistogram::{CreationError, Histogram, RecordError};
+use std::fmt;
+use std::time::Duration;
+
+/// Sliding window of values recorded in HDR histograms, that answers
+/// percentile queries with the precision of [`hdrhistogram`], eg. the
+/// 99.9th percentile of the latencies of the last minute.
+///
+/// The window is split into buckets of the same duration, each one with
+/// its own [`Histogram`], that are kept in a [`SumQueue`], so the buckets
+/// expire like any other element of the queue, all the values of a bucket at
+/// once. The histograms within the window are merged to answer the queries.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::WindowedHistogram;
+///
+/// let mut latencies = WindowedHistogram::new(Duration::from_secs(60), 6, 3).unwrap();
+/// for latency in 1..=10_000 {
+///     latencies.record(latency).unwrap();
+/// }
+/// assert_eq!(latencies.len(), 10_000);
+/// assert_eq!(latencies.value_at_quantile(0.999), Some(9_991));
+/// ```
+pub struct WindowedHistogram {
+    /// one histogram per bucket, the newest is the current one
+    buckets: SumQueue<Histogram<u64>>,
+    bucket_width: Duration,
+    /// empty histogram with the settings of the buckets
+    template: Histogram<u64>,
+}
+
+impl WindowedHistogram {
+    /// Creates an empty window of the values recorded within the last
+    /// `window` of time, split in `buckets` buckets (at least one), with
+    /// histograms that auto-resize to record any value with `sigfig`
+    /// significant digits, see [`Histogram::new()`].
+    pub fn new(
+        window: Duration,
+        buckets: usize,
+        sigfig: u8,
+    ) -> Result<WindowedHistogram, CreationError> {
+        Ok(WindowedHistogram::with_template(
+            window,
+            buckets,
+            Histogram::new(sigfig)?,
+        ))
+    }
+
+    /// Creates an empty window like [`WindowedHistogram::new()`], but with histograms
+    /// that record values between `low` and `high`, see [`Histogram::new_with_bounds()`].
+    pub fn new_with_bounds(
+        window: Duration,
+        buckets: usize,
+        low: u64,
+        high: u64,
+        sigfig: u8,
+    ) -> Result<WindowedHistogram, CreationError> {
+        Ok(WindowedHistogram::with_template(
+            window,
+            buckets,
+            Histogram::new_with_bounds(low, high, sigfig)?,
+        ))
+    }
+
+    fn with_template(
+        window: Duration,
+        buckets: usize,
+        template: Histogram<u64>,
+    ) -> WindowedHistogram {
+        let buckets = buckets.max(1);
+        WindowedHistogram {
+            buckets: SumQueue::with_capacity(window, buckets + 1),
+            bucket_width: (window / buckets as u32).max(Duration::from_nanos(1)),
+            template,
+        }
+    }
+
+    /// Returns the duration covered by each bucket.
+    pub fn bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.buckets.max_age()
+    }
+
+    /// Returns the histogram of the current bucket,
+    /// starting a new bucket if the last one is full.
+    fn current(&mut self) -> &mut Histogram<u64> {
+        let bucket_width = self.bucket_width;
+        let full = self
+            .buckets
+            .newest()
+            .is_none_or(|(_, age)| age >= bucket_width);
+        if full {
+            self.buckets.push(Histogram::new_from(&self.template));
+        }
+        self.buckets.iter_mut().next_back().unwrap()
+    }
+
+    /// Records a value in the current bucket, or returns an error if
+    /// the value is out of the bounds of the histograms.
+    pub fn record(&mut self, value: u64) -> Result<(), RecordError> {
+        self.current().record(value)
+    }
+
+    /// Records a value in the current bucket, recording the values out
+    /// of the bounds of the histograms as the lowest or highest value.
+    pub fn saturating_record(&mut self, value: u64) {
+        self.current().saturating_record(value)
+    }
+
+    /// Returns a histogram with all the values within the window.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::WindowedHistogram;
+    /// let mut window = WindowedHistogram::new(Duration::from_secs(60), 6, 2).unwrap();
+    /// window.record(10).unwrap();
+    /// window.record(20).unwrap();
+    /// let merged = window.merged();
+    /// assert_eq!(merged.max(), 20);
+    /// assert_eq!(merged.mean(), 15.0);
+    /// ```
+    pub fn merged(&mut self) -> Histogram<u64> {
+        let mut merged = Histogram::new_from(&self.template);
+        for histogram in self.buckets.iter() {
+            merged
+                .add(histogram)
+                .expect("buckets are created with the same settings");
+        }
+        merged
+    }
+
+    /// Returns the value at the quantile `q` (between 0 and 1) of the
+    /// values within the window, or `None` if there is no value.
+    pub fn value_at_quantile(&mut self, q: f64) -> Option<u64> {
+        let merged = self.merged();
+        if merged.is_empty() {
+            return None;
+        }
+        Some(merged.value_at_quantile(q))
+    }
+
+    /// Returns the number of values within the window.
+    pub fn len(&mut self) -> u64 {
+        self.buckets.iter().map(|histogram| histogram.len()).sum()
+    }
+
+    /// Checks if there is no value within the window.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl fmt::Debug for WindowedHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowedHistogram")
+            .field("window", &self.buckets.max_age())
+            .field("bucket_width", &self.bucket_width)
+            .field("buckets", &self.buckets.len_unpruned())
+            .field("sigfig", &self.template.sigfig())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WindowedHistogram;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn rotate_buckets() {
+        let mut window = WindowedHistogram::new(Duration::from_millis(100), 2, 3).unwrap();
+        assert_eq!(window.window(), Duration::from_millis(100));
+        assert_eq!(window.bucket_width(), Duration::from_millis(50));
+        assert_eq!(window.value_at_quantile(0.5), None);
+        window.record(1000).unwrap();
+        thread::sleep(Duration::from_millis(60));
+        window.record(10).unwrap();
+        window.record(20).unwrap();
+        assert_eq!(window.len(), 3);
+        assert_eq!(window.value_at_quantile(1.0), Some(1000));
+        assert_eq!(window.buckets.len(), 2);
+        thread::sleep(Duration::from_millis(60));
+        // the first bucket expired
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.value_at_quantile(1.0), Some(20));
+        window.clear();
+        assert!(window.is_empty());
+    }
+
+    #[test]
+    fn bounds() {
+        let mut window =
+            WindowedHistogram::new_with_bounds(Duration::from_secs(60), 4, 1, 100, 2).unwrap();
+        assert!(window.record(1000).is_err());
+        window.saturating_record(1000);
+        assert_eq!(window.value_at_quantile(0.5), Some(100));
+        assert!(WindowedHistogram::new(Duration::from_secs(60), 4, 6).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{QueueRegistry, QueueStats, Summable};
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{header, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::{self, Write};
+use std::io;
+use std::sync::Arc;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// Path where the stats are served.
+const STATS_PATH: &str = "/stats";
+
+/// Writes `s` as a JSON string, with the quotes and the control characters escaped.
+fn write_json_string(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+/// Writes `value` as a JSON number, or `null` if there is none or it's
+/// not a finite number, like the `NaN` and infinite floats, that JSON lacks.
+fn write_json_value<T: fmt::Display>(out: &mut String, value: &Option<T>) -> fmt::Result {
+    match value.as_ref().map(ToString::to_string) {
+        Some(number) if number.parse::<f64>().is_ok_and(f64::is_finite) => out.write_str(&number),
+        _ => out.write_str("null"),
+    }
+}
+
+/// Renders the stats of the queues as a JSON object with the labels as keys.
+fn stats_json<T: fmt::Display>(stats: &BTreeMap<String, QueueStats<T>>) -> String {
+    let mut out = String::from("{");
+    for (i, (label, stats)) in stats.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        // writing into a String never fails
+        let _ = write_json_string(&mut out, label);
+        let _ = write!(out, ":{{\"len\":{}", stats.len);
+        for (name, value) in [
+            ("sum", &stats.sum),
+            ("min", &stats.min),
+            ("max", &stats.max),
+        ] {
+            let _ = write!(out, ",\"{}\":", name);
+            let _ = write_json_value(&mut out, value);
+        }
+        out.push('}');
+    }
+    out.push('}');
+    out
+}
+
+fn respond<T, B>(registry: &QueueRegistry<T>, request: &Request<B>) -> Response<Full<Bytes>>
+where
+    T: Summable + fmt::Display,
+{
+    let (status, body) = match (request.method(), request.uri().path()) {
+        (&Method::GET, STATS_PATH) => (StatusCode::OK, stats_json(&registry.stats())),
+        (_, STATS_PATH) => (StatusCode::METHOD_NOT_ALLOWED, String::new()),
+        _ => (StatusCode::NOT_FOUND, String::new()),
+    };
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    if status == StatusCode::OK {
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+    }
+    response
+}
+
+/// Serves the current stats of the queues of `registry` as JSON at
+/// `GET /stats`, listening at `addr`, eg. to be scraped by a monitoring
+/// system or checked with `curl`.
+///
+/// The response is an object with the labels of the queues as keys,
+/// and their **len**, **sum**, **min** and **max** as values, written
+/// with their `Display` implementation, or `null` if the queue is empty
+/// or the value is not a finite number, like a `NaN` float:
+///
+/// ```text
+/// {"api.latency":{"len":2,"sum":200,"min":80,"max":120}}
+/// ```
+///
+/// It runs until accepting a connection fails, serving each
+/// connection in its own task of the Tokio runtime.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use sum_queue::{serve_stats, QueueRegistry};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> std::io::Result<()> {
+/// let registry = Arc::new(QueueRegistry::new(Duration::from_secs(60)));
+/// registry.queue("api.latency").push(120);
+/// serve_stats("127.0.0.1:9100", registry).await
+/// # }
+/// ```
+pub async fn serve_stats<T, A>(addr: A, registry: Arc<QueueRegistry<T>>) -> io::Result<()>
+where
+    T: Summable + fmt::Display + Send + 'static,
+    A: ToSocketAddrs,
+{
+    serve_stats_on(TcpListener::bind(addr).await?, registry).await
+}
+
+/// Serves the stats of the queues of `registry` like [`serve_stats()`],
+/// but accepting the connections of a listener already bound, eg.
+/// to a port chosen by the system.
+pub async fn serve_stats_on<T>(
+    listener: TcpListener,
+    registry: Arc<QueueRegistry<T>>,
+) -> io::Result<()>
+where
+    T: Summable + fmt::Display + Send + 'static,
+{
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let response = respond(&registry, &request);
+                async move { Ok::<_, Infallible>(response) }
+            });
+            // errors of a connection, like a client disconnecting,
+            // don't stop serving other connections
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::stats_json;
+    use crate::{serve_stats_on, QueueRegistry, QueueStats};
+    use std::collections::BTreeMap;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    fn get(addr: SocketAddr, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn serve_stats() {
+        let registry = Arc::new(QueueRegistry::new(Duration::from_secs(60)));
+        registry.queue("api.latency").push(120);
+        registry.queue("api.latency").push(80);
+        registry.queue("say \"hi\"").push(-1);
+        registry.queue("empty");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_stats_on(listener, registry));
+        let responses = tokio::task::spawn_blocking(move || {
+            vec![
+                get(addr, "GET", "/stats"),
+                get(addr, "POST", "/stats"),
+                get(addr, "GET", "/"),
+            ]
+        })
+        .await
+        .unwrap();
+        assert!(responses[0].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(responses[0].contains("content-type: application/json\r\n"));
+        assert!(responses[0].ends_with(concat!(
+            r#"{"api.latency":{"len":2,"sum":200,"min":80,"max":120},"#,
+            r#""empty":{"len":0,"sum":null,"min":null,"max":null},"#,
+            r#""say \"hi\"":{"len":1,"sum":-1,"min":-1,"max":-1}}"#
+        )));
+        assert!(responses[1].starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(responses[2].starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[test]
+    fn stats_json_not_finite() {
+        let mut stats = BTreeMap::new();
+        let not_finite = QueueStats {
+            min: Some(f64::NEG_INFINITY),
+            max: Some(f64::INFINITY),
+            sum: Some(f64::NAN),
+            len: 3,
+        };
+        let floats = QueueStats {
+            min: Some(-0.5),
+            max: Some(2.5),
+            sum: Some(2.0),
+            len: 2,
+        };
+        stats.insert("nan".to_string(), not_finite);
+        stats.insert("floats".to_string(), floats);
+        assert_eq!(
+            stats_json(&stats),
+            concat!(
+                r#"{"floats":{"len":2,"sum":2,"min":-0.5,"max":2.5},"#,
+                r#""nan":{"len":3,"sum":null,"min":null,"max":null}}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{now, Instant, QueueStats, Summable};
+use smallvec::SmallVec;
+use std::fmt;
+use std::time::Duration;
+
+/// Queue that stores up to `N` elements inline, without allocating
+/// memory in the heap, like a `SumQueue` with few elements.
+///
+/// It's useful to hold many short-lived queues with a handful of
+/// elements each, eg. a queue per key. When more than `N` elements are
+/// pushed, they are moved to the heap like in a `Vec`. Because the elements
+/// are stored contiguously, popping the oldest element takes linear time,
+/// so it's not meant for large queues, use a [`crate::SumQueue`] instead.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::InlineSumQueue;
+/// let mut queue: InlineSumQueue<i32, 4> = InlineSumQueue::new(Duration::from_secs(60));
+/// queue.push(1);
+/// queue.push(5);
+/// queue.push(2);
+/// assert_eq!(queue.stats().sum, Some(8));
+/// assert!(!queue.spilled());
+/// ```
+pub struct InlineSumQueue<T, const N: usize> {
+    queue: SmallVec<[(Instant, T); N]>,
+    max_age: Duration,
+}
+
+impl<T, const N: usize> InlineSumQueue<T, N> {
+    /// Creates an empty queue, where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> InlineSumQueue<T, N> {
+        InlineSumQueue {
+            queue: SmallVec::new(),
+            max_age: max_age_duration,
+        }
+    }
+
+    fn clear_oldest(&mut self, now: Instant) {
+        let max_age = self.max_age;
+        let expired = self
+            .queue
+            .partition_point(|(time, _)| now.saturating_duration_since(*time) > max_age);
+        if expired > 0 {
+            self.queue.drain(..expired);
+        }
+    }
+
+    /// Pushes an item at the back of the queue, and returns the length of
+    /// the queue. Before push the element, it also drops all expired elements.
+    pub fn push(&mut self, item: T) -> usize {
+        let now = now();
+        self.clear_oldest(now);
+        self.queue.push((now, item));
+        self.queue.len()
+    }
+
+    /// Removes the first item from the queue and returns it, or `None` if it
+    /// is empty. Before pop the element, it also drops all expired elements.
+    pub fn pop(&mut self) -> Option<T> {
+        self.clear_oldest(now());
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(self.queue.remove(0).1)
+    }
+
+    /// Returns the first item in the queue, or `None` if it is empty.
+    /// Before return the element, it also drops all expired elements.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.clear_oldest(now());
+        self.queue.first().map(|(_, value)| value)
+    }
+
+    /// Returns the length of the queue.
+    /// Before compute the length, it also drops all expired elements.
+    pub fn len(&mut self) -> usize {
+        self.clear_oldest(now());
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty.
+    /// Before check it, it also drops all expired elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of elements the queue can hold inline, that is `N`.
+    pub const fn inline_capacity(&self) -> usize {
+        N
+    }
+
+    /// Checks if the elements were moved to the heap, because
+    /// at some point the queue held more than `N` elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::InlineSumQueue;
+    /// let mut queue: InlineSumQueue<i32, 2> = InlineSumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert!(!queue.spilled());
+    /// queue.push(3);
+    /// assert!(queue.spilled());
+    /// ```
+    pub fn spilled(&self) -> bool {
+        self.queue.spilled()
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Drops all items.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Returns an iterator visiting all values in the queue, in
+    /// same order they were pushed.
+    /// Before return the iterator, it also drops all expired elements.
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        self.clear_oldest(now());
+        self.queue.iter().map(|(_, value)| value)
+    }
+}
+
+impl<T: Summable, const N: usize> InlineSumQueue<T, N> {
+    /// Get statistics of the queue, see [`crate::SumQueue::stats()`].
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    pub fn stats(&mut self) -> QueueStats<T> {
+        self.iter().fold(QueueStats::default(), |stats, &value| {
+            stats.merge(QueueStats {
+                min: Some(value),
+                max: Some(value),
+                sum: Some(value),
+                len: 1,
+            })
+        })
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for InlineSumQueue<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineSumQueue")
+            .field("max_age", &self.max_age)
+            .field("len", &self.queue.len())
+            .field("inline_capacity", &N)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InlineSumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn expire() {
+        let mut queue: InlineSumQueue<i32, 4> = InlineSumQueue::new(Duration::from_millis(100));
+        assert_eq!(queue.inline_capacity(), 4);
+        queue.push(1);
+        thread::sleep(Duration::from_millis(60));
+        queue.push(2);
+        assert_eq!(queue.peek(), Some(&1));
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn spilled() {
+        let mut queue: InlineSumQueue<i32, 3> = InlineSumQueue::new(Duration::from_secs(60));
+        for i in 0..5 {
+            queue.push(i);
+        }
+        assert!(queue.spilled());
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&0, &1, &2, &3, &4]);
+        let stats = queue.stats();
+        assert_eq!(stats.min, Some(0));
+        assert_eq!(stats.sum, Some(10));
+        queue.clear();
+        assert_eq!(queue.stats().len, 0);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{Instant, QueueElement, SumQueue};
+use std::collections::VecDeque;
+
+/// Checks that the elements in `range` of `elements`, and their
+/// neighbours, are sorted by their push times.
+fn assert_sorted<T>(name: &str, elements: &VecDeque<QueueElement<T>>, start: usize, end: usize) {
+    let start = start.saturating_sub(1);
+    let end = (end + 1).min(elements.len());
+    for i in start + 1..end {
+        assert!(
+            elements[i - 1].time <= elements[i].time,
+            "{} element {} pushed before the element {}",
+            name,
+            i,
+            i - 1
+        );
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Checks the internal consistency of the queue, panicking with the
+    /// invariant broken, if any: the elements are sorted by the time they
+    /// were pushed, the queue doesn't hold more elements than its max length,
+    /// and the indexes of the values, like the min and max kept with
+    /// [`SumQueue::with_min_max()`], are the same as if rebuilt from the elements.
+    ///
+    /// It's meant to catch bugs in the crate itself. In debug builds the
+    /// pushes check the order of the elements they push, but this method
+    /// checks all the elements and the indexes. It does nothing in release builds.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// queue.debug_assert_invariants();
+    /// ```
+    pub fn debug_assert_invariants(&self) {
+        if cfg!(debug_assertions) {
+            assert_sorted("expired", &self.expired, 0, self.expired.len());
+            assert_sorted("live", &self.queue, 0, self.queue.len());
+            self.assert_len();
+            if let Some(index) = &self.min_max {
+                index.assert_rebuilt(&self.queue);
+            }
+            if let Some(index) = &self.unique {
+                index.assert_rebuilt(&self.queue);
+            }
+        }
+    }
+
+    /// Checks the invariants around the elements pushed
+    /// from `start` to `end`, in debug builds.
+    pub(crate) fn debug_assert_pushed(&self, start: usize, end: usize) {
+        if cfg!(debug_assertions) {
+            assert_sorted("live", &self.queue, start, end);
+            self.assert_len();
+        }
+    }
+
+    /// Checks that no element is older than the max age
+    /// after the expired elements are dropped at `now`, in debug builds.
+    pub(crate) fn debug_assert_pruned(&self, now: Instant) {
+        if cfg!(debug_assertions) {
+            if let Some(el) = self.queue.front() {
+                let age = self.epoch.age(el.time, now);
+                assert!(age <= self.max_age, "live element expired {:?} ago", age);
+            }
+            if let Some(el) = self.expired.front() {
+                let age = self.epoch.age(el.time, now);
+                assert!(
+                    age <= self.max_age + self.grace,
+                    "expired element out of the grace period {:?} ago",
+                    age
+                );
+            }
+        }
+    }
+
+    fn assert_len(&self) {
+        if let Some(max_len) = self.max_len {
+            assert!(
+                self.queue.len() <= max_len,
+                "{} elements in a queue of max length {}",
+                self.queue.len(),
+                max_len
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, SumQueue};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn invariants() {
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(60))
+            .max_len(3)
+            .build();
+        let now = Instant::now();
+        queue.push_at(now - Duration::from_secs(3), 1).unwrap();
+        queue.push_at(now - Duration::from_secs(2), 2).unwrap();
+        queue.push(3);
+        queue.push_at(now - Duration::from_secs(1), 4).unwrap();
+        assert_eq!(queue.to_vec(), vec![2, 4, 3]);
+        queue.debug_assert_invariants();
+        queue.queue.swap(0, 2);
+        let broken = panic::catch_unwind(AssertUnwindSafe(|| queue.debug_assert_invariants()));
+        assert_eq!(broken.is_err(), cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn invariants_indexes() {
+        let clock = Clock::manual();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(10))
+            .max_len(5)
+            .clock(clock.clone())
+            .build()
+            .with_min_max();
+        for i in 0..50u32 {
+            match i % 7 {
+                0 => drop(queue.pop()),
+                1 => clock.advance(Duration::from_secs(3)),
+                _ => drop(queue.push_unique(i * 31 % 11)),
+            }
+            queue.min();
+            queue.debug_assert_invariants();
+        }
+        // changed without marking the indexes as stale
+        queue.queue[0].value += 100;
+        let broken = panic::catch_unwind(AssertUnwindSafe(|| queue.debug_assert_invariants()));
+        assert_eq!(broken.is_err(), cfg!(debug_assertions));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::nan::is_nan;
+use crate::SumQueue;
+
+/// Elements of a queue with the min and max key,
+/// see [`SumQueue::stats_by_key()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyStats<'a, T> {
+    /// element with the min key
+    pub min: Option<&'a T>,
+    /// element with the max key
+    pub max: Option<&'a T>,
+    /// size of the queue, same than [`SumQueue::len()`]
+    pub len: usize,
+}
+
+impl<T> SumQueue<T> {
+    /// Returns the elements with the min and max key computed by `key`,
+    /// eg. the whole record of the fastest and the slowest request, not
+    /// only their latency. With many elements with the same key, the
+    /// oldest one is returned. Keys not comparable, like `NaN`, are skipped.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut requests = SumQueue::new(Duration::from_secs(60));
+    /// requests.push(("/", 0.12));
+    /// requests.push(("/login", 0.45));
+    /// requests.push(("/about", 0.03));
+    /// let stats = requests.stats_by_key(|&(_, latency)| latency);
+    /// assert_eq!(stats.min, Some(&("/about", 0.03)));
+    /// assert_eq!(stats.max, Some(&("/login", 0.45)));
+    /// assert_eq!(stats.len, 3);
+    /// ```
+    pub fn stats_by_key<K, F>(&mut self, mut key: F) -> KeyStats<'_, T>
+    where
+        K: PartialOrd + Clone,
+        F: FnMut(&T) -> K,
+    {
+        self.clear_oldest(self.now());
+        let mut min: Option<(K, &T)> = None;
+        let mut max: Option<(K, &T)> = None;
+        for el in self.queue.iter() {
+            let k = key(&el.value);
+            if is_nan(&k) {
+                continue;
+            }
+            if min.as_ref().is_none_or(|(m, _)| k < *m) {
+                min = Some((k.clone(), &el.value));
+            }
+            if max.as_ref().is_none_or(|(m, _)| k > *m) {
+                max = Some((k, &el.value));
+            }
+        }
+        KeyStats {
+            min: min.map(|(_, value)| value),
+            max: max.map(|(_, value)| value),
+            len: self.queue.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn stats_by_key() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        let stats = queue.stats_by_key(|s: &&str| s.len());
+        assert_eq!((stats.min, stats.max, stats.len), (None, None, 0));
+        queue.push("a");
+        thread::sleep(Duration::from_millis(150));
+        queue.push("bb");
+        queue.push("ccc");
+        queue.push("dd");
+        queue.push("eee");
+        let stats = queue.stats_by_key(|s| s.len());
+        // the oldest element with the same key is returned
+        assert_eq!(stats.min, Some(&"bb"));
+        assert_eq!(stats.max, Some(&"ccc"));
+        assert_eq!(stats.len, 4);
+        let stats = queue.stats_by_key(|s| if *s == "ccc" { f64::NAN } else { 1.0 });
+        assert_eq!((stats.min, stats.max), (Some(&"bb"), Some(&"bb")));
+    }
+}
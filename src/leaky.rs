@@ -0,0 +1,201 @@
+use crate::{check_max_age, now, Instant, SumQueue};
+use std::fmt;
+use std::time::Duration;
+
+/// Queue that smooths bursts of elements, letting them out
+/// ("leaking") at a constant rate of one element every `leak_every`.
+///
+/// Elements are pushed at any rate, up to `capacity` elements waiting
+/// in the bucket, after that the new elements overflow and are rejected.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::LeakyBucket;
+///
+/// let mut bucket = LeakyBucket::new(2, Duration::from_secs(1));
+/// assert_eq!(bucket.push("a"), Ok(1));
+/// assert_eq!(bucket.push("b"), Ok(2));
+/// assert_eq!(bucket.push("c"), Err("c"));
+/// assert_eq!(bucket.overflows(), 1);
+///
+/// // the first element leaks right away, the next one a second later
+/// assert_eq!(bucket.leak(), Some("a"));
+/// assert_eq!(bucket.leak(), None);
+/// assert_eq!(bucket.fill_level(), 1);
+/// ```
+pub struct LeakyBucket<T> {
+    queue: SumQueue<T>,
+    capacity: usize,
+    leak_every: Duration,
+    /// time when the next element can leak
+    next_leak: Option<Instant>,
+    overflows: u64,
+}
+
+impl<T> LeakyBucket<T> {
+    /// Creates an empty bucket that holds up to `capacity` elements,
+    /// and lets out one element every `leak_every`.
+    pub fn new(capacity: usize, leak_every: Duration) -> LeakyBucket<T> {
+        LeakyBucket {
+            queue: SumQueue::with_capacity(Duration::MAX, capacity),
+            capacity,
+            leak_every,
+            next_leak: None,
+            overflows: 0,
+        }
+    }
+
+    /// Sets the max time the elements can wait in the bucket, after that
+    /// they expire like in a [`SumQueue`], without leaking. By default the
+    /// elements wait as long as needed.
+    ///
+    /// It panics if `max_wait` is longer than [`crate::MAX_AGE_LIMIT`].
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::LeakyBucket;
+    /// let mut bucket = LeakyBucket::new(10, Duration::from_secs(1))
+    ///     .max_wait(Duration::from_millis(100));
+    /// bucket.push(1).unwrap();
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert_eq!(bucket.leak(), None);
+    /// assert_eq!(bucket.fill_level(), 0);
+    /// ```
+    pub fn max_wait(mut self, max_wait: Duration) -> LeakyBucket<T> {
+        check_max_age(max_wait, Duration::ZERO);
+        self.queue.max_age = max_wait;
+        self
+    }
+
+    /// Returns the max number of elements the bucket can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the time between two elements leaking.
+    pub fn leak_every(&self) -> Duration {
+        self.leak_every
+    }
+
+    /// Returns the number of elements waiting in the bucket.
+    pub fn fill_level(&mut self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the number of elements rejected because the bucket was full.
+    pub fn overflows(&self) -> u64 {
+        self.overflows
+    }
+
+    /// Pushes an element into the bucket, returning the fill level,
+    /// or the same element back as `Err` if the bucket is full.
+    pub fn push(&mut self, item: T) -> Result<usize, T> {
+        if self.queue.len() >= self.capacity {
+            self.overflows += 1;
+            return Err(item);
+        }
+        Ok(self.queue.push(item))
+    }
+
+    /// Removes the oldest element from the bucket if it's time for an
+    /// element to leak, otherwise returns `None`.
+    ///
+    /// Time without elements is not accumulated, so after the bucket
+    /// is empty for a while only one element leaks right away.
+    pub fn leak(&mut self) -> Option<T> {
+        let now = now();
+        if self.next_leak.is_some_and(|next_leak| now < next_leak) {
+            return None;
+        }
+        let item = self.queue.pop()?;
+        self.next_leak = Some(match self.next_leak {
+            // keep the pace if the element was taken on time
+            Some(next_leak) if now - next_leak < self.leak_every => next_leak + self.leak_every,
+            _ => now + self.leak_every,
+        });
+        Some(item)
+    }
+
+    /// Returns the time until the next element can leak, zero if it can leak
+    /// now, or `None` if the bucket is empty.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::LeakyBucket;
+    /// let mut bucket = LeakyBucket::new(10, Duration::from_millis(100));
+    /// bucket.push(1).unwrap();
+    /// bucket.push(2).unwrap();
+    /// while let Some(wait) = bucket.next_leak_in() {
+    ///     thread::sleep(wait);
+    ///     if let Some(item) = bucket.leak() {
+    ///         println!("{}", item);
+    ///     }
+    /// }
+    /// ```
+    pub fn next_leak_in(&mut self) -> Option<Duration> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(match self.next_leak {
+            Some(next_leak) => next_leak.saturating_duration_since(now()),
+            None => Duration::ZERO,
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LeakyBucket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeakyBucket")
+            .field("capacity", &self.capacity)
+            .field("leak_every", &self.leak_every)
+            .field("overflows", &self.overflows)
+            .field("queue", &self.queue)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LeakyBucket;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn leak() {
+        let mut bucket = LeakyBucket::new(3, Duration::from_millis(50));
+        assert_eq!(bucket.capacity(), 3);
+        assert_eq!(bucket.leak_every(), Duration::from_millis(50));
+        assert_eq!(bucket.next_leak_in(), None);
+        for i in 0..5 {
+            let _ = bucket.push(i);
+        }
+        assert_eq!(bucket.overflows(), 2);
+        assert_eq!(bucket.fill_level(), 3);
+        assert_eq!(bucket.next_leak_in(), Some(Duration::ZERO));
+        assert_eq!(bucket.leak(), Some(0));
+        assert_eq!(bucket.leak(), None);
+        assert!(bucket.next_leak_in().unwrap() > Duration::ZERO);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(bucket.leak(), Some(1));
+        assert_eq!(bucket.leak(), None);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(bucket.leak(), Some(2));
+        assert_eq!(bucket.leak(), None);
+        assert_eq!(bucket.push(5), Ok(1));
+    }
+
+    #[test]
+    fn idle() {
+        let mut bucket = LeakyBucket::new(3, Duration::from_millis(50));
+        bucket.push(1).unwrap();
+        assert_eq!(bucket.leak(), Some(1));
+        thread::sleep(Duration::from_millis(200));
+        bucket.push(2).unwrap();
+        bucket.push(3).unwrap();
+        // the idle time doesn't allow a burst
+        assert_eq!(bucket.leak(), Some(2));
+        assert_eq!(bucket.leak(), None);
+    }
+}
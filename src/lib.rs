@@ -29,7 +29,7 @@
 //! queue.push(5);
 //! queue.push(2);
 //! // Elements can be iterated as many times as you want
-//! println!("heap data: {:?}", queue.iter().collect::<Vec<_>>());  // [1, 5, 2]
+//! println!("queue data: {:?}", queue.iter().collect::<Vec<_>>()); // [1, 5, 2]
 //!
 //! // Check stats
 //! let stats = queue.stats();
@@ -60,8 +60,10 @@
 //!
 //! ## Implementation
 //!
-//! Underneath uses a [`BinaryHeap`] struct to keep the values,
-//! and implements the same methods: `push()`, `pop()`, `peek()` ...
+//! Underneath uses a [`VecDeque`] struct to keep the values
+//! in the same order they were pushed, so the oldest elements
+//! are always at the front of the queue, and implements similar
+//! methods: `push()`, `pop()`, `peek()` ...
 //! although worth to note that the implementations of the `SumQueue` type take mutable
 //! ownership of the `self` reference (eg. `peek(&mut self) -> Option<&T>`). That is
 //! because the cleaning of the expired elements of the queue occurs each time
@@ -76,16 +78,147 @@
 //! those queues to push, pop or get the stats of them. In that case you can at least
 //! try to call often to the `len()` method to force the unused queues to remove and
 //! deallocate the expired elements.
+//!
+//! Or keep the queues in a [`QueueRegistry`], that can drop the expired elements
+//! of all its queues at once with [`QueueRegistry::prune_all()`], or periodically
+//! in a background thread.
+
+#[cfg(feature = "derive")]
+extern crate self as sum_queue;
+
+use alarm::Alarm;
+use decay::DecayFn;
+use depth::DepthHistory;
+use minmax::MinMaxIndex;
+use observer::Observers;
+use prune::PrunePolicy;
+use residence::ResidenceHistory;
+use stamp::{check_max_age, Epoch, Stamp};
+use std::collections::vec_deque;
+use std::collections::VecDeque;
+use std::fmt;
+use std::iter::Peekable;
+use std::ops::{Add, Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+use unique::UniqueIndex;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+mod alarm;
+mod average;
+#[cfg(feature = "bench-utils")]
+pub mod bench;
+mod bloom;
+mod builder;
+mod clock;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
+mod correlation;
+mod counter;
+mod countmin;
+mod deadline;
+mod debounce;
+mod decay;
+mod depth;
+#[cfg(feature = "tdigest")]
+mod digest;
+mod distinct;
+mod duration;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+#[cfg(feature = "events")]
+mod events;
+mod fields;
+#[cfg(feature = "ordered-float")]
+mod float;
+mod fold;
+mod group;
+#[cfg(feature = "hdrhistogram")]
+mod histogram;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "smallvec")]
+mod inline;
+mod invariants;
+mod keyed;
+mod leaky;
+mod limiter;
+mod lock;
+mod map;
+mod minmax;
+mod nan;
+mod observer;
+#[cfg(feature = "rayon")]
+mod par;
+mod priority;
+mod prune;
+#[cfg(feature = "python")]
+pub mod python;
+mod registry;
+mod render;
+mod residence;
+mod sample;
+mod score;
+mod sharded;
+mod simulation;
+mod sliding;
+mod stamp;
+pub mod sync;
+mod topk;
+mod unique;
 
-use std::cmp::Ordering;
-use std::collections::binary_heap;
-use std::collections::BinaryHeap;
-use std::ops::Add;
-use std::time::{Duration, Instant};
+pub use average::{AsF64, MovingAverage};
+pub use bloom::WindowedBloom;
+pub use builder::SumQueueBuilder;
+pub use clock::Clock;
+pub use counter::{RateMeter, SlidingCounter};
+pub use countmin::WindowedCountMin;
+pub use deadline::DeadlineQueue;
+pub use debounce::{Debouncer, Throttler};
+pub use depth::DepthStats;
+#[cfg(feature = "tdigest")]
+pub use digest::{WindowedDigest, DEFAULT_COMPRESSION};
+pub use distinct::{WindowedDistinct, DEFAULT_PRECISION};
+pub use duration::DurationStats;
+#[cfg(feature = "events")]
+pub use events::QueueEvent;
+pub use fields::FieldStats;
+pub use group::{GroupStats, QueueGroup};
+#[cfg(feature = "hdrhistogram")]
+pub use histogram::WindowedHistogram;
+#[cfg(feature = "http")]
+pub use http::{serve_stats, serve_stats_on};
+#[cfg(feature = "smallvec")]
+pub use inline::InlineSumQueue;
+pub use keyed::KeyStats;
+pub use leaky::LeakyBucket;
+pub use limiter::{Limiter, RateLimiter, TokenBucket};
+pub use map::SumMap;
+pub use minmax::MovingMinMax;
+pub use nan::{NanError, NanPolicy};
+pub use observer::QueueObserver;
+#[cfg(feature = "ordered-float")]
+pub use ordered_float::OrderedFloat;
+#[cfg(feature = "rayon")]
+pub use par::ParIter;
+pub use priority::PrioritySumQueue;
+pub use registry::QueueRegistry;
+pub use residence::ResidenceStats;
+pub use sharded::ShardedSumQueue;
+pub use simulation::{SimulatedQueue, Step, StepResult};
+pub use sliding::SlidingSum;
+pub use stamp::MAX_AGE_LIMIT;
+#[cfg(feature = "derive")]
+pub use sum_queue_derive::FieldStats;
+pub use topk::TopK;
 
 /// Internal element used by `SumQueue` to hold the values.
+#[derive(Clone)]
 struct QueueElement<T> {
-    time: Instant,
+    time: Stamp,
     value: T,
 }
 
@@ -133,37 +266,129 @@ struct QueueElement<T> {
 /// assert_eq!(stats.sum, Some(6));
 /// assert_eq!(stats.len, 3);
 /// ```
-pub struct QueueStats<T: Ord + Add<Output = T>> {
+///
+/// The **sum** can be accumulated in a wider type than the values,
+/// so it doesn't overflow, see [`SumQueue::stats_widened()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStats<T, A = T> {
     /// min value of the queue
     pub min: Option<T>,
     /// max value of the queue
     pub max: Option<T>,
     /// sum of all the values in the queue
-    pub sum: Option<T>,
+    pub sum: Option<A>,
     /// size of the queue, same than [`SumQueue::len()`]
     pub len: usize,
 }
 
-impl<T> PartialEq for QueueElement<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.time == other.time
+impl<T: Copy + PartialOrd, A: From<T> + Add<Output = A>> QueueStats<T, A> {
+    /// Updates the **min**, **max** and **sum** with the value,
+    /// used by the code generated by `#[derive(FieldStats)]`.
+    #[doc(hidden)]
+    pub fn push_value(&mut self, value: T) {
+        if self.min.is_none() || Some(value) < self.min {
+            self.min = Some(value);
+        }
+        if self.max.is_none() || Some(value) > self.max {
+            self.max = Some(value);
+        }
+        self.sum = Some(match self.sum.take() {
+            Some(s) => s + A::from(value),
+            None => A::from(value),
+        });
+    }
+}
+
+/// Stats of an empty queue: no **min**, **max** and **sum**
+/// values, and a **length** of `0`.
+impl<T, A> Default for QueueStats<T, A> {
+    fn default() -> Self {
+        QueueStats {
+            min: None,
+            max: None,
+            sum: None,
+            len: 0,
+        }
     }
 }
-impl<T> Eq for QueueElement<T> {}
 
-impl<T> Ord for QueueElement<T> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        //! Reverse order to set lower number higher
-        other.time.cmp(&self.time)
+impl<T: PartialOrd, A: Add<Output = A>> QueueStats<T, A> {
+    /// Merges the stats of two groups of elements, eg. from two
+    /// different queues, returning the stats of all the elements together.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let mut other = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(-1);
+    /// queue.push(5);
+    /// other.push(10);
+    /// let stats = queue.stats().merge(other.stats());
+    /// assert_eq!(stats.min, Some(-1));
+    /// assert_eq!(stats.max, Some(10));
+    /// assert_eq!(stats.sum, Some(14));
+    /// assert_eq!(stats.len, 3);
+    /// ```
+    pub fn merge(self, other: QueueStats<T, A>) -> QueueStats<T, A> {
+        fn pick<T>(a: Option<T>, b: Option<T>, f: fn(T, T) -> T) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, None) => a,
+                (None, b) => b,
+            }
+        }
+        QueueStats {
+            min: pick(self.min, other.min, |a, b| if b < a { b } else { a }),
+            max: pick(self.max, other.max, |a, b| if b > a { b } else { a }),
+            sum: pick(self.sum, other.sum, Add::add),
+            len: self.len + other.len,
+        }
     }
 }
 
-impl<T> PartialOrd for QueueElement<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Values of the elements that the stats of a queue can be computed of,
+/// implemented for all the types that are `Copy`, `Ord` and `Add`, like
+/// the integer types and [`Duration`].
+///
+/// With the `num-traits` feature, it's implemented for all the types that
+/// are `Copy`, `PartialOrd` and `Add` instead, so the floats, and any other
+/// numeric type of the `num-traits` ecosystem, like fixed-point or wrapper
+/// types, work with the stats as well. How the `NaN` values affect the
+/// stats is set with [`SumQueue::with_nan_policy()`].
+///
+/// ```
+/// # #[cfg(feature = "num-traits")]
+/// # {
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push(1.5);
+/// queue.push(-0.5);
+/// let stats = queue.stats();
+/// assert_eq!(stats.min, Some(-0.5));
+/// assert_eq!(stats.sum_or_zero(), 1.0);
+/// # }
+/// ```
+pub trait Summable: Copy + PartialOrd + Add<Output = Self> {}
+
+#[cfg(not(feature = "num-traits"))]
+impl<T: Copy + Ord + Add<Output = T>> Summable for T {}
+
+#[cfg(feature = "num-traits")]
+impl<T: Copy + PartialOrd + Add<Output = T>> Summable for T {}
+
+#[cfg(feature = "num-traits")]
+impl<T, A: Clone + num_traits::Zero> QueueStats<T, A> {
+    /// Returns the **sum** of the values, or zero if there are no values.
+    pub fn sum_or_zero(&self) -> A {
+        self.sum.clone().unwrap_or_else(A::zero)
     }
 }
 
+/// Max age of the elements in a queue created with [`SumQueue::default()`].
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60);
+
 fn now() -> Instant {
     Instant::now()
 }
@@ -183,42 +408,214 @@ fn now() -> Instant {
 /// // Create with 500 milliseconds expiration and an initial capacity of 20 elements
 /// queue = SumQueue::with_capacity(Duration::from_millis(500), 20);
 /// ```
+///
+/// If the elements implement `Clone`, the queue can be cloned as well,
+/// the copy keeps the same elements with the same ages, so they
+/// expire in both queues at the same time:
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+///
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push(1);
+/// let mut snapshot = queue.clone();
+/// queue.push(2);
+/// assert_eq!(snapshot.iter().collect::<Vec<_>>(), vec![&1]);
+/// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &2]);
+/// ```
+///
+/// The elements are stored inline in the slots of a [`VecDeque`] ring
+/// buffer, so the new elements reuse the slots of the popped and expired
+/// ones without an allocation per element, and memory is only allocated
+/// when the queue grows beyond its capacity, see [`SumQueue::with_capacity()`].
+///
+/// The time each element was pushed is stored in 4 bytes, as the
+/// milliseconds elapsed since an epoch of the queue, set on the first push
+/// and moved forward when a new time doesn't fit in the 4 bytes, that span
+/// about 49.7 days. So the elements can be kept up to [`MAX_AGE_LIMIT`],
+/// adding up the max age and the grace period, and the ages are rounded up
+/// to the millisecond, so an element can expire up to a millisecond early.
+#[derive(Clone)]
 pub struct SumQueue<T> {
-    /// the heap with the data
-    heap: BinaryHeap<QueueElement<T>>,
+    /// the elements of the queue, from
+    /// the oldest to the newest
+    queue: VecDeque<QueueElement<T>>,
+    /// instant the times of the elements
+    /// are counted from.
+    epoch: Epoch,
     /// max time the elements will
     /// live in the queue.
     max_age: Duration,
+    /// max number of elements the queue
+    /// can hold, the oldest are dropped
+    /// to make room for the new ones.
+    max_len: Option<usize>,
+    /// min time the elements have to be
+    /// in the queue before they can be popped.
+    min_age: Duration,
+    /// time the expired elements are kept
+    /// for the queries of the past.
+    grace: Duration,
+    /// the elements expired within the grace
+    /// period, from the oldest to the newest.
+    expired: VecDeque<QueueElement<T>>,
+    /// lengths of the queue recorded
+    /// after each push and pop, if any.
+    depth: Option<DepthHistory>,
+    /// times the elements popped were
+    /// in the queue, if recorded.
+    residence: Option<ResidenceHistory>,
+    /// elements that can be the min
+    /// and max, if kept up to date.
+    min_max: Option<MinMaxIndex<T>>,
+    /// elements by the hash of their values,
+    /// if searched with `push_unique()`.
+    unique: Option<UniqueIndex<T>>,
+    /// function called with the elements
+    /// dropped by the queue.
+    on_expire: Option<ExpireCallback<T>>,
+    /// thresholds evaluated after each push.
+    alarms: Vec<Alarm<T>>,
+    /// function that computes the value
+    /// of the elements from their age.
+    decay: Option<DecayFn<T>>,
+    /// how the `NaN` values affect the stats.
+    nan_policy: NanPolicy,
+    /// observers of the elements pushed,
+    /// popped and expired.
+    observers: Observers<T>,
+    /// source of the time.
+    clock: Clock,
+    /// how often the expired elements are
+    /// dropped, if not on each operation.
+    prune: Option<PrunePolicy>,
 }
 
+/// Function called with each element dropped by the queue.
+type ExpireCallback<T> = Arc<dyn Fn(T) + Send + Sync>;
+
 impl<T> SumQueue<T> {
     /// Creates an empty `SumQueue`, where the elements inside
     /// will live `max_age_duration` at maximum.
-    pub fn new(max_age_duration: Duration) -> SumQueue<T> {
+    ///
+    /// It's a `const fn`, so queues can be created in constant
+    /// expressions as well:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// const QUEUE: SumQueue<i32> = SumQueue::new(Duration::from_secs(5));
+    /// let mut queue = QUEUE;
+    /// queue.push(1);
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    ///
+    /// It panics if `max_age_duration` is longer than [`MAX_AGE_LIMIT`],
+    /// unless it's `Duration::MAX`, so the elements never expire.
+    pub const fn new(max_age_duration: Duration) -> SumQueue<T> {
+        check_max_age(max_age_duration, Duration::ZERO);
         SumQueue {
-            heap: BinaryHeap::<QueueElement<T>>::new(),
+            queue: VecDeque::new(),
+            epoch: Epoch::new(),
             max_age: max_age_duration,
+            max_len: None,
+            min_age: Duration::ZERO,
+            grace: Duration::ZERO,
+            expired: VecDeque::new(),
+            depth: None,
+            residence: None,
+            min_max: None,
+            unique: None,
+            on_expire: None,
+            alarms: Vec::new(),
+            decay: None,
+            nan_policy: NanPolicy::Propagate,
+            observers: Vec::new(),
+            clock: Clock::system(),
+            prune: None,
         }
     }
 
     /// Creates an empty `SumQueue` with a specific initial capacity.
     /// This preallocates enough memory for `capacity` elements,
-    /// so that the [`BinaryHeap`] inside the `SumQueue` does not have
+    /// so that the [`VecDeque`] inside the `SumQueue` does not have
     /// to be reallocated until it contains at least that many values.
-    /// The elements inside the queue will live `max_age_duration` time at maximum.
+    /// The elements inside the queue will live `max_age_duration` time at maximum,
+    /// that can't be longer than [`MAX_AGE_LIMIT`], like with [`SumQueue::new()`].
     pub fn with_capacity(max_age_duration: Duration, capacity: usize) -> SumQueue<T> {
+        check_max_age(max_age_duration, Duration::ZERO);
         SumQueue {
-            heap: BinaryHeap::<QueueElement<T>>::with_capacity(capacity),
+            queue: VecDeque::with_capacity(capacity),
+            epoch: Epoch::new(),
             max_age: max_age_duration,
+            max_len: None,
+            min_age: Duration::ZERO,
+            grace: Duration::ZERO,
+            expired: VecDeque::new(),
+            depth: None,
+            residence: None,
+            min_max: None,
+            unique: None,
+            on_expire: None,
+            alarms: Vec::new(),
+            decay: None,
+            nan_policy: NanPolicy::Propagate,
+            observers: Vec::new(),
+            clock: Clock::system(),
+            prune: None,
+        }
+    }
+
+    /// Creates a [`SumQueueBuilder`] to configure a new `SumQueue`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue: SumQueue<i32> = SumQueue::builder()
+    ///     .max_age(Duration::from_secs(60))
+    ///     .capacity(64)
+    ///     .max_len(1000)
+    ///     .on_expire(|value| println!("{} expired", value))
+    ///     .build();
+    /// assert_eq!(queue.max_len(), Some(1000));
+    /// ```
+    pub fn builder() -> SumQueueBuilder<T> {
+        SumQueueBuilder::new()
+    }
+
+    /// Creates an empty queue with the same configuration than `self`.
+    fn empty_like(&self, queue: VecDeque<QueueElement<T>>) -> SumQueue<T> {
+        SumQueue {
+            queue,
+            epoch: self.epoch,
+            max_age: self.max_age,
+            max_len: self.max_len,
+            min_age: self.min_age,
+            grace: self.grace,
+            expired: VecDeque::new(),
+            depth: self.depth.as_ref().map(DepthHistory::empty_like),
+            residence: self.residence.as_ref().map(ResidenceHistory::empty_like),
+            min_max: self.min_max.as_ref().map(MinMaxIndex::empty_like),
+            unique: self.unique.as_ref().map(UniqueIndex::empty_like),
+            on_expire: self.on_expire.clone(),
+            alarms: self.alarms.clone(),
+            decay: self.decay.clone(),
+            nan_policy: self.nan_policy,
+            observers: self.observers.clone(),
+            clock: self.clock.clone(),
+            prune: self.prune.clone(),
         }
     }
 
-    /// Pushes an item onto the heap of the queue.
+    /// Pushes an item at the back of the queue.
     ///
-    /// See [`BinaryHeap::push`] to known more about the time complexity.
+    /// See [`VecDeque::push_back`] to known more about the time complexity.
     ///
-    /// It returns the size of the queue, and before the element is pushed to the heap,
-    /// it also drops all expired elements in the queue.
+    /// It returns the size of the queue, and before the element is pushed to the queue,
+    /// it also drops all expired elements in the queue. If the queue was
+    /// configured with a max length, the oldest elements are
+    /// dropped as well to make room for the new element.
     ///
     /// ```
     /// use std::time::Duration;
@@ -230,43 +627,427 @@ impl<T> SumQueue<T> {
     /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &5, &2]);
     /// ```
     pub fn push(&mut self, item: T) -> usize {
-        let now = now();
+        let now = self.now();
         self.clear_oldest(now);
-        self.heap.push(QueueElement {
-            time: now,
-            value: item,
-        });
-        self.heap.len()
+        self.notify_pushed(&item);
+        let time = self.stamp(now);
+        self.queue.push_back(QueueElement { time, value: item });
+        self.indexes_pushed();
+        self.truncate_to_max_len();
+        self.debug_assert_pushed(self.queue.len() - 1, self.queue.len());
+        self.record_depth();
+        self.check_alarms();
+        self.queue.len()
+    }
+
+    /// Pushes an item as if it was pushed at the instant `at`, eg. an
+    /// event parsed from a log with its own timestamp, so it expires based
+    /// on when it happened and not when it was pushed.
+    ///
+    /// The item is inserted among the other elements in the order of their
+    /// push times, so it takes linear time unless `at` is the newest. Instants
+    /// in the future are clamped to now.
+    ///
+    /// It returns the size of the queue, or the same item back as `Err` if it's
+    /// already older than the max age of the queue. Before the element is pushed,
+    /// it also drops all expired elements in the queue. If the queue was
+    /// configured with a max length, the oldest elements are dropped as well.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let now = Instant::now();
+    /// queue.push(1);
+    /// assert_eq!(queue.push_at(now - Duration::from_secs(10), 5), Ok(2));
+    /// assert_eq!(queue.push_at(now - Duration::from_secs(90), 2), Err(2));
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &1]);
+    /// ```
+    pub fn push_at(&mut self, at: Instant, item: T) -> Result<usize, T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        if now.saturating_duration_since(at) > self.max_age {
+            return Err(item);
+        }
+        let time = self.stamp(at.min(now));
+        let index = self.queue.partition_point(|el| el.time <= time);
+        self.notify_pushed(&item);
+        self.queue.insert(index, QueueElement { time, value: item });
+        self.indexes_changed();
+        let dropped = self.truncate_to_max_len();
+        self.debug_assert_pushed(
+            index.saturating_sub(dropped),
+            (index + 1).saturating_sub(dropped),
+        );
+        self.record_depth();
+        self.check_alarms();
+        Ok(self.queue.len())
+    }
+
+    /// Checks if the queue reached its max length, if any,
+    /// without dropping the expired elements.
+    #[cfg(feature = "async")]
+    fn is_full(&self) -> bool {
+        self.max_len
+            .is_some_and(|max_len| self.queue.len() >= max_len)
+    }
+
+    /// Drops the oldest elements beyond the max length, if any,
+    /// and returns how many were dropped.
+    fn truncate_to_max_len(&mut self) -> usize {
+        let len = self.queue.len();
+        if let Some(max_len) = self.max_len {
+            while self.queue.len() > max_len {
+                self.drop_front();
+            }
+        }
+        len - self.queue.len()
+    }
+
+    /// Drops the oldest element, passing it to the expiration callback.
+    fn drop_front(&mut self) {
+        self.indexes_popping();
+        if let Some(el) = self.queue.pop_front() {
+            self.expire(el.value);
+        }
+    }
+
+    /// Adds the element just pushed at the back to the indexes
+    /// of the values, like the one of [`SumQueue::with_min_max()`].
+    fn indexes_pushed(&mut self) {
+        self.min_max_pushed();
+        self.unique_pushed();
+    }
+
+    /// Removes the first element, about to be removed from
+    /// the queue, from the indexes of the values.
+    fn indexes_popping(&mut self) {
+        if !self.queue.is_empty() {
+            self.min_max_popping();
+            self.unique_popping();
+        }
+    }
+
+    /// Marks the indexes of the values as stale, after the elements
+    /// were changed other than at the ends of the queue.
+    fn indexes_changed(&mut self) {
+        self.min_max_changed();
+        self.unique_changed();
+    }
+
+    /// Drops the `n` oldest elements, passing them to the expiration
+    /// callback, and records the new length of the queue.
+    fn drop_oldest(&mut self, n: usize) {
+        for _ in 0..n {
+            self.drop_front();
+        }
+        self.record_depth();
+    }
+
+    /// Checks if the oldest element is old enough to be popped at `now`,
+    /// see [`SumQueue::with_min_age()`].
+    fn front_ready(&self, now: Instant) -> bool {
+        self.queue
+            .front()
+            .is_some_and(|q_element| self.epoch.age(q_element.time, now) >= self.min_age)
+    }
+
+    /// Number of elements old enough to be popped at `now`.
+    fn ready_len(&self, now: Instant) -> usize {
+        self.queue
+            .partition_point(|q_element| self.epoch.age(q_element.time, now) >= self.min_age)
+    }
+
+    /// Index of the first element not expired at `now`.
+    fn live_start(&self, now: Instant) -> usize {
+        self.queue
+            .partition_point(|el| self.epoch.age(el.time, now) > self.max_age)
     }
 
-    fn clear_oldest(&mut self, now: Instant) {
-        while let Some(el) = self.heap.peek() {
-            let peek_age = now - el.time;
+    /// Drops the expired elements at `now`, or moves them to the
+    /// expired ones if the queue has a grace period.
+    fn drop_expired(&mut self, now: Instant) {
+        while let Some(el) = self.queue.front() {
+            let peek_age = self.epoch.age(el.time, now);
             if peek_age > self.max_age {
-                self.heap.pop();
+                if self.grace.is_zero() {
+                    self.drop_front();
+                } else {
+                    self.indexes_popping();
+                    if let Some(el) = self.queue.pop_front() {
+                        self.expired.push_back(el);
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+        while let Some(el) = self.expired.front() {
+            if self.epoch.age(el.time, now) > self.max_age + self.grace {
+                if let Some(el) = self.expired.pop_front() {
+                    self.expire(el.value);
+                }
             } else {
                 break;
             }
         }
     }
 
-    /// Drops all items.
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// The elements keep the time they were pushed into `other`, so they
+    /// expire in `self` considering the time they were already living in
+    /// the other queue, but using the max age of `self`.
+    ///
+    /// It returns the size of the queue after the elements are moved, and
+    /// before return it also drops all expired elements in the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let mut other = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// other.push(2);
+    /// other.push(3);
+    /// assert_eq!(queue.append(&mut other), 3);
+    /// assert_eq!(queue.pop_n(3), vec![1, 2, 3]);
+    /// assert!(other.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut SumQueue<T>) -> usize {
+        let epoch = other.epoch;
+        let items = std::mem::take(&mut other.queue)
+            .into_iter()
+            .map(|el| (epoch.instant(el.time), el.value))
+            .collect();
+        let others = self.stamp_sorted(items);
+        self.merge(others);
+        self.len()
+    }
+
+    /// Pushes all the items of `iter` as if each one was pushed at the
+    /// instant it comes with, eg. to rebuild the window from a log or
+    /// a snapshot, see [`SumQueue::push_at()`].
+    ///
+    /// The items don't need to be in order, they are sorted by their
+    /// instants (keeping the order of the items with the same instant)
+    /// and merged with the elements of the queue in one pass. Instants
+    /// in the future are clamped to now, and the items already older
+    /// than the max age of the queue are skipped.
+    ///
+    /// It returns the size of the queue after the items are pushed, and
+    /// before return it also drops all expired elements in the queue.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let now = Instant::now();
+    /// queue.push(1);
+    /// let history = vec![
+    ///     (now - Duration::from_secs(10), 3),
+    ///     (now - Duration::from_secs(90), 0),
+    ///     (now - Duration::from_secs(30), 2),
+    /// ];
+    /// assert_eq!(queue.extend_with_times(history), 3);
+    /// assert_eq!(queue.to_vec(), vec![2, 3, 1]);
+    /// ```
+    pub fn extend_with_times<I>(&mut self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = (Instant, T)>,
+    {
+        let now = self.now();
+        self.clear_oldest(now);
+        let max_age = self.max_age;
+        let mut others = iter
+            .into_iter()
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= max_age)
+            .map(|(at, value)| (at.min(now), value))
+            .collect::<Vec<_>>();
+        others.sort_by_key(|(at, _)| *at);
+        let others = self.stamp_sorted(others);
+        self.merge(others);
+        self.len()
+    }
+
+    /// Moves all the elements of `others`, sorted by their
+    /// push times, into the queue keeping the order.
+    fn merge(&mut self, mut others: VecDeque<QueueElement<T>>) {
+        let newest = self.queue.back().map(|el| el.time);
+        let start = if others
+            .front()
+            .is_none_or(|el| newest.is_none_or(|newest| el.time >= newest))
+        {
+            // all the elements are newer, so they are pushed
+            // at the back reusing the memory of the queue
+            let start = self.queue.len();
+            for el in others {
+                self.notify_pushed(&el.value);
+                self.queue.push_back(el);
+                self.indexes_pushed();
+            }
+            start
+        } else {
+            let mut mine = std::mem::take(&mut self.queue).into_iter().peekable();
+            self.queue.reserve(mine.len() + others.len());
+            loop {
+                let el = match (mine.peek(), others.front()) {
+                    (Some(a), Some(b)) if b.time >= a.time => mine.next(),
+                    (_, Some(b)) => {
+                        self.notify_pushed(&b.value);
+                        others.pop_front()
+                    }
+                    (Some(_), None) => mine.next(),
+                    (None, None) => None,
+                };
+                match el {
+                    Some(el) => self.queue.push_back(el),
+                    None => break,
+                }
+            }
+            self.indexes_changed();
+            0
+        };
+        let dropped = self.truncate_to_max_len();
+        self.debug_assert_pushed(start.saturating_sub(dropped), self.queue.len());
+        self.record_depth();
+        self.check_alarms();
+    }
+
+    /// Splits the queue in two, moving all the elements older than
+    /// `age` into a new queue that is returned, with the same max
+    /// age than `self`. The elements moved keep the time they were pushed,
+    /// so they expire in the new queue at the same time they would in `self`.
+    ///
+    /// Before the elements are moved, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use std::thread;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// thread::sleep(Duration::from_millis(100));
+    /// queue.push(3);
+    /// let mut stale = queue.split_off_older_than(Duration::from_millis(50));
+    /// assert_eq!(stale.pop_n(3), vec![1, 2]);
+    /// assert_eq!(queue.pop_n(3), vec![3]);
+    /// ```
+    pub fn split_off_older_than(&mut self, age: Duration) -> SumQueue<T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let at = self
+            .queue
+            .partition_point(|el| self.epoch.age(el.time, now) > age);
+        let newer = self.queue.split_off(at);
+        let older = std::mem::replace(&mut self.queue, newer);
+        self.indexes_changed();
+        self.empty_like(older)
+    }
+
+    /// Drops all items, including the ones kept
+    /// within the grace period, if any.
     pub fn clear(&mut self) {
-        self.heap.clear();
+        self.queue.clear();
+        self.expired.clear();
+        self.indexes_changed();
+    }
+
+    /// Drops now all the items pushed more than `age` ago, even if
+    /// `age` is shorter than the max age of the queue, eg. to discard
+    /// the stale items after an incident, and returns the number of
+    /// items dropped. The max age of the queue is not changed.
+    ///
+    /// The items dropped are passed to the expiration callback and
+    /// the observers, if any, like the expired ones.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// thread::sleep(Duration::from_millis(100));
+    /// queue.push(3);
+    /// assert_eq!(queue.keep_only_last(Duration::from_millis(50)), 2);
+    /// assert_eq!(queue.to_vec(), vec![3]);
+    /// assert_eq!(queue.max_age(), Duration::from_secs(60));
+    /// ```
+    pub fn keep_only_last(&mut self, age: Duration) -> usize {
+        let now = self.now();
+        self.clear_oldest(now);
+        let at = self
+            .queue
+            .partition_point(|el| self.epoch.age(el.time, now) > age);
+        self.drop_oldest(at);
+        at
+    }
+
+    /// Drops the oldest items to keep only the `n` newest ones, eg. to
+    /// trim the queue after a burst, and returns the number of items dropped.
+    ///
+    /// Before the items are dropped, it also drops all expired elements.
+    /// The items dropped are passed to the expiration callback and
+    /// the observers, if any, like the expired ones.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// queue.push(3);
+    /// assert_eq!(queue.truncate_to_newest(2), 1);
+    /// assert_eq!(queue.to_vec(), vec![2, 3]);
+    /// assert_eq!(queue.truncate_to_newest(5), 0);
+    /// ```
+    pub fn truncate_to_newest(&mut self, n: usize) -> usize {
+        self.clear_oldest(self.now());
+        let dropped = self.queue.len().saturating_sub(n);
+        self.drop_oldest(dropped);
+        dropped
     }
 
-    /// Returns the length of the heap.
+    /// Returns the length of the queue.
     ///
     /// It takes a mutable reference of `self` because
     /// before return the size it also cleans all the
     /// expired elements of the queue, so only
     /// no expired elements are count.
     pub fn len(&mut self) -> usize {
-        self.clear_oldest(now());
-        self.heap.len()
+        self.clear_oldest(self.now());
+        self.queue.len()
+    }
+
+    /// Returns the length of the queue without dropping the expired elements.
+    ///
+    /// Unlike [`SumQueue::len()`] it only needs a shared reference of `self`,
+    /// so it can be used when the queue is shared behind a read lock.
+    /// The expired elements are not count, but they are not dropped
+    /// either, so they are still holding memory until
+    /// a method that takes a mutable reference is called.
+    ///
+    /// Take into account that the result is just a snapshot, elements
+    /// about to expire are count as well.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use std::thread;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_millis(100));
+    /// queue.push(1);
+    /// queue.push(5);
+    /// assert_eq!(queue.len_unpruned(), 2);
+    /// thread::sleep(Duration::from_millis(200));
+    /// assert_eq!(queue.len_unpruned(), 0);
+    /// ```
+    pub fn len_unpruned(&self) -> usize {
+        self.queue.len() - self.live_start(self.now())
     }
 
-    /// Checks if the heap is empty. Expired elements are not taken
+    /// Checks if the queue is empty. Expired elements are not taken
     /// into account because are droped by `is_empty()` before
     /// return the result.
     ///
@@ -291,7 +1072,7 @@ impl<T> SumQueue<T> {
         self.len() == 0
     }
 
-    /// Returns the number of elements the heap can hold without reallocating.
+    /// Returns the number of elements the queue can hold without reallocating.
     ///
     /// ```
     /// use std::time::Duration;
@@ -301,7 +1082,20 @@ impl<T> SumQueue<T> {
     /// assert_eq!(queue.len(), 0);
     /// ```
     pub fn capacity(&self) -> usize {
-        self.heap.capacity()
+        self.queue.capacity()
+    }
+
+    /// Returns the max number of elements the queue can hold, if it was
+    /// configured with [`SumQueueBuilder::max_len()`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue: SumQueue<char> = SumQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.max_len(), None);
+    /// ```
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
     }
 
     /// Returns the max time the elements will live in the queue.
@@ -316,7 +1110,100 @@ impl<T> SumQueue<T> {
         self.max_age
     }
 
-    /// Returns the first item in the heap, or `None` if it is empty.
+    /// Sets the min time the elements have to be in the queue before
+    /// they can be popped, turning the queue into a delay queue, eg.
+    /// to schedule retries with backoff. The elements still expire after
+    /// the max age, so it only makes sense with a min age shorter than that.
+    ///
+    /// Until the oldest element reaches the min age, [`SumQueue::pop()`] and
+    /// the other methods that remove elements from the front return nothing,
+    /// while the methods that only read the elements, like [`SumQueue::peek()`],
+    /// see all of them.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut retries = SumQueue::new(Duration::from_secs(60))
+    ///     .with_min_age(Duration::from_millis(100));
+    /// retries.push("job-1");
+    /// assert_eq!(retries.pop(), None);
+    /// assert_eq!(retries.peek(), Some(&"job-1"));
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert_eq!(retries.pop(), Some("job-1"));
+    /// ```
+    pub fn with_min_age(mut self, min_age: Duration) -> SumQueue<T> {
+        self.min_age = min_age;
+        self
+    }
+
+    /// Returns the min time the elements have to be in the queue before
+    /// they can be popped, zero unless set with [`SumQueue::with_min_age()`].
+    pub fn min_age(&self) -> Duration {
+        self.min_age
+    }
+
+    /// Sets the time the elements are kept after they expire, so the
+    /// stats of the past can be queried with [`SumQueue::stats_as_of()`],
+    /// eg. to compare the stats of now with the stats of 10 seconds ago.
+    ///
+    /// The elements kept are not visible to the other methods, and are
+    /// passed to the expiration callback, if any, when the grace period
+    /// ends. Only the elements that expire are kept, not the ones removed
+    /// by the caller or dropped because the queue reached its max length.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::{Duration, Instant};
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_millis(100))
+    ///     .with_grace(Duration::from_secs(10));
+    /// queue.push(1);
+    /// let before = Instant::now();
+    /// thread::sleep(Duration::from_millis(150));
+    /// queue.push(2);
+    /// assert_eq!(queue.stats().sum, Some(2));
+    /// assert_eq!(queue.stats_as_of(before).sum, Some(1));
+    /// ```
+    ///
+    /// It panics if the max age plus `grace` is longer than [`MAX_AGE_LIMIT`].
+    pub fn with_grace(mut self, grace: Duration) -> SumQueue<T> {
+        check_max_age(self.max_age, grace);
+        self.grace = grace;
+        self
+    }
+
+    /// Returns the time the elements are kept after they expire,
+    /// zero unless set with [`SumQueue::with_grace()`].
+    pub fn grace(&self) -> Duration {
+        self.grace
+    }
+
+    /// Returns the time until the oldest element can be popped, zero if it
+    /// can be popped now, or `None` if the queue is empty.
+    ///
+    /// Before the time is returned, it also drops all expired
+    /// elements from the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60))
+    ///     .with_min_age(Duration::from_secs(5));
+    /// assert_eq!(queue.ready_in(), None);
+    /// queue.push(1);
+    /// assert!(queue.ready_in().unwrap() > Duration::from_secs(4));
+    /// ```
+    pub fn ready_in(&mut self) -> Option<Duration> {
+        let now = self.now();
+        self.clear_oldest(now);
+        self.queue.front().map(|q_element| {
+            self.min_age
+                .saturating_sub(self.epoch.age(q_element.time, now))
+        })
+    }
+
+    /// Returns the first item in the queue, or `None` if it is empty.
     ///
     /// Before the element is returned, it also drops all expired
     /// elements from the queue.
@@ -332,11 +1219,51 @@ impl<T> SumQueue<T> {
     /// assert_eq!(queue.peek(), Some(&"Hello"));
     /// ```
     pub fn peek(&mut self) -> Option<&T> {
-        self.clear_oldest(now());
-        self.heap.peek().map(|q_element| &q_element.value)
+        self.clear_oldest(self.now());
+        self.queue.front().map(|q_element| &q_element.value)
+    }
+
+    /// Returns a mutable reference to the first item in the queue, or
+    /// `None` if it is empty or the item is not old enough to be popped,
+    /// see [`SumQueue::with_min_age()`].
+    ///
+    /// The reference is wrapped in a [`PeekMut`] guard, that also allows
+    /// to remove the element from the queue with [`PeekMut::pop()`], so the
+    /// element can be inspected and then consumed only when needed, like
+    /// with [`SumQueue::pop()`].
+    ///
+    /// Before the element is returned, it also drops all expired
+    /// elements from the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{PeekMut, SumQueue};
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// assert!(queue.peek_mut().is_none());
+    /// queue.push(1);
+    /// queue.push(5);
+    /// if let Some(mut first) = queue.peek_mut() {
+    ///     *first += 1;
+    /// }
+    /// assert_eq!(queue.peek(), Some(&2));
+    /// if let Some(first) = queue.peek_mut() {
+    ///     if *first < 5 {
+    ///         assert_eq!(PeekMut::pop(first), 2);
+    ///     }
+    /// }
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5]);
+    /// ```
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T>> {
+        let now = self.now();
+        self.clear_oldest(now);
+        if self.front_ready(now) {
+            Some(PeekMut { queue: self })
+        } else {
+            None
+        }
     }
 
-    /// Removes the first item from the heap and returns it, or `None` if it
+    /// Removes the first item from the queue and returns it, or `None` if it
     /// is empty.
     ///
     /// Before the element is dropped from the queue and returned,
@@ -356,167 +1283,1603 @@ impl<T> SumQueue<T> {
     /// assert_eq!(queue.pop(), None);
     /// ```
     pub fn pop(&mut self) -> Option<T> {
-        self.clear_oldest(now());
-        self.heap.pop().map(|q_element| q_element.value)
+        let now = self.now();
+        self.clear_oldest(now);
+        if !self.front_ready(now) {
+            return None;
+        }
+        let item = self.pop_front(now).map(|q_element| q_element.value);
+        self.record_depth();
+        item
     }
 
-    /// Returns an iterator visiting all values in the underlying heap, in
-    /// same order they were pushed.
-    ///
-    /// Before return the iterator, it also drops all expired elements.
+    /// Removes the oldest element, recording the time it was in the queue.
+    fn pop_front(&mut self, now: Instant) -> Option<QueueElement<T>> {
+        self.remove_at(now, 0)
+    }
+
+    /// Removes the element at `index`, recording the time it was in the queue.
+    fn remove_at(&mut self, now: Instant, index: usize) -> Option<QueueElement<T>> {
+        match index {
+            0 => self.indexes_popping(),
+            _ => self.indexes_changed(),
+        }
+        let q_element = self.queue.remove(index)?;
+        self.notify_popped(&q_element.value);
+        if let Some(residence) = self.residence.as_mut() {
+            residence.record(self.epoch.age(q_element.time, now));
+        }
+        Some(q_element)
+    }
+
+    /// Returns the first item in the queue with its age, that is the time
+    /// passed since it was pushed, or `None` if the queue is empty.
     ///
-    /// The iterator does not change the state of the queue, this
-    /// method takes ownership of the queue because as mentioned above
-    /// it clears the expired elements before return the iterator, even
-    /// if the iterator is not consumed later on.
+    /// Before the element is returned, it also drops all expired
+    /// elements from the queue.
     ///
     /// ```
     /// use std::time::Duration;
     /// use sum_queue::SumQueue;
     /// let mut queue = SumQueue::new(Duration::from_secs(60));
-    /// queue.push('a');
-    /// queue.push('z');
-    /// queue.push('x');
+    /// assert_eq!(queue.peek_with_age(), None);
+    /// queue.push("Hello");
+    /// let (value, age) = queue.peek_with_age().unwrap();
+    /// assert_eq!(value, &"Hello");
+    /// assert!(age < Duration::from_secs(60));
+    /// ```
+    pub fn peek_with_age(&mut self) -> Option<(&T, Duration)> {
+        let now = self.now();
+        self.clear_oldest(now);
+        self.queue
+            .front()
+            .map(|q_element| (&q_element.value, self.epoch.age(q_element.time, now)))
+    }
+
+    /// Returns the oldest item in the queue with its age, or `None`
+    /// if it is empty. Same than [`SumQueue::peek_with_age()`].
+    ///
+    /// Before the element is returned, it also drops all expired
+    /// elements from the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.oldest(), None);
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert_eq!(queue.oldest().map(|(value, _)| value), Some(&1));
+    /// ```
+    pub fn oldest(&mut self) -> Option<(&T, Duration)> {
+        self.peek_with_age()
+    }
+
+    /// Returns the newest item in the queue, the last pushed, with its
+    /// age, or `None` if it is empty.
+    ///
+    /// Before the element is returned, it also drops all expired
+    /// elements from the queue.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.newest(), None);
+    /// queue.push(1);
+    /// queue.push(2);
+    /// let (value, age) = queue.newest().unwrap();
+    /// assert_eq!(value, &2);
+    /// assert!(age < Duration::from_secs(60));
+    /// ```
+    pub fn newest(&mut self) -> Option<(&T, Duration)> {
+        let now = self.now();
+        self.clear_oldest(now);
+        self.queue
+            .back()
+            .map(|q_element| (&q_element.value, self.epoch.age(q_element.time, now)))
+    }
+
+    /// Removes the first item from the queue and returns it with its age,
+    /// that is the time the element was in the queue, or `None` if it is empty.
+    ///
+    /// Before the element is dropped from the queue and returned,
+    /// it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push('a');
+    /// let (value, age) = queue.pop_with_age().unwrap();
+    /// assert_eq!(value, 'a');
+    /// assert!(age < Duration::from_secs(60));
+    /// assert_eq!(queue.pop_with_age(), None);
+    /// ```
+    pub fn pop_with_age(&mut self) -> Option<(T, Duration)> {
+        let now = self.now();
+        self.clear_oldest(now);
+        if !self.front_ready(now) {
+            return None;
+        }
+        let item = self
+            .pop_front(now)
+            .map(|q_element| (q_element.value, self.epoch.age(q_element.time, now)));
+        self.record_depth();
+        item
+    }
+
+    /// Removes the last item pushed into the queue and returns it, or `None`
+    /// if the queue is empty, so the queue can be consumed as a stack, where
+    /// the latest item wins while the oldest ones still expire.
+    ///
+    /// If the queue was configured with a min age, see [`SumQueue::with_min_age()`],
+    /// it returns `None` until the newest item reaches the min age.
+    ///
+    /// Before the element is dropped from the queue and returned,
+    /// it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert_eq!(queue.pop_newest(), Some(2));
+    /// assert_eq!(queue.pop_newest(), Some(1));
+    /// assert_eq!(queue.pop_newest(), None);
+    /// ```
+    pub fn pop_newest(&mut self) -> Option<T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let last = self.queue.len().checked_sub(1)?;
+        if self.epoch.age(self.queue[last].time, now) < self.min_age {
+            return None;
+        }
+        let item = self.remove_at(now, last).map(|q_element| q_element.value);
+        self.record_depth();
+        item
+    }
+
+    /// Removes up to `n` items from the queue, the oldest first,
+    /// and returns them in the same order they were pushed.
+    ///
+    /// Before the elements are dropped from the queue and returned,
+    /// it also drops all expired elements, doing it once for
+    /// the whole batch instead of once per element as with `pop()`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// queue.push(3);
+    /// assert_eq!(queue.pop_n(2), vec![1, 2]);
+    /// assert_eq!(queue.pop_n(2), vec![3]);
+    /// assert!(queue.pop_n(2).is_empty());
+    /// ```
+    pub fn pop_n(&mut self, n: usize) -> Vec<T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let n = n.min(self.ready_len(now));
+        let items = (0..n)
+            .filter_map(|_| self.pop_front(now))
+            .map(|q_element| q_element.value)
+            .collect();
+        self.record_depth();
+        items
+    }
+
+    /// Removes the items from the queue, the oldest first, while
+    /// the `predicate` returns `true` for them, and returns the removed
+    /// items in the same order they were pushed. The first element
+    /// that doesn't satisfy the predicate and the rest of elements
+    /// after it are kept in the queue.
+    ///
+    /// Before the elements are dropped from the queue and returned,
+    /// it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// queue.push(10);
+    /// queue.push(3);
+    /// assert_eq!(queue.pop_while(|&x| x < 5), vec![1, 2]);
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&10, &3]);
+    /// ```
+    pub fn pop_while<P>(&mut self, mut predicate: P) -> Vec<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let now = self.now();
+        self.clear_oldest(now);
+        let mut items = Vec::new();
+        while let Some(q_element) = self.queue.front() {
+            if self.epoch.age(q_element.time, now) < self.min_age || !predicate(&q_element.value) {
+                break;
+            }
+            if let Some(q_element) = self.pop_front(now) {
+                items.push(q_element.value);
+            }
+        }
+        self.record_depth();
+        items
+    }
+
+    /// Removes the smallest item from the queue and returns it, or `None`
+    /// if the queue is empty. If there are many items equal to the smallest,
+    /// the oldest is removed.
+    ///
+    /// Before the element is dropped from the queue and returned, it also
+    /// drops all expired elements.
+    ///
+    /// It takes O(n) time, with n the number of elements ready to pop: the
+    /// elements are kept in push order, so finding the item is a linear
+    /// scan, and removing it from the middle of the queue shifts the
+    /// elements after or before it anyway, so an ordered index of the
+    /// values would only save the comparisons while making all the
+    /// other operations slower.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(5);
+    /// queue.push(1);
+    /// queue.push(9);
+    /// assert_eq!(queue.pop_min(), Some(1));
+    /// assert_eq!(queue.pop_max(), Some(9));
+    /// assert_eq!(queue.to_vec(), vec![5]);
+    /// ```
+    pub fn pop_min(&mut self) -> Option<T>
+    where
+        T: Ord,
+    {
+        self.pop_by(|value, best| value < best)
+    }
+
+    /// Removes the largest item from the queue and returns it, or `None`
+    /// if the queue is empty. If there are many items equal to the largest,
+    /// the oldest is removed.
+    ///
+    /// Before the element is dropped from the queue and returned, it also
+    /// drops all expired elements. Like [`SumQueue::pop_min()`], it takes
+    /// O(n) time, with n the number of elements ready to pop.
+    pub fn pop_max(&mut self) -> Option<T>
+    where
+        T: Ord,
+    {
+        self.pop_by(|value, best| value > best)
+    }
+
+    /// Removes the oldest of the best items ready to be popped,
+    /// where `better` checks if a value is better than another.
+    fn pop_by(&mut self, better: fn(&T, &T) -> bool) -> Option<T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let mut found: Option<(usize, &T)> = None;
+        for (index, q_element) in self.queue.range(..self.ready_len(now)).enumerate() {
+            if found.is_none_or(|(_, best)| better(&q_element.value, best)) {
+                found = Some((index, &q_element.value));
+            }
+        }
+        let index = found.map(|(index, _)| index);
+        let item = index.and_then(|index| self.remove_at(now, index));
+        self.record_depth();
+        item.map(|q_element| q_element.value)
+    }
+
+    /// Returns `true` if the queue contains an element equal
+    /// to the given value.
+    ///
+    /// Before the search is done, it also drops all expired elements,
+    /// so only no expired elements are compared.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push("Hello");
+    /// queue.push("World");
+    /// assert!(queue.contains(&"World"));
+    /// assert!(!queue.contains(&"Bye"));
+    /// ```
+    pub fn contains(&mut self, item: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.clear_oldest(self.now());
+        self.queue.iter().any(|q_element| q_element.value == *item)
+    }
+
+    /// Returns an iterator visiting all values in the queue, in
+    /// same order they were pushed.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    ///
+    /// The iterator does not change the state of the queue, this
+    /// method takes ownership of the queue because as mentioned above
+    /// it clears the expired elements before return the iterator, even
+    /// if the iterator is not consumed later on.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push('a');
+    /// queue.push('z');
+    /// queue.push('x');
     /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&'a', &'z', &'x']);
     /// ```
+    ///
+    /// The iterator can be reversed to visit the newest elements first:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push('a');
+    /// queue.push('z');
+    /// queue.push('x');
+    /// assert_eq!(queue.iter().rev().collect::<Vec<_>>(), vec![&'x', &'z', &'a']);
+    /// ```
     pub fn iter(&mut self) -> Iter<'_, T> {
-        self.clear_oldest(now());
+        self.clear_oldest(self.now());
         Iter {
-            iter: self.heap.iter(),
+            iter: self.queue.iter(),
         }
     }
-}
 
-impl<T: Copy + Ord + Add<Output = T>> SumQueue<T> {
-    fn _stats(&mut self, len: usize) -> QueueStats<T> {
-        let mut min = None;
-        let mut max = None;
-        let mut sum = None;
-        for i in self.heap.iter().map(|x| x.value) {
-            if min.is_none() || Some(i) < min {
-                min = Some(i);
-            }
-            if max.is_none() || Some(i) > max {
-                max = Some(i);
-            }
-            sum = match sum {
-                Some(s) => Some(s + i),
-                None => Some(i),
-            };
+    /// Returns an iterator that allows modifying each value in the queue,
+    /// in same order they were pushed.
+    ///
+    /// The values are modified in place, so they keep their ages,
+    /// unlike popping the elements and pushing them again.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(5);
+    /// for value in queue.iter_mut() {
+    ///     *value *= 10;
+    /// }
+    /// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&10, &50]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.clear_oldest(self.now());
+        self.indexes_changed();
+        IterMut {
+            iter: self.queue.iter_mut(),
         }
-        QueueStats { min, max, sum, len }
     }
 
-    /// Get statistics of the queue. The type of the elements
-    /// on it needs to implements the `Copy`, `Ord` and `Add` traits.
+    /// Returns an iterator visiting all values in the queue, in
+    /// same order they were pushed, without dropping the expired elements.
     ///
-    /// Before the stats are returned, it also drops all expired elements.
+    /// Unlike [`SumQueue::iter()`] it only needs a shared reference of `self`,
+    /// so it can be used when the queue is shared behind a read lock.
+    /// The expired elements are skipped, but they are not dropped
+    /// either, see [`SumQueue::len_unpruned()`].
     ///
     /// ```
+    /// use std::sync::RwLock;
     /// use std::time::Duration;
     /// use sum_queue::SumQueue;
-    /// let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(1000));
-    /// queue.push(-10);
-    /// queue.push(50);
-    /// queue.push(40);
-    /// queue.push(20);
-    /// let stats = queue.stats();
-    /// assert_eq!(stats.min, Some(-10));
-    /// assert_eq!(stats.max, Some(50));
-    /// assert_eq!(stats.sum, Some(100));
-    /// assert_eq!(stats.len, 4);
+    /// let lock = RwLock::new(SumQueue::new(Duration::from_secs(60)));
+    /// lock.write().unwrap().push('a');
+    /// lock.write().unwrap().push('z');
+    /// let queue = lock.read().unwrap();
+    /// assert_eq!(queue.iter_unpruned().collect::<Vec<_>>(), vec![&'a', &'z']);
     /// ```
+    pub fn iter_unpruned(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.queue.range(self.live_start(self.now())..),
+        }
+    }
+
+    /// Returns an iterator visiting all values in the queue
+    /// alongside their ages, that is the time passed since each
+    /// value was pushed, in same order they were pushed.
     ///
-    /// See also `push_and_stats`.
-    pub fn stats(&mut self) -> QueueStats<T> {
-        let len = self.len();
-        self._stats(len)
+    /// Before return the iterator, it also drops all expired elements.
+    /// All the ages are computed against the same instant, taken
+    /// when this method is called.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push('a');
+    /// queue.push('z');
+    /// for (value, age) in queue.iter_with_age() {
+    ///     assert!(age < Duration::from_secs(60));
+    ///     println!("{} pushed {:?} ago", value, age);
+    /// }
+    /// ```
+    pub fn iter_with_age(&mut self) -> IterWithAge<'_, T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        IterWithAge {
+            iter: self.queue.iter(),
+            epoch: self.epoch,
+            now,
+        }
+    }
+
+    /// Returns an iterator visiting the values in the queue pushed
+    /// more than `age` ago, in the same order they were pushed.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    /// It's the complement of [`SumQueue::iter_newer_than()`].
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// thread::sleep(Duration::from_millis(100));
+    /// queue.push(3);
+    /// let age = Duration::from_millis(50);
+    /// assert_eq!(queue.iter_older_than(age).collect::<Vec<_>>(), vec![&1, &2]);
+    /// assert_eq!(queue.iter_newer_than(age).collect::<Vec<_>>(), vec![&3]);
+    /// ```
+    pub fn iter_older_than(&mut self, age: Duration) -> Iter<'_, T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let at = self
+            .queue
+            .partition_point(|el| self.epoch.age(el.time, now) > age);
+        Iter {
+            iter: self.queue.range(..at),
+        }
+    }
+
+    /// Returns an iterator visiting the values in the queue pushed
+    /// within the last `age`, in the same order they were pushed.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    /// It's the complement of [`SumQueue::iter_older_than()`].
+    pub fn iter_newer_than(&mut self, age: Duration) -> Iter<'_, T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let at = self
+            .queue
+            .partition_point(|el| self.epoch.age(el.time, now) > age);
+        Iter {
+            iter: self.queue.range(at..),
+        }
+    }
+
+    /// Splits the values in the queue in two vectors in one pass: the
+    /// values pushed within the last `age`, and the values pushed more
+    /// than `age` ago, both in the same order they were pushed.
+    ///
+    /// Before the values are split, it also drops all expired elements.
+    /// The values are kept in the queue, see [`SumQueue::split_off_older_than()`]
+    /// to move the older values out of the queue instead.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// thread::sleep(Duration::from_millis(100));
+    /// queue.push(2);
+    /// queue.push(3);
+    /// let (newer, older) = queue.partition_by_age(Duration::from_millis(50));
+    /// assert_eq!(newer, vec![&2, &3]);
+    /// assert_eq!(older, vec![&1]);
+    /// ```
+    pub fn partition_by_age(&mut self, age: Duration) -> (Vec<&T>, Vec<&T>) {
+        let now = self.now();
+        self.clear_oldest(now);
+        let at = self
+            .queue
+            .partition_point(|el| self.epoch.age(el.time, now) > age);
+        let newer = self.queue.range(at..).map(|el| &el.value).collect();
+        let older = self.queue.range(..at).map(|el| &el.value).collect();
+        (newer, older)
+    }
+
+    /// Returns an iterator over the values in the queue grouped by the
+    /// time they were pushed in consecutive buckets of `bucket` duration,
+    /// eg. to export the values pushed each second, where each item is
+    /// an iterator over the values of a bucket.
+    ///
+    /// The buckets are aligned to now: the last bucket holds the values
+    /// pushed within the last `bucket` of time, the previous one the values
+    /// pushed within the `bucket` of time before it, and so on until the
+    /// bucket of the oldest value. The buckets without values in between
+    /// are visited as well, as empty iterators.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// thread::sleep(Duration::from_millis(250));
+    /// queue.push(3);
+    /// let chunks = queue
+    ///     .chunks_by_time(Duration::from_millis(200))
+    ///     .map(|chunk| chunk.collect::<Vec<_>>())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec![vec![&1, &2], vec![&3]]);
+    /// ```
+    pub fn chunks_by_time(&mut self, bucket: Duration) -> ChunksByTime<'_, T> {
+        let now = self.now();
+        self.clear_oldest(now);
+        let bucket = bucket.max(Duration::from_nanos(1));
+        let remaining = self
+            .queue
+            .front()
+            .map_or(0, |el| bucket_of(self.epoch.age(el.time, now), bucket) + 1);
+        ChunksByTime {
+            queue: &self.queue,
+            start: 0,
+            epoch: self.epoch,
+            now,
+            bucket,
+            remaining,
+        }
+    }
+
+    /// Returns an iterator over the pairs of values of this queue and
+    /// `other` pushed within `tolerance` of each other, in the same order
+    /// they were pushed, eg. to correlate related streams of events.
+    ///
+    /// Each value is paired at most once: every value of this queue is
+    /// paired with the oldest value of `other` not paired yet and pushed
+    /// within the tolerance, the values without a match are skipped.
+    ///
+    /// Before return the iterator, it also drops all
+    /// expired elements of both queues.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut requests = SumQueue::new(Duration::from_secs(60));
+    /// let mut responses = SumQueue::new(Duration::from_secs(60));
+    /// requests.push("GET /");
+    /// responses.push(200);
+    /// requests.push("GET /about");
+    /// responses.push(404);
+    /// let tolerance = Duration::from_secs(1);
+    /// let pairs = requests.zip_by_time(&mut responses, tolerance).collect::<Vec<_>>();
+    /// assert_eq!(pairs, vec![(&"GET /", &200), (&"GET /about", &404)]);
+    /// ```
+    pub fn zip_by_time<'a, U>(
+        &'a mut self,
+        other: &'a mut SumQueue<U>,
+        tolerance: Duration,
+    ) -> ZipByTime<'a, T, U> {
+        let now = self.now();
+        self.clear_oldest(now);
+        other.clear_oldest(now);
+        ZipByTime {
+            left: self.queue.iter(),
+            left_epoch: self.epoch,
+            right: other.queue.iter().peekable(),
+            right_epoch: other.epoch,
+            tolerance,
+        }
+    }
+
+    /// Consumes the queue and returns a vector with all the values,
+    /// in the same order they were pushed. Expired elements are
+    /// not included.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(String::from("a"));
+    /// queue.push(String::from("b"));
+    /// assert_eq!(queue.into_vec(), vec!["a", "b"]);
+    /// ```
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.clear_oldest(self.now());
+        self.queue.into_iter().map(|el| el.value).collect()
+    }
+
+    /// Consumes the queue and returns a queue with the values transformed
+    /// by `f`, eg. to turn a queue of raw events into a queue of metrics.
+    ///
+    /// The elements keep the time they were pushed, and the new queue gets
+    /// the same max age, max length, min age and grace period than `self`,
+    /// but not the expiration callback, the alarms, the decay, the observers
+    /// or the min and max kept with [`SumQueue::with_min_max()`], that
+    /// depend on the type of the values. Expired elements are not included,
+    /// except the ones kept within the grace period, that are transformed
+    /// as well for [`SumQueue::stats_as_of()`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut requests = SumQueue::new(Duration::from_secs(60));
+    /// requests.push(("/login", 120));
+    /// requests.push(("/home", 15));
+    /// let mut latencies = requests.map(|(_, latency)| latency);
+    /// assert_eq!(latencies.stats().sum, Some(135));
+    /// assert_eq!(latencies.max_age(), Duration::from_secs(60));
+    /// ```
+    pub fn map<U, F>(self, f: F) -> SumQueue<U>
+    where
+        F: Fn(T) -> U,
+    {
+        self.filter_map(|value| Some(f(value)))
+    }
+
+    /// Consumes the queue and returns a queue with the values transformed
+    /// by `f`, skipping the values for which `f` returns `None`, eg. to
+    /// clean the values of a captured window in one pass.
+    ///
+    /// The elements keep the time they were pushed, and the new queue
+    /// gets the same settings than with [`SumQueue::map()`]. Expired
+    /// elements are not included either, except the ones kept within
+    /// the grace period.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut readings = SumQueue::new(Duration::from_secs(60));
+    /// readings.push("21");
+    /// readings.push("n/a");
+    /// readings.push("23");
+    /// let mut temperatures = readings.filter_map(|reading| reading.parse::<i32>().ok());
+    /// assert_eq!(temperatures.to_vec(), vec![21, 23]);
+    /// ```
+    pub fn filter_map<U, F>(mut self, mut f: F) -> SumQueue<U>
+    where
+        F: FnMut(T) -> Option<U>,
+    {
+        self.clear_oldest(self.now());
+        let mut filter_map = |el: QueueElement<T>| {
+            let time = el.time;
+            f(el.value).map(|value| QueueElement { time, value })
+        };
+        SumQueue {
+            queue: self.queue.into_iter().filter_map(&mut filter_map).collect(),
+            epoch: self.epoch,
+            max_age: self.max_age,
+            max_len: self.max_len,
+            min_age: self.min_age,
+            grace: self.grace,
+            expired: self.expired.into_iter().filter_map(filter_map).collect(),
+            depth: self.depth,
+            residence: self.residence,
+            min_max: None,
+            unique: None,
+            on_expire: None,
+            alarms: Vec::new(),
+            decay: None,
+            nan_policy: self.nan_policy,
+            observers: Vec::new(),
+            clock: self.clock,
+            prune: self.prune,
+        }
+    }
+
+    /// Returns a vector with a copy of all the values,
+    /// in the same order they were pushed.
+    ///
+    /// Before the values are copied, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(5);
+    /// assert_eq!(queue.to_vec(), vec![1, 5]);
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    pub fn to_vec(&mut self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    /// Returns a vector with a copy of all the values alongside their ages,
+    /// in the same order they were pushed.
+    ///
+    /// Before the values are copied, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(5);
+    /// let items = queue.to_vec_with_ages();
+    /// assert_eq!(items.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![1, 5]);
+    /// assert!(items[0].0 >= items[1].0);
+    /// ```
+    pub fn to_vec_with_ages(&mut self) -> Vec<(Duration, T)>
+    where
+        T: Clone,
+    {
+        self.iter_with_age()
+            .map(|(value, age)| (age, value.clone()))
+            .collect()
+    }
+}
+
+impl<T: Summable> SumQueue<T> {
+    fn _stats<A: From<T> + Add<Output = A>>(&self, len: usize) -> QueueStats<T, A> {
+        let now = self.now();
+        let values = self.queue.iter().map(|el| self.decayed(el, now));
+        Self::stats_of(values, len, self.nan_policy)
+    }
+
+    fn stats_of<A: From<T> + Add<Output = A>>(
+        values: impl Iterator<Item = T>,
+        len: usize,
+        nan_policy: NanPolicy,
+    ) -> QueueStats<T, A> {
+        let mut stats = QueueStats {
+            len,
+            ..QueueStats::default()
+        };
+        for i in values {
+            stats.push_value_with(i, nan_policy);
+        }
+        stats
+    }
+
+    /// Get statistics of the queue. The type of the elements
+    /// on it needs to implements the [`Summable`] trait.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(1000));
+    /// queue.push(-10);
+    /// queue.push(50);
+    /// queue.push(40);
+    /// queue.push(20);
+    /// let stats = queue.stats();
+    /// assert_eq!(stats.min, Some(-10));
+    /// assert_eq!(stats.max, Some(50));
+    /// assert_eq!(stats.sum, Some(100));
+    /// assert_eq!(stats.len, 4);
+    /// ```
+    ///
+    /// See also `push_and_stats`.
+    pub fn stats(&mut self) -> QueueStats<T> {
+        let len = self.len();
+        self._stats(len)
+    }
+
+    /// Get statistics of the queue like [`SumQueue::stats()`], but with
+    /// the **sum** accumulated in the wider type `A`, eg. `u64` for
+    /// a queue of `u8` counters, so large windows don't overflow.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::{QueueStats, SumQueue};
+    /// let mut queue: SumQueue<u8> = SumQueue::new(Duration::from_secs(60));
+    /// for _ in 0..1000 {
+    ///     queue.push(u8::MAX);
+    /// }
+    /// let stats: QueueStats<u8, u64> = queue.stats_widened();
+    /// assert_eq!(stats.max, Some(255));
+    /// assert_eq!(stats.sum, Some(255_000));
+    /// ```
+    pub fn stats_widened<A: From<T> + Add<Output = A>>(&mut self) -> QueueStats<T, A> {
+        let len = self.len();
+        self._stats(len)
+    }
+
+    /// Get the statistics the queue had at the instant `at`, that is
+    /// of the elements pushed within the max age before `at`.
+    ///
+    /// The elements that expired before now are only taken into account
+    /// if they are still within the grace period set with
+    /// [`SumQueue::with_grace()`], so instants older than the grace
+    /// period return partial stats. Elements popped before now are
+    /// not taken into account either.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    pub fn stats_as_of(&mut self, at: Instant) -> QueueStats<T> {
+        self.clear_oldest(self.now());
+        let (epoch, max_age) = (self.epoch, self.max_age);
+        let elements = self.expired.iter().chain(self.queue.iter());
+        let mut len = 0;
+        let values = elements
+            .filter(|el| epoch.instant(el.time) <= at && epoch.age(el.time, at) <= max_age)
+            .inspect(|_| len += 1)
+            .map(|el| self.decayed(el, at));
+        let mut stats = Self::stats_of(values, 0, self.nan_policy);
+        // counted while the values are folded, to walk them once
+        stats.len = len;
+        stats
+    }
+
+    /// Pushes an item at the back of the queue, and returns
+    /// the stats of the queue. The type of the elements
+    /// on it need to implements the [`Summable`] trait.
+    ///
+    /// Before push and return the stats, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(1000));
+    /// queue.push(-10);
+    /// queue.push(50);
+    /// queue.push(40);
+    /// let stats = queue.push_and_stats(20);
+    /// assert_eq!(stats.min, Some(-10));
+    /// assert_eq!(stats.max, Some(50));
+    /// assert_eq!(stats.sum, Some(100));
+    /// assert_eq!(stats.len, 4);
+    /// ```
+    ///
+    /// Use `push` instead if you don't need the stats
+    /// or the elements in the queue don't implement
+    /// any of the required traits.
+    pub fn push_and_stats(&mut self, item: T) -> QueueStats<T> {
+        let len = self.push(item);
+        self._stats(len)
+    }
+}
+
+/// Creates an empty `SumQueue` where the elements live
+/// [`DEFAULT_MAX_AGE`] (60 seconds) at maximum.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+///
+/// #[derive(Default)]
+/// struct Metrics {
+///     latencies: SumQueue<u64>,
+/// }
+///
+/// let metrics = Metrics::default();
+/// assert_eq!(metrics.latencies.max_age(), Duration::from_secs(60));
+/// ```
+impl<T> Default for SumQueue<T> {
+    fn default() -> Self {
+        SumQueue::new(DEFAULT_MAX_AGE)
+    }
+}
+
+/// Creates a queue with the values of the vector pushed now, in the same
+/// order, where the elements will live the duration given at maximum.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::from((vec![1, 2, 3], Duration::from_secs(60)));
+/// assert_eq!(queue.stats().sum, Some(6));
+/// assert_eq!(queue.max_age(), Duration::from_secs(60));
+/// ```
+impl<T> From<(Vec<T>, Duration)> for SumQueue<T> {
+    fn from((values, max_age_duration): (Vec<T>, Duration)) -> Self {
+        let mut queue = SumQueue::with_capacity(max_age_duration, values.len());
+        let now = queue.now();
+        let time = queue.stamp(now);
+        queue
+            .queue
+            .extend(values.into_iter().map(|value| QueueElement { time, value }));
+        queue
+    }
+}
+
+/// Returns the values of the queue, in the same order they were pushed,
+/// see [`SumQueue::into_vec()`]. Expired elements are not included.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push('a');
+/// queue.push('b');
+/// assert_eq!(Vec::from(queue), vec!['a', 'b']);
+/// ```
+impl<T> From<SumQueue<T>> for Vec<T> {
+    fn from(queue: SumQueue<T>) -> Self {
+        queue.into_vec()
+    }
+}
+
+/// Returns the values of the queue, in the same order they
+/// were pushed. Expired elements are not included.
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push('a');
+/// queue.push('b');
+/// assert_eq!(VecDeque::from(queue), VecDeque::from(vec!['a', 'b']));
+/// ```
+impl<T> From<SumQueue<T>> for VecDeque<T> {
+    fn from(mut queue: SumQueue<T>) -> Self {
+        queue.clear_oldest(queue.now());
+        queue.queue.drain(..).map(|el| el.value).collect()
+    }
+}
+
+/// Inserts the values with the ages given, like if they were pushed that
+/// time ago, eg. to load a snapshot or a test fixture, see
+/// [`SumQueue::extend_with_times()`]. Values older than the max age are dropped.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push(1);
+/// queue.extend(vec![
+///     (Duration::from_secs(30), 2),
+///     (Duration::from_secs(90), 3), // too old
+/// ]);
+/// assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &1]);
+/// ```
+impl<T> Extend<(Duration, T)> for SumQueue<T> {
+    fn extend<I: IntoIterator<Item = (Duration, T)>>(&mut self, iter: I) {
+        let now = self.now();
+        let max_age = self.max_age;
+        self.extend_with_times(iter.into_iter().filter_map(|(age, value)| {
+            if age > max_age {
+                return None;
+            }
+            now.checked_sub(age).map(|at| (at, value))
+        }));
+    }
+}
+
+/// Compares the queues by the values of their elements, in the
+/// same order they were pushed, ignoring the exact time they were pushed.
+///
+/// Because the comparison doesn't take a mutable reference of the
+/// queues, expired elements are not dropped, but they are not taken
+/// into account either, only the live elements of each queue are compared.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// let mut other = SumQueue::new(Duration::from_secs(10));
+/// queue.push(1);
+/// queue.push(5);
+/// other.push(1);
+/// assert!(queue != other);
+/// other.push(5);
+/// assert!(queue == other);
+/// ```
+impl<T: PartialEq> PartialEq for SumQueue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter_unpruned().eq(other.iter_unpruned())
+    }
+}
+
+/// Formats the queue showing its max age, its length, and
+/// the values with their ages.
+///
+/// Because formatting doesn't take a mutable reference of the
+/// queue, expired elements are not dropped before, so they
+/// may be listed as well.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumQueue;
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.push(1);
+/// queue.push(5);
+/// println!("{:?}", queue); // SumQueue { max_age: 60s, len: 2, elements: [(1, 7.2µs), (5, 3.1µs)] }
+/// ```
+impl<T: fmt::Debug> fmt::Debug for SumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = self.now();
+        let elements = self
+            .queue
+            .iter()
+            .map(|el| (&el.value, self.epoch.age(el.time, now)))
+            .collect::<Vec<_>>();
+        f.debug_struct("SumQueue")
+            .field("max_age", &self.max_age)
+            .field("len", &self.queue.len())
+            .field("elements", &elements)
+            .finish()
+    }
+}
+
+/// Structure wrapping a mutable reference to the first item of a `SumQueue`.
+///
+/// This `struct` is created by [`SumQueue::peek_mut()`]. See its
+/// documentation for more.
+pub struct PeekMut<'a, T: 'a> {
+    queue: &'a mut SumQueue<T>,
+}
+
+impl<'a, T> PeekMut<'a, T> {
+    /// Removes the peeked value from the queue and returns it,
+    /// like [`SumQueue::pop()`].
+    pub fn pop(this: PeekMut<'a, T>) -> T {
+        let now = this.queue.now();
+        let item = this.queue.pop_front(now).map(|q_element| q_element.value);
+        this.queue.record_depth();
+        item.unwrap()
+    }
+}
+
+impl<T> Deref for PeekMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.queue.queue[0].value
+    }
+}
+
+impl<T> DerefMut for PeekMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.queue.min_max_changed();
+        &mut self.queue.queue[0].value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PeekMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PeekMut").field(self.deref()).finish()
+    }
+}
+
+/// An iterator over the elements of a `SumQueue`.
+///
+/// This `struct` is created by [`SumQueue::iter()`]. See its
+/// documentation for more.
+pub struct Iter<'a, T: 'a> {
+    iter: vec_deque::Iter<'a, QueueElement<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let element = self.iter.next()?;
+        Some(&element.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        let element = self.iter.next_back()?;
+        Some(&element.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+/// A mutable iterator over the elements of a `SumQueue`.
+///
+/// This `struct` is created by [`SumQueue::iter_mut()`]. See its
+/// documentation for more.
+pub struct IterMut<'a, T: 'a> {
+    iter: vec_deque::IterMut<'a, QueueElement<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let element = self.iter.next()?;
+        Some(&mut element.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        let element = self.iter.next_back()?;
+        Some(&mut element.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+/// An iterator over the elements of a `SumQueue` and their ages.
+///
+/// This `struct` is created by [`SumQueue::iter_with_age()`]. See its
+/// documentation for more.
+pub struct IterWithAge<'a, T: 'a> {
+    iter: vec_deque::Iter<'a, QueueElement<T>>,
+    epoch: Epoch,
+    now: Instant,
+}
+
+impl<'a, T> Iterator for IterWithAge<'a, T> {
+    type Item = (&'a T, Duration);
+
+    fn next(&mut self) -> Option<(&'a T, Duration)> {
+        let element = self.iter.next()?;
+        Some((&element.value, self.epoch.age(element.time, self.now)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterWithAge<'a, T> {
+    fn next_back(&mut self) -> Option<(&'a T, Duration)> {
+        let element = self.iter.next_back()?;
+        Some((&element.value, self.epoch.age(element.time, self.now)))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterWithAge<'a, T> {}
+
+/// An iterator over the pairs of values of two `SumQueue` pushed at the same time.
+///
+/// This `struct` is created by [`SumQueue::zip_by_time()`]. See its
+/// documentation for more.
+pub struct ZipByTime<'a, T: 'a, U: 'a> {
+    left: vec_deque::Iter<'a, QueueElement<T>>,
+    left_epoch: Epoch,
+    right: Peekable<vec_deque::Iter<'a, QueueElement<U>>>,
+    right_epoch: Epoch,
+    tolerance: Duration,
+}
+
+impl<'a, T, U> Iterator for ZipByTime<'a, T, U> {
+    type Item = (&'a T, &'a U);
+
+    fn next(&mut self) -> Option<(&'a T, &'a U)> {
+        let (left_epoch, right_epoch) = (self.left_epoch, self.right_epoch);
+        let tolerance = self.tolerance;
+        for left in self.left.by_ref() {
+            let left_time = left_epoch.instant(left.time);
+            // skip the values of the other queue pushed too early to be paired
+            while self
+                .right
+                .next_if(|right| right_epoch.age(right.time, left_time) > tolerance)
+                .is_some()
+            {}
+            if let Some(right) = self.right.next_if(|right| {
+                right_epoch
+                    .instant(right.time)
+                    .saturating_duration_since(left_time)
+                    <= tolerance
+            }) {
+                return Some((&left.value, &right.value));
+            }
+        }
+        None
+    }
+}
+
+/// Returns the number of whole `bucket` durations within `age`.
+fn bucket_of(age: Duration, bucket: Duration) -> u64 {
+    (age.as_nanos() / bucket.as_nanos()) as u64
+}
+
+/// An iterator over the elements of a `SumQueue` grouped by time.
+///
+/// This `struct` is created by [`SumQueue::chunks_by_time()`]. See its
+/// documentation for more.
+pub struct ChunksByTime<'a, T: 'a> {
+    queue: &'a VecDeque<QueueElement<T>>,
+    /// first element of the next bucket
+    start: usize,
+    epoch: Epoch,
+    now: Instant,
+    bucket: Duration,
+    /// number of buckets left, the next one is `remaining - 1` buckets before now
+    remaining: u64,
+}
+
+impl<'a, T> Iterator for ChunksByTime<'a, T> {
+    type Item = Iter<'a, T>;
+
+    fn next(&mut self) -> Option<Iter<'a, T>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (epoch, now) = (self.epoch, self.now);
+        let (bucket, index) = (self.bucket, self.remaining);
+        let end = self
+            .queue
+            .partition_point(|el| bucket_of(epoch.age(el.time, now), bucket) >= index);
+        let start = std::mem::replace(&mut self.start, end);
+        Some(Iter {
+            iter: self.queue.range(start..end),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for ChunksByTime<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    pub use crate::{PeekMut, SumQueue};
+    pub use std::thread;
+    pub use std::time::Duration;
+
+    #[test]
+    fn push_pop_peek() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        queue.push(5);
+        assert_eq!(queue.push(2), 3); // push return queue length
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.peek(), Some(&1)); // still the same
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.peek(), None);
+        queue.push(1_000);
+        assert_eq!(queue.peek(), Some(&1_000));
+    }
+
+    #[test]
+    fn push_pop_peek_refs() {
+        let mut queue: SumQueue<&i32> = SumQueue::new(Duration::from_secs(60));
+        queue.push(&1);
+        queue.push(&5);
+        assert_eq!(queue.push(&2), 3);
+        assert_eq!(queue.peek(), Some(&&1));
+        assert_eq!(queue.peek(), Some(&&1));
+        assert_eq!(queue.pop(), Some(&1));
+        assert_eq!(queue.pop(), Some(&5));
+        assert_eq!(queue.pop(), Some(&2));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.peek(), None);
+        queue.push(&1_000);
+        assert_eq!(queue.peek(), Some(&&1_000));
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert!(queue.peek_mut().is_none());
+        queue.push(1);
+        sleep_millis(150);
+        queue.push(5);
+        *queue.peek_mut().unwrap() = 10;
+        assert_eq!(queue.peek(), Some(&10));
+        sleep_millis(100);
+        // the modified element keeps its age
+        assert_eq!(queue.peek(), Some(&5));
+        let first = queue.peek_mut().unwrap();
+        assert_eq!(format!("{:?}", first), "PeekMut(5)");
+        assert_eq!(PeekMut::pop(first), 5);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn peek_mut_pop() {
+        use crate::{Clock, QueueObserver};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Popped(Mutex<Vec<i32>>);
+
+        impl QueueObserver<i32> for Popped {
+            fn popped(&self, value: &i32) {
+                self.0.lock().unwrap().push(*value);
+            }
+        }
+
+        let clock = Clock::manual();
+        let popped = Arc::new(Popped::default());
+        let mut queue = SumQueue::new(Duration::from_secs(60))
+            .with_min_age(Duration::from_secs(5))
+            .with_residence_history(Duration::from_secs(60), 1)
+            .with_clock(clock.clone());
+        queue.subscribe(popped.clone());
+        queue.push(1);
+        queue.push(2);
+        // not ready to be popped yet
+        assert!(queue.peek_mut().is_none());
+        clock.advance(Duration::from_secs(5));
+        let first = queue.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(first), 1);
+        assert_eq!(*popped.0.lock().unwrap(), vec![1]);
+        assert_eq!(queue.residence_stats().unwrap().count, 1);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn pop_peek_with_age() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        sleep_millis(100);
+        queue.push(5);
+        let (value, age) = queue.peek_with_age().unwrap();
+        assert_eq!(value, &1);
+        assert!(age >= Duration::from_millis(100));
+        let (value, age) = queue.pop_with_age().unwrap();
+        assert_eq!(value, 1);
+        assert!(age >= Duration::from_millis(100));
+        let (value, age) = queue.pop_with_age().unwrap();
+        assert_eq!(value, 5);
+        assert!(age < Duration::from_millis(100));
+        assert_eq!(queue.pop_with_age(), None);
+        assert_eq!(queue.peek_with_age(), None);
+    }
+
+    #[test]
+    fn oldest_newest() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert_eq!(queue.oldest(), None);
+        assert_eq!(queue.newest(), None);
+        queue.push(1);
+        sleep_millis(150);
+        queue.push(5);
+        let (value, oldest_age) = queue.oldest().unwrap();
+        assert_eq!(value, &1);
+        assert!(oldest_age >= Duration::from_millis(150));
+        let (value, newest_age) = queue.newest().unwrap();
+        assert_eq!(value, &5);
+        assert!(newest_age < Duration::from_millis(150));
+        sleep_millis(100);
+        assert_eq!(queue.oldest().map(|(v, _)| *v), Some(5));
+        assert_eq!(queue.newest().map(|(v, _)| *v), Some(5));
+    }
+
+    #[test]
+    fn pop_n() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert!(queue.pop_n(3).is_empty());
+        queue.push(1);
+        queue.push(5);
+        queue.push(2);
+        queue.push(7);
+        assert_eq!(queue.pop_n(0), Vec::<i32>::new());
+        assert_eq!(queue.pop_n(3), vec![1, 5, 2]);
+        assert_eq!(queue.len(), 1);
+        queue.push(8);
+        sleep_millis(300);
+        // expired elements are not returned
+        assert!(queue.pop_n(3).is_empty());
+    }
+
+    #[test]
+    fn pop_while() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        assert!(queue.pop_while(|_| true).is_empty());
+        queue.push(1);
+        queue.push(5);
+        queue.push(2);
+        queue.push(7);
+        assert!(queue.pop_while(|&x| x > 1).is_empty());
+        assert_eq!(queue.pop_while(|&x| x < 6), vec![1, 5, 2]);
+        assert_eq!(queue.peek(), Some(&7));
+        assert_eq!(queue.pop_while(|_| true), vec![7]);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn min_age() {
+        let mut queue: SumQueue<i32> = SumQueue::builder()
+            .max_age(Duration::from_millis(300))
+            .min_age(Duration::from_millis(100))
+            .build();
+        assert_eq!(queue.min_age(), Duration::from_millis(100));
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.pop_with_age(), None);
+        assert!(queue.pop_n(2).is_empty());
+        assert!(queue.pop_while(|_| true).is_empty());
+        assert_eq!(queue.len(), 2);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(3);
+        assert_eq!(queue.ready_in(), Some(Duration::ZERO));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop_n(5), vec![2]);
+        assert!(queue.ready_in().unwrap() > Duration::ZERO);
+        // the max age still expires the elements
+        thread::sleep(Duration::from_millis(350));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn append() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        let mut other: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        other.push(1);
+        // the times are stored in milliseconds
+        sleep_millis(2);
+        queue.push(2);
+        sleep_millis(150);
+        other.push(3);
+        sleep_millis(2);
+        queue.push(4);
+        assert_eq!(queue.append(&mut other), 4);
+        assert_eq!(other.len(), 0);
+        assert_eq!(queue.peek(), Some(&1));
+        sleep_millis(100);
+        // moved elements expire with the times they were pushed into other
+        assert_eq!(queue.pop_n(4), vec![3, 4]);
     }
 
-    /// Pushes an item onto the heap of the queue, and returns
-    /// the stats of the queue. The type of the elements
-    /// on it need to implements the `Copy`, `Ord` and `Add`
-    /// traits.
-    ///
-    /// Before push and return the stats, it also drops all expired elements.
-    ///
-    /// ```
-    /// use std::time::Duration;
-    /// use sum_queue::SumQueue;
-    /// let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(1000));
-    /// queue.push(-10);
-    /// queue.push(50);
-    /// queue.push(40);
-    /// let stats = queue.push_and_stats(20);
-    /// assert_eq!(stats.min, Some(-10));
-    /// assert_eq!(stats.max, Some(50));
-    /// assert_eq!(stats.sum, Some(100));
-    /// assert_eq!(stats.len, 4);
-    /// ```
-    ///
-    /// Use `push` instead if you don't need the stats
-    /// or the elements in the heap don't implement
-    /// any of the required traits.
-    pub fn push_and_stats(&mut self, item: T) -> QueueStats<T> {
-        let len = self.push(item);
-        self._stats(len)
+    #[test]
+    fn append_reuses_memory() {
+        let mut queue = SumQueue::with_capacity(Duration::from_secs(60), 8);
+        let mut other = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        other.push(2);
+        other.push(3);
+        let capacity = queue.capacity();
+        assert_eq!(queue.append(&mut other), 3);
+        assert_eq!(queue.capacity(), capacity);
+        assert_eq!(queue.to_vec(), vec![1, 2, 3]);
     }
-}
 
-/// An iterator over the elements of a `SumQueue`.
-///
-/// This `struct` is created by [`SumQueue::iter()`]. See its
-/// documentation for more.
-pub struct Iter<'a, T: 'a> {
-    iter: binary_heap::Iter<'a, QueueElement<T>>,
-}
+    #[test]
+    fn split_off_older_than() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(300));
+        queue.push(1);
+        queue.push(5);
+        sleep_millis(150);
+        queue.push(2);
+        let mut older = queue.split_off_older_than(Duration::from_millis(100));
+        assert_eq!(older.max_age(), queue.max_age());
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(older.len(), 2);
+        assert!(queue
+            .split_off_older_than(Duration::from_millis(100))
+            .is_empty());
+        sleep_millis(200);
+        // moved elements still expire at the time they were pushed
+        assert_eq!(older.len(), 0);
+        assert_eq!(queue.len(), 1);
+    }
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
+    #[test]
+    fn clone() {
+        let mut queue: SumQueue<String> = SumQueue::new(Duration::from_millis(200));
+        queue.push("a".to_string());
+        sleep_millis(100);
+        queue.push("b".to_string());
+        let mut copy = queue.clone();
+        assert_eq!(copy.max_age(), queue.max_age());
+        copy.push("c".to_string());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(copy.len(), 3);
+        sleep_millis(150);
+        // the copy keeps the original ages
+        assert_eq!(copy.iter().collect::<Vec<_>>(), vec!["b", "c"]);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec!["b"]);
+    }
 
-    fn next(&mut self) -> Option<&'a T> {
-        let element = self.iter.next()?;
-        Some(&element.value)
+    #[test]
+    fn debug() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(
+            format!("{:?}", queue),
+            "SumQueue { max_age: 60s, len: 0, elements: [] }"
+        );
+        queue.push(1);
+        queue.push(5);
+        let output = format!("{:?}", queue);
+        assert!(output.starts_with("SumQueue { max_age: 60s, len: 2, elements: [(1, "));
+        assert!(output.contains("), (5, "));
+        let output = format!("{:?}", queue.stats());
+        assert_eq!(
+            output,
+            "QueueStats { min: Some(1), max: Some(5), sum: Some(6), len: 2 }"
+        );
     }
-}
 
-mod tests {
-    pub use crate::SumQueue;
-    pub use std::thread;
-    pub use std::time::Duration;
+    #[test]
+    fn eq() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(100));
+        let mut other: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        assert!(queue == other);
+        queue.push(1);
+        other.push(1);
+        assert!(queue == other);
+        queue.push(2);
+        other.push(3);
+        assert!(queue != other);
+        let mut copy = other.clone();
+        assert!(copy == other);
+        copy.pop();
+        assert!(copy != other);
+        queue.clear();
+        other.clear();
+        queue.push(4);
+        sleep_millis(50);
+        other.push(4);
+        assert!(queue == other);
+        queue.push(5);
+        other.push(5);
+        sleep_millis(70);
+        // the first element of queue expired,
+        // even when queue was not accessed
+        assert!(queue != other);
+    }
 
     #[test]
-    fn push_pop_peek() {
+    fn pop_after_push_keeps_order() {
         let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        for i in 0..10 {
+            queue.push(i);
+        }
+        queue.pop();
+        queue.pop();
+        queue.push(10);
+        assert_eq!(
+            queue.iter().collect::<Vec<_>>(),
+            vec![&2, &3, &4, &5, &6, &7, &8, &9, &10]
+        );
+    }
+
+    #[test]
+    fn to_vec() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert!(queue.to_vec().is_empty());
         queue.push(1);
+        sleep_millis(150);
         queue.push(5);
-        assert_eq!(queue.push(2), 3); // push return queue length
-        assert_eq!(queue.peek(), Some(&1));
-        assert_eq!(queue.peek(), Some(&1)); // still the same
-        assert_eq!(queue.pop(), Some(1));
-        assert_eq!(queue.pop(), Some(5));
-        assert_eq!(queue.pop(), Some(2));
-        assert_eq!(queue.pop(), None);
-        assert_eq!(queue.peek(), None);
-        queue.push(1_000);
-        assert_eq!(queue.peek(), Some(&1_000));
+        queue.push(2);
+        assert_eq!(queue.to_vec(), vec![1, 5, 2]);
+        let items = queue.to_vec_with_ages();
+        assert_eq!(items.len(), 3);
+        assert!(items[0].0 >= Duration::from_millis(150));
+        assert!(items[1].0 < Duration::from_millis(150));
+        sleep_millis(100);
+        assert_eq!(queue.clone().into_vec(), vec![5, 2]);
+        assert_eq!(queue.to_vec(), vec![5, 2]);
     }
 
     #[test]
-    fn push_pop_peek_refs() {
-        let mut queue: SumQueue<&i32> = SumQueue::new(Duration::from_secs(60));
-        queue.push(&1);
-        queue.push(&5);
-        assert_eq!(queue.push(&2), 3);
-        assert_eq!(queue.peek(), Some(&&1));
-        assert_eq!(queue.peek(), Some(&&1));
-        assert_eq!(queue.pop(), Some(&1));
-        assert_eq!(queue.pop(), Some(&5));
-        assert_eq!(queue.pop(), Some(&2));
-        assert_eq!(queue.pop(), None);
-        assert_eq!(queue.peek(), None);
-        queue.push(&1_000);
-        assert_eq!(queue.peek(), Some(&&1_000));
+    fn unpruned() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert_eq!(queue.len_unpruned(), 0);
+        assert_eq!(queue.iter_unpruned().count(), 0);
+        queue.push(1);
+        sleep_millis(150);
+        queue.push(5);
+        assert_eq!(queue.len_unpruned(), 2);
+        assert_eq!(queue.iter_unpruned().collect::<Vec<_>>(), vec![&1, &5]);
+        sleep_millis(100);
+        assert_eq!(queue.len_unpruned(), 1);
+        assert_eq!(queue.iter_unpruned().collect::<Vec<_>>(), vec![&5]);
+        // the expired element is still there until pruned
+        assert_eq!(queue.queue.len(), 2);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.queue.len(), 1);
     }
 
     #[test]
@@ -536,6 +2899,360 @@ mod tests {
         assert_eq!(queue.len(), 1);
     }
 
+    #[test]
+    fn keep_only_last() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(300));
+        assert_eq!(queue.keep_only_last(Duration::ZERO), 0);
+        queue.push(1);
+        sleep_millis(100);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.keep_only_last(Duration::from_secs(1)), 0);
+        assert_eq!(queue.keep_only_last(Duration::from_millis(50)), 1);
+        assert_eq!(queue.to_vec(), vec![2, 3]);
+        sleep_millis(100);
+        // the max age is still the same
+        queue.push(4);
+        assert_eq!(queue.to_vec(), vec![2, 3, 4]);
+        assert_eq!(queue.keep_only_last(Duration::ZERO), 3);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn keep_only_last_expire() {
+        use crate::{Clock, QueueObserver};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Expired(Mutex<Vec<i32>>);
+
+        impl QueueObserver<i32> for Expired {
+            fn expired(&self, value: &i32) {
+                self.0.lock().unwrap().push(*value);
+            }
+        }
+
+        let clock = Clock::manual();
+        let expired = Arc::new(Expired::default());
+        let on_expire = Arc::new(Mutex::new(Vec::new()));
+        let dropped = on_expire.clone();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(60))
+            .on_expire(move |value| dropped.lock().unwrap().push(value))
+            .build()
+            .with_depth_history(Duration::from_secs(60), 1)
+            .with_clock(clock.clone());
+        queue.subscribe(expired.clone());
+        queue.push(1);
+        queue.push(2);
+        clock.advance(Duration::from_secs(10));
+        queue.push(3);
+        assert_eq!(queue.keep_only_last(Duration::from_secs(5)), 2);
+        assert_eq!(*expired.0.lock().unwrap(), vec![1, 2]);
+        assert_eq!(*on_expire.lock().unwrap(), vec![1, 2]);
+        assert_eq!(queue.depth_stats().unwrap().min, 1);
+    }
+
+    #[test]
+    fn truncate_to_newest() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert_eq!(queue.truncate_to_newest(0), 0);
+        queue.push(1);
+        queue.push(2);
+        sleep_millis(250);
+        queue.push(3);
+        queue.push(4);
+        queue.push(5);
+        // the expired items are not counted
+        assert_eq!(queue.truncate_to_newest(2), 1);
+        assert_eq!(queue.to_vec(), vec![4, 5]);
+        assert_eq!(queue.truncate_to_newest(0), 2);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn truncate_to_newest_expire() {
+        use crate::QueueObserver;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Expired(Mutex<Vec<i32>>);
+
+        impl QueueObserver<i32> for Expired {
+            fn expired(&self, value: &i32) {
+                self.0.lock().unwrap().push(*value);
+            }
+        }
+
+        let expired = Arc::new(Expired::default());
+        let on_expire = Arc::new(Mutex::new(Vec::new()));
+        let dropped = on_expire.clone();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(60))
+            .on_expire(move |value| dropped.lock().unwrap().push(value))
+            .build()
+            .with_depth_history(Duration::from_secs(60), 1);
+        queue.subscribe(expired.clone());
+        for i in 1..=4 {
+            queue.push(i);
+        }
+        assert_eq!(queue.truncate_to_newest(1), 3);
+        assert_eq!(*expired.0.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(*on_expire.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(queue.depth_stats().unwrap().min, 1);
+    }
+
+    #[test]
+    fn pop_min_max() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert_eq!(queue.pop_min(), None);
+        assert_eq!(queue.pop_max(), None);
+        queue.push(1);
+        sleep_millis(250);
+        queue.push(2);
+        queue.push(9);
+        queue.push(5);
+        queue.push(2);
+        queue.push(9);
+        // the expired element is not the min
+        assert_eq!(queue.pop_min(), Some(2));
+        assert_eq!(queue.pop_max(), Some(9));
+        // the oldest of the equal elements are popped
+        assert_eq!(queue.to_vec(), vec![5, 2, 9]);
+        assert_eq!(queue.pop_min(), Some(2));
+        assert_eq!(queue.pop_min(), Some(5));
+        assert_eq!(queue.pop_max(), Some(9));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pop_newest() {
+        let mut queue: SumQueue<i32> =
+            SumQueue::new(Duration::from_millis(200)).with_min_age(Duration::from_millis(50));
+        assert_eq!(queue.pop_newest(), None);
+        queue.push(1);
+        queue.push(2);
+        sleep_millis(100);
+        queue.push(3);
+        // the newest is not old enough to be popped
+        assert_eq!(queue.pop_newest(), None);
+        sleep_millis(60);
+        assert_eq!(queue.pop_newest(), Some(3));
+        assert_eq!(queue.pop_newest(), Some(2));
+        sleep_millis(60);
+        // the expired elements are not popped
+        assert_eq!(queue.pop_newest(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn map() {
+        let mut queue: SumQueue<&str> = SumQueue::builder()
+            .max_age(Duration::from_millis(200))
+            .max_len(3)
+            .build();
+        queue.push("a");
+        sleep_millis(100);
+        queue.push("bb");
+        queue.push("ccc");
+        let mut lengths = queue.map(str::len);
+        assert_eq!(lengths.to_vec(), vec![1, 2, 3]);
+        assert_eq!(lengths.max_len(), Some(3));
+        sleep_millis(50);
+        lengths.push(4);
+        assert_eq!(lengths.to_vec(), vec![2, 3, 4]);
+        sleep_millis(170);
+        // the elements keep their push times
+        assert_eq!(lengths.to_vec(), vec![4]);
+    }
+
+    #[test]
+    fn filter_map() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        queue.push(-1);
+        sleep_millis(150);
+        queue.push(4);
+        queue.push(-9);
+        queue.push(16);
+        sleep_millis(100);
+        let mut calls = 0;
+        let mut positives = queue.filter_map(|value| {
+            calls += 1;
+            if value > 0 {
+                Some(value as u32)
+            } else {
+                None
+            }
+        });
+        // the expired element is skipped
+        assert_eq!(calls, 3);
+        assert_eq!(positives.to_vec(), vec![4, 16]);
+        sleep_millis(150);
+        // the elements keep their push times
+        assert!(positives.is_empty());
+    }
+
+    #[test]
+    fn filter_map_grace() {
+        use crate::Clock;
+
+        let clock = Clock::manual();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(10))
+            .grace(Duration::from_secs(10))
+            .clock(clock.clone())
+            .build()
+            .with_min_max();
+        queue.push(-1);
+        queue.push(2);
+        let before = clock.now();
+        clock.advance(Duration::from_secs(15));
+        queue.push(3);
+        assert_eq!(queue.max(), Some(&3));
+        let mut positives = queue.filter_map(|value| (value > 0).then_some(value as u32));
+        // the elements within the grace period are transformed as well
+        assert_eq!(positives.to_vec(), vec![3]);
+        assert_eq!(positives.stats_as_of(before).sum, Some(2));
+        // the min and max of another type of values are not kept
+        assert!(positives.min_max.is_none());
+        assert_eq!(positives.max(), Some(&3));
+    }
+
+    #[cfg(feature = "num-traits")]
+    #[test]
+    fn float_stats() {
+        let mut queue = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.stats().sum_or_zero(), 0.0);
+        queue.push(2.5f32);
+        queue.push(-1.0);
+        let mut other = SumQueue::new(Duration::from_secs(60));
+        other.push(4.0);
+        let stats = queue.stats().merge(other.stats());
+        assert_eq!(
+            (stats.min, stats.max, stats.sum, stats.len),
+            (Some(-1.0), Some(4.0), Some(5.5), 3)
+        );
+    }
+
+    #[test]
+    fn stats_widened() {
+        let mut queue: SumQueue<i16> = SumQueue::new(Duration::from_secs(60));
+        let stats = queue.stats_widened::<i128>();
+        assert_eq!((stats.sum, stats.len), (None, 0));
+        for _ in 0..3 {
+            queue.push(i16::MIN);
+        }
+        queue.push(7);
+        let stats = queue.stats_widened::<i128>();
+        assert_eq!(
+            (stats.min, stats.max, stats.sum, stats.len),
+            (Some(i16::MIN), Some(7), Some(3 * i16::MIN as i128 + 7), 4)
+        );
+        let merged = stats.clone().merge(stats);
+        assert_eq!(merged.sum, Some(6 * i16::MIN as i128 + 14));
+    }
+
+    #[test]
+    fn extend_with_ages() {
+        let mut queue = SumQueue::new(Duration::from_millis(200));
+        queue.push(1);
+        queue.extend(vec![
+            (Duration::from_millis(100), 2),
+            (Duration::ZERO, 3),
+            (Duration::from_millis(300), 4),
+        ]);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2, &1, &3]);
+        let (_, age) = queue.peek_with_age().unwrap();
+        assert!(age >= Duration::from_millis(100));
+        sleep_millis(150);
+        // the element inserted with an age expires first
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &3]);
+    }
+
+    #[test]
+    fn from_into() {
+        use std::collections::VecDeque;
+        let mut queue = SumQueue::from((vec![1, 2], Duration::from_millis(100)));
+        queue.push(3);
+        assert_eq!(Vec::from(queue.clone()), vec![1, 2, 3]);
+        sleep_millis(150);
+        // expired elements are not included
+        assert_eq!(VecDeque::from(queue.clone()), VecDeque::new());
+        let empty: Vec<i32> = queue.into();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn contains() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert!(!queue.contains(&1));
+        queue.push(1);
+        queue.push(5);
+        assert!(queue.contains(&1));
+        assert!(queue.contains(&5));
+        assert!(!queue.contains(&2));
+        sleep_millis(300);
+        // expired elements are not found
+        assert!(!queue.contains(&1));
+        assert!(!queue.contains(&5));
+    }
+
+    #[test]
+    fn push_unique() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(200));
+        assert!(queue.push_unique(1));
+        assert!(queue.push_unique(2));
+        assert!(!queue.push_unique(1));
+        assert_eq!(queue.to_vec(), vec![1, 2]);
+        sleep_millis(300);
+        // the expired element doesn't prevent the push
+        assert!(queue.push_unique(1));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn push_at() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(300));
+        let start = crate::now();
+        sleep_millis(100);
+        queue.push(1);
+        assert_eq!(queue.push_at(start, 0), Ok(2));
+        assert_eq!(queue.push_at(start + Duration::from_secs(60), 9), Ok(3));
+        assert_eq!(queue.to_vec(), vec![0, 1, 9]);
+        sleep_millis(250);
+        // the backdated element expires first
+        assert_eq!(queue.to_vec(), vec![1, 9]);
+        assert_eq!(queue.push_at(start, 0), Err(0));
+        // the element from the future was clamped to the time it was pushed
+        sleep_millis(100);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn extend_with_times() {
+        let mut queue: SumQueue<i32> = SumQueue::builder()
+            .max_age(Duration::from_millis(300))
+            .max_len(4)
+            .build();
+        let start = crate::now();
+        sleep_millis(100);
+        queue.push(10);
+        let now = crate::now();
+        let history = vec![
+            (now, 11),
+            (start, 0),
+            (start + Duration::from_millis(20), 5),
+            (start, 1),
+            (now + Duration::from_secs(60), 12),
+        ];
+        // the oldest elements are dropped beyond the max length
+        assert_eq!(queue.extend_with_times(history), 4);
+        assert_eq!(queue.to_vec(), vec![5, 10, 11, 12]);
+        sleep_millis(250);
+        assert_eq!(queue.to_vec(), vec![10, 11, 12]);
+        assert_eq!(queue.extend_with_times(vec![(start, 0)]), 3);
+    }
+
     #[test]
     fn iter() {
         let mut queue: SumQueue<&str> = SumQueue::with_capacity(Duration::from_secs(60), 20);
@@ -556,6 +3273,138 @@ mod tests {
         println!();
     }
 
+    #[test]
+    fn iter_with_age() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        sleep_millis(100);
+        queue.push(5);
+        let items = queue.iter_with_age().collect::<Vec<_>>();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].0, &1);
+        assert_eq!(items[1].0, &5);
+        assert!(items[0].1 >= Duration::from_millis(100));
+        assert!(items[1].1 < items[0].1);
+    }
+
+    #[test]
+    fn iter_older_newer_than() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(300));
+        queue.push(1);
+        sleep_millis(100);
+        queue.push(2);
+        sleep_millis(100);
+        queue.push(3);
+        let age = Duration::from_millis(150);
+        assert_eq!(queue.iter_older_than(age).collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(queue.iter_newer_than(age).collect::<Vec<_>>(), vec![&2, &3]);
+        assert_eq!(queue.iter_newer_than(Duration::ZERO).len(), 0);
+        sleep_millis(150);
+        // expired elements are not visited
+        assert_eq!(queue.iter_older_than(age).collect::<Vec<_>>(), vec![&2, &3]);
+        assert_eq!(queue.iter_older_than(Duration::from_secs(1)).len(), 0);
+    }
+
+    #[test]
+    fn partition_by_age() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(300));
+        assert_eq!(queue.partition_by_age(Duration::ZERO), (vec![], vec![]));
+        queue.push(1);
+        sleep_millis(100);
+        queue.push(2);
+        sleep_millis(100);
+        queue.push(3);
+        let (newer, older) = queue.partition_by_age(Duration::from_millis(150));
+        assert_eq!((newer, older), (vec![&2, &3], vec![&1]));
+        sleep_millis(150);
+        let (newer, older) = queue.partition_by_age(Duration::from_millis(200));
+        assert_eq!((newer, older), (vec![&3], vec![&2]));
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn chunks_by_time() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_millis(500));
+        let bucket = Duration::from_millis(100);
+        assert_eq!(queue.chunks_by_time(bucket).len(), 0);
+        queue.push(1);
+        sleep_millis(250);
+        queue.push(2);
+        queue.push(3);
+        sleep_millis(20);
+        queue.push(4);
+        let chunks = queue
+            .chunks_by_time(bucket)
+            .map(|chunk| chunk.copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        // the bucket in between is empty
+        assert_eq!(chunks, vec![vec![1], vec![], vec![2, 3, 4]]);
+        let mut chunks = queue.chunks_by_time(Duration::from_secs(1));
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks.next().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut queue: SumQueue<String> = SumQueue::new(Duration::from_millis(200));
+        queue.push("a".to_string());
+        sleep_millis(150);
+        queue.push("b".to_string());
+        for value in queue.iter_mut() {
+            value.push('!');
+        }
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec!["a!", "b!"]);
+        if let Some(value) = queue.iter_mut().next_back() {
+            value.push('?');
+        }
+        sleep_millis(100);
+        // modified values keep their ages
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec!["b!?"]);
+    }
+
+    #[test]
+    fn iter_rev() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        sleep_millis(10);
+        queue.push(5);
+        queue.push(2);
+        assert_eq!(queue.iter().len(), 3);
+        assert_eq!(queue.iter().rev().collect::<Vec<_>>(), vec![&2, &5, &1]);
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&2));
+        assert_eq!(iter.next(), Some(&5));
+        assert_eq!(iter.next_back(), None);
+        let ages = queue.iter_with_age().rev().map(|(_, age)| age);
+        assert!(ages.collect::<Vec<_>>().windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn zip_by_time() {
+        let mut a: SumQueue<i32> = SumQueue::new(Duration::from_millis(500));
+        let mut b: SumQueue<char> = SumQueue::new(Duration::from_secs(60));
+        let tolerance = Duration::from_millis(30);
+        b.push('x');
+        sleep_millis(100);
+        a.push(1);
+        a.push(2);
+        b.push('a');
+        sleep_millis(100);
+        b.push('b');
+        a.push(3);
+        b.push('c');
+        // each value is paired once
+        let pairs = a.zip_by_time(&mut b, tolerance).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(&1, &'a'), (&3, &'b')]);
+        sleep_millis(550);
+        // expired values are not paired
+        a.push(4);
+        b.push('d');
+        let pairs = a.zip_by_time(&mut b, tolerance).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(&4, &'d')]);
+    }
+
     #[test]
     fn expire() {
         let max_age_secs = 2;
@@ -680,13 +3529,46 @@ mod tests {
         assert_eq!(stats.len, 3);
     }
 
-    #[cfg(test)]
+    #[test]
+    fn stats_as_of() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let counter = dropped.clone();
+        let mut queue: SumQueue<i32> = SumQueue::builder()
+            .max_age(Duration::from_millis(100))
+            .grace(Duration::from_millis(200))
+            .on_expire(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })
+            .build();
+        assert_eq!(queue.grace(), Duration::from_millis(200));
+        queue.push(1);
+        queue.push(2);
+        let first = crate::now();
+        sleep_millis(150);
+        queue.push(3);
+        let second = crate::now();
+        // the expired elements are kept but not visible
+        assert_eq!(queue.to_vec(), vec![3]);
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+        let stats = queue.stats_as_of(first);
+        assert_eq!((stats.sum, stats.len), (Some(3), 2));
+        assert_eq!(queue.stats_as_of(second), queue.stats());
+        sleep_millis(200);
+        // the grace period of the first elements ended
+        assert_eq!(queue.stats_as_of(first).len, 0);
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+        assert_eq!(queue.stats_as_of(second).sum, Some(3));
+        queue.clear();
+        assert_eq!(queue.stats_as_of(second).len, 0);
+    }
+
     fn sleep_secs(dur_secs: u64) {
         println!("\nSleeping {} secs ...", dur_secs);
         thread::sleep(Duration::from_secs(dur_secs));
     }
 
-    #[cfg(test)]
     fn sleep_millis(dur_millis: u64) {
         println!("\nSleeping {} millis ...", dur_millis);
         thread::sleep(Duration::from_millis(dur_millis));
@@ -0,0 +1,308 @@
+use crate::lock::{self, Mutex, MutexGuard};
+use crate::{now, Instant, SumQueue};
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+/// Common interface of the rate limiters, [`RateLimiter`] and [`TokenBucket`],
+/// so the code that uses a limiter doesn't depend on its strategy.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::{Limiter, RateLimiter, TokenBucket};
+///
+/// fn send_all(limiter: &dyn Limiter, messages: &[&str]) -> usize {
+///     messages.iter().filter(|_| limiter.try_acquire()).count()
+/// }
+///
+/// let messages = ["a", "b", "c"];
+/// assert_eq!(send_all(&RateLimiter::new(2, Duration::from_secs(1)), &messages), 2);
+/// assert_eq!(send_all(&TokenBucket::new(1, Duration::from_secs(1)), &messages), 1);
+/// ```
+pub trait Limiter {
+    /// Registers a new event and returns `Ok` if the limit allows it, otherwise
+    /// returns `Err` with the time to wait until the event could be accepted.
+    fn check(&self) -> Result<(), Duration>;
+
+    /// Returns the number of events that can be accepted right now.
+    fn available(&self) -> usize;
+
+    /// Tries to register a new event, returning `true` if it's
+    /// accepted, or `false` if the limit was reached.
+    fn try_acquire(&self) -> bool {
+        self.check().is_ok()
+    }
+
+    /// Registers a new event, blocking the current thread
+    /// until the limit allows it.
+    fn acquire_wait(&self) {
+        while let Err(wait) = self.check() {
+            // the limit changes right after the waiting time
+            thread::sleep(wait + Duration::from_nanos(1));
+        }
+    }
+}
+
+/// Thread-safe rate limiter that allows up to `max_events` events
+/// in any sliding `window` of time.
+///
+/// The time of each accepted event is kept in a [`SumQueue`] that
+/// expires them after `window`, so an event is accepted
+/// as long as the queue has less than `max_events` elements.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::{Limiter, RateLimiter};
+///
+/// let limiter = RateLimiter::new(2, Duration::from_secs(1));
+/// assert!(limiter.try_acquire());
+/// assert!(limiter.try_acquire());
+/// // the third event within the same second is rejected
+/// assert!(!limiter.try_acquire());
+/// ```
+pub struct RateLimiter {
+    queue: Mutex<SumQueue<()>>,
+    max_events: usize,
+}
+
+impl RateLimiter {
+    /// Creates a limiter that allows up to `max_events` events
+    /// in any sliding `window` of time.
+    pub fn new(max_events: usize, window: Duration) -> RateLimiter {
+        RateLimiter {
+            queue: Mutex::new(SumQueue::with_capacity(window, max_events)),
+            max_events,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SumQueue<()>> {
+        lock::lock(&self.queue)
+    }
+
+    /// Returns the max number of events allowed in the window.
+    pub fn max_events(&self) -> usize {
+        self.max_events
+    }
+
+    /// Returns the duration of the sliding window.
+    pub fn window(&self) -> Duration {
+        self.lock().max_age()
+    }
+}
+
+/// The oldest event is kept until its age reaches the window, and
+/// the waiting time returned by [`Limiter::check()`] is the time left
+/// for that to happen.
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use sum_queue::{Limiter, RateLimiter};
+///
+/// let limiter = RateLimiter::new(1, Duration::from_millis(100));
+/// let start = Instant::now();
+/// limiter.acquire_wait();
+/// limiter.acquire_wait();
+/// assert!(start.elapsed() >= Duration::from_millis(100));
+/// ```
+///
+/// If `max_events` is zero no event is ever accepted.
+impl Limiter for RateLimiter {
+    fn check(&self) -> Result<(), Duration> {
+        let mut queue = self.lock();
+        if queue.len() < self.max_events {
+            queue.push(());
+            return Ok(());
+        }
+        let window = queue.max_age();
+        Err(match queue.peek_with_age() {
+            Some((_, age)) => window.saturating_sub(age),
+            None => window,
+        })
+    }
+
+    fn available(&self) -> usize {
+        self.max_events.saturating_sub(self.lock().len())
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut queue = self.lock();
+        f.debug_struct("RateLimiter")
+            .field("max_events", &self.max_events)
+            .field("window", &queue.max_age())
+            .field("events", &queue.len())
+            .finish()
+    }
+}
+
+/// Thread-safe rate limiter that allows bursts of up to `capacity`
+/// events, and then one event every `refill_every`.
+///
+/// The bucket starts full of tokens, each event takes one token, and a
+/// new token is added every `refill_every` until the bucket is full again.
+/// Unlike the [`RateLimiter`], idle time is "saved" as tokens
+/// for a later burst of events.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::{Limiter, TokenBucket};
+///
+/// let bucket = TokenBucket::new(3, Duration::from_millis(100));
+/// assert!(bucket.try_acquire());
+/// assert!(bucket.try_acquire());
+/// assert!(bucket.try_acquire());
+/// assert!(!bucket.try_acquire());
+/// assert_eq!(bucket.available(), 0);
+/// ```
+pub struct TokenBucket {
+    state: Mutex<TokenBucketState>,
+    capacity: usize,
+    refill_every: Duration,
+}
+
+struct TokenBucketState {
+    tokens: usize,
+    /// time when the last token was added
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket with `capacity` tokens, where a
+    /// new token is added every `refill_every`.
+    pub fn new(capacity: usize, refill_every: Duration) -> TokenBucket {
+        TokenBucket {
+            state: Mutex::new(TokenBucketState {
+                tokens: capacity,
+                refilled_at: now(),
+            }),
+            capacity,
+            refill_every,
+        }
+    }
+
+    /// Returns the max number of tokens of the bucket.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the time it takes to add a new token to the bucket.
+    pub fn refill_every(&self) -> Duration {
+        self.refill_every
+    }
+
+    /// Locks the state, adding the tokens earned since the last refill.
+    fn refill(&self) -> MutexGuard<'_, TokenBucketState> {
+        let mut state = lock::lock(&self.state);
+        let now = now();
+        let elapsed = now.saturating_duration_since(state.refilled_at);
+        let new_tokens = if self.refill_every.is_zero() {
+            self.capacity as u128
+        } else {
+            elapsed.as_nanos() / self.refill_every.as_nanos()
+        };
+        if state.tokens as u128 + new_tokens >= self.capacity as u128 {
+            state.tokens = self.capacity;
+            state.refilled_at = now;
+        } else if new_tokens > 0 {
+            state.tokens += new_tokens as usize;
+            state.refilled_at += self.refill_every * new_tokens as u32;
+        }
+        state
+    }
+}
+
+/// If `capacity` is zero no event is ever accepted.
+impl Limiter for TokenBucket {
+    fn check(&self) -> Result<(), Duration> {
+        let mut state = self.refill();
+        if state.tokens > 0 {
+            state.tokens -= 1;
+            return Ok(());
+        }
+        let elapsed = now().saturating_duration_since(state.refilled_at);
+        Err(self.refill_every.saturating_sub(elapsed))
+    }
+
+    fn available(&self) -> usize {
+        self.refill().tokens
+    }
+}
+
+impl fmt::Debug for TokenBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBucket")
+            .field("capacity", &self.capacity)
+            .field("refill_every", &self.refill_every)
+            .field("tokens", &self.refill().tokens)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Limiter, RateLimiter, TokenBucket};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn try_acquire() {
+        let limiter = RateLimiter::new(3, Duration::from_millis(100));
+        assert_eq!(limiter.max_events(), 3);
+        assert_eq!(limiter.window(), Duration::from_millis(100));
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert_eq!(limiter.available(), 1);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        assert_eq!(limiter.available(), 0);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(limiter.available(), 3);
+        assert!(limiter.try_acquire());
+        assert!(!RateLimiter::new(0, Duration::from_secs(1)).try_acquire());
+    }
+
+    #[test]
+    fn acquire_wait_threads() {
+        let limiter = Arc::new(RateLimiter::new(2, Duration::from_millis(100)));
+        let start = Instant::now();
+        let threads = (0..3)
+            .map(|_| {
+                let limiter = limiter.clone();
+                thread::spawn(move || {
+                    for _ in 0..2 {
+                        limiter.acquire_wait();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+        // 6 events, 2 every 100ms
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn token_bucket() {
+        let bucket = TokenBucket::new(2, Duration::from_millis(50));
+        assert_eq!(bucket.capacity(), 2);
+        assert_eq!(bucket.refill_every(), Duration::from_millis(50));
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        let wait = bucket.check().unwrap_err();
+        assert!(wait <= Duration::from_millis(50));
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(bucket.available(), 1);
+        // idle time doesn't add more tokens than the capacity
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(bucket.available(), 2);
+        let start = Instant::now();
+        for _ in 0..3 {
+            bucket.acquire_wait();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(!TokenBucket::new(0, Duration::from_millis(1)).try_acquire());
+    }
+}
@@ -0,0 +1,28 @@
+//! Lock used by the thread-safe queues.
+//!
+//! It's the standard [`std::sync::Mutex`], or the `parking_lot::Mutex`
+//! when the `parking_lot` feature is enabled. None of them is poisoned
+//! when a thread panics while holding the lock.
+
+#[cfg(feature = "parking_lot")]
+pub(crate) use parking_lot::{Mutex, MutexGuard};
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::PoisonError;
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) use std::sync::{Mutex, MutexGuard};
+
+/// Acquires the lock, blocking the current thread until it's able to do so.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    #[cfg(feature = "parking_lot")]
+    return mutex.lock();
+    #[cfg(not(feature = "parking_lot"))]
+    return mutex.lock().unwrap_or_else(PoisonError::into_inner);
+}
+
+/// Consumes the lock, returning the inner value.
+pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+    #[cfg(feature = "parking_lot")]
+    return mutex.into_inner();
+    #[cfg(not(feature = "parking_lot"))]
+    return mutex.into_inner().unwrap_or_else(PoisonError::into_inner);
+}
@@ -0,0 +1,354 @@
+//! `sum-queue` command line tool: reads numbers from the standard input,
+//! one per line or separated by commas, and prints the stats of the
+//! numbers read in the last window of time.
+//!
+//! ```text
+//! $ tail -f latencies.log | sum-queue --window 60s --format json
+//! {"len":1,"sum":12.5,"min":12.5,"max":12.5,"avg":12.5}
+//! {"len":2,"sum":20,"min":7.5,"max":12.5,"avg":10}
+//! ```
+//!
+//! With `--dashboard` it shows instead a live view of the
+//! stats, a sparkline and a histogram of the window, refreshed each second.
+
+use clap::{Arg, ArgAction, Command};
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use sum_queue::{OrderedFloat, QueueStats, SumQueue};
+
+/// Format of the stats printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Dashboard,
+}
+
+/// Number of buckets of the histogram of the dashboard.
+const BUCKETS: usize = 10;
+
+/// Width of the sparkline of the dashboard.
+const SPARKLINE_WIDTH: usize = 60;
+
+/// Parses a duration like `500ms`, `60s`, `5m` or `1h`,
+/// a number without unit is taken as seconds.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration {:?}", s))?;
+    let secs = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => {
+            return Err(format!(
+                "invalid duration unit {:?}, use ms, s, m or h",
+                unit
+            ))
+        }
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| format!("invalid duration {:?}", s))
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    match s {
+        "text" => Ok(Format::Text),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("invalid format {:?}, use text or json", s)),
+    }
+}
+
+/// Parses the numbers of a line, separated by commas or spaces,
+/// returning the fields that are not valid numbers as errors.
+fn parse_values(line: &str) -> Vec<Result<f64, String>> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|field| !field.is_empty())
+        .map(|field| match field.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(format!("invalid number {:?}", field)),
+        })
+        .collect()
+}
+
+fn format_value(value: Option<f64>, format: Format) -> String {
+    match (value, format) {
+        (Some(value), _) => value.to_string(),
+        (None, Format::Json) => "null".to_string(),
+        (None, _) => "-".to_string(),
+    }
+}
+
+fn format_stats(stats: &QueueStats<f64>, format: Format) -> String {
+    let avg = stats.sum.map(|sum| sum / stats.len as f64);
+    let fields = [
+        ("len", stats.len.to_string()),
+        ("sum", format_value(stats.sum, format)),
+        ("min", format_value(stats.min, format)),
+        ("max", format_value(stats.max, format)),
+        ("avg", format_value(avg, format)),
+    ];
+    match format {
+        Format::Text | Format::Dashboard => fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Format::Json => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("\"{}\":{}", name, value))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(","))
+        }
+    }
+}
+
+/// Renders a screen of the dashboard, with the escape codes to
+/// draw it over the previous one from the top of the terminal.
+fn render_dashboard(
+    window: Duration,
+    stats: &QueueStats<f64>,
+    sparkline: &str,
+    histogram: &str,
+) -> String {
+    let mut lines = vec![format!("sum-queue - window of {:?}", window), String::new()];
+    lines.extend(
+        format_stats(stats, Format::Dashboard)
+            .split(' ')
+            .map(|field| {
+                let (name, value) = field.split_at(field.find('=').unwrap());
+                format!("{:<4}{}", name, &value[1..])
+            }),
+    );
+    lines.push(String::new());
+    lines.push(sparkline.to_string());
+    lines.push(String::new());
+    lines.extend(histogram.lines().map(String::from));
+    let lines = lines
+        .iter()
+        .map(|line| format!("{}\x1b[K\n", line))
+        .collect::<String>();
+    format!("\x1b[H{}\x1b[J", lines)
+}
+
+/// Window of numbers read from the standard input.
+struct Window {
+    queue: SumQueue<OrderedFloat<f64>>,
+    format: Format,
+}
+
+impl Window {
+    /// Pushes the numbers of the line, warning about the invalid ones.
+    fn push_line(&mut self, line: &str) {
+        for value in parse_values(line) {
+            match value {
+                Ok(value) => {
+                    self.queue.push_float(value);
+                }
+                Err(err) => eprintln!("sum-queue: {}", err),
+            }
+        }
+    }
+
+    fn print(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let stats = self.queue.stats_float();
+        if self.format == Format::Dashboard {
+            let sparkline = self.queue.sparkline(SPARKLINE_WIDTH);
+            let histogram = self.queue.ascii_histogram(BUCKETS);
+            let screen = render_dashboard(self.queue.max_age(), &stats, &sparkline, &histogram);
+            write!(out, "{}", screen)?;
+        } else {
+            writeln!(out, "{}", format_stats(&stats, self.format))?;
+        }
+        out.flush()
+    }
+
+    /// Prints the stats after each line read.
+    fn run_per_line(&mut self, input: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            self.push_line(&line?);
+            self.print(out)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the stats each `interval`, and once more when the input ends.
+    fn run_per_interval(&mut self, interval: Duration, out: &mut impl Write) -> io::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut next = Instant::now() + interval;
+        loop {
+            match rx.recv_timeout(next.saturating_duration_since(Instant::now())) {
+                Ok(line) => self.push_line(&line?),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.print(out)?;
+                    next += interval;
+                }
+                Err(RecvTimeoutError::Disconnected) => return self.print(out),
+            }
+        }
+    }
+}
+
+fn command() -> Command {
+    Command::new("sum-queue")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Reads numbers from stdin, one per line or comma separated, and prints the stats of the last window of time")
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .short('w')
+                .value_name("DURATION")
+                .default_value("60s")
+                .value_parser(parse_duration)
+                .help("Max age of the numbers in the window, eg. 500ms, 60s, 5m or 1h"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .value_name("FORMAT")
+                .default_value("text")
+                .value_parser(parse_format)
+                .help("Format of the stats: text or json"),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .short('i')
+                .value_name("DURATION")
+                .value_parser(parse_duration)
+                .help("Print the stats each interval instead of after each line"),
+        )
+        .arg(
+            Arg::new("dashboard")
+                .long("dashboard")
+                .short('d')
+                .action(ArgAction::SetTrue)
+                .conflicts_with("format")
+                .help("Show a live view of the stats, a sparkline and a histogram, refreshed each interval (1s by default)"),
+        )
+}
+
+fn main() {
+    let matches = command().get_matches();
+    let dashboard = matches.get_flag("dashboard");
+    let mut window = Window {
+        queue: SumQueue::new(matches.get_one::<Duration>("window").copied().unwrap()),
+        format: match dashboard {
+            true => Format::Dashboard,
+            false => matches.get_one::<Format>("format").copied().unwrap(),
+        },
+    };
+    let mut interval = matches.get_one::<Duration>("interval").copied();
+    if dashboard {
+        interval = interval.or(Some(Duration::from_secs(1)));
+    }
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let result = match interval {
+        Some(interval) => window.run_per_interval(interval, &mut out),
+        None => window.run_per_line(io::stdin().lock(), &mut out),
+    };
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("sum-queue: {}", err);
+            process::exit(1);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        command, format_stats, parse_duration, parse_values, render_dashboard, Format, Window,
+    };
+    use std::time::Duration;
+    use sum_queue::{QueueStats, SumQueue};
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("10"), Ok(Duration::from_secs(10)));
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("s").is_err());
+        command().debug_assert();
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_values("1, 2.5,-3"), vec![Ok(1.0), Ok(2.5), Ok(-3.0)]);
+        assert_eq!(parse_values("  "), vec![]);
+        let values = parse_values("4 x nan");
+        assert_eq!(values[0], Ok(4.0));
+        assert!(values[1].is_err());
+        assert!(values[2].is_err());
+    }
+
+    #[test]
+    fn stats() {
+        let empty = QueueStats::default();
+        assert_eq!(
+            format_stats(&empty, Format::Text),
+            "len=0 sum=- min=- max=- avg=-"
+        );
+        assert_eq!(
+            format_stats(&empty, Format::Json),
+            r#"{"len":0,"sum":null,"min":null,"max":null,"avg":null}"#
+        );
+        let mut window = Window {
+            queue: SumQueue::new(Duration::from_secs(60)),
+            format: Format::Json,
+        };
+        let mut out = Vec::new();
+        window
+            .run_per_line("1,2\nfoo\n4.5\n".as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().lines().collect::<Vec<_>>(),
+            vec![
+                r#"{"len":2,"sum":3,"min":1,"max":2,"avg":1.5}"#,
+                r#"{"len":2,"sum":3,"min":1,"max":2,"avg":1.5}"#,
+                r#"{"len":3,"sum":7.5,"min":1,"max":4.5,"avg":2.5}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn dashboard() {
+        let stats = QueueStats {
+            min: Some(1.0),
+            max: Some(3.0),
+            sum: Some(4.0),
+            len: 2,
+        };
+        let histogram = "1 | ## 1\n2 | ## 1\n";
+        let screen = render_dashboard(Duration::from_secs(60), &stats, "▁█", histogram);
+        assert!(screen.starts_with("\x1b[Hsum-queue - window of 60s\x1b[K\n"));
+        assert!(screen.contains("avg 2\x1b[K\n"));
+        assert!(screen.contains("\n▁█\x1b[K\n"));
+        assert!(screen.contains("\n2 | ## 1\x1b[K\n"));
+        assert!(screen.ends_with("\x1b[J"));
+        assert!(command()
+            .try_get_matches_from(["sum-queue", "--dashboard", "--format", "json"])
+            .is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{QueueGroup, QueueStats, SumQueue, Summable};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Map where each key holds its own window of values, eg. the latencies
+/// of each endpoint or the actions of each user within the last minute,
+/// and where the keys expire when all their values expire.
+///
+/// Like a [`QueueGroup`], each key gets its own [`SumQueue`] the first time
+/// a value is pushed with the key, but the keys without values within the
+/// window are dropped by [`SumMap::prune()`], so the map doesn't grow
+/// with keys that are not used anymore.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SumMap;
+///
+/// let mut latencies = SumMap::new(Duration::from_secs(60));
+/// latencies.push("/login", 120);
+/// latencies.push("/login", 80);
+/// latencies.push("/home", 15);
+///
+/// assert_eq!(latencies.stats(&"/login").unwrap().sum, Some(200));
+/// assert_eq!(latencies.stats(&"/about"), None);
+/// assert_eq!(latencies.stats_all()[&"/home"].max, Some(15));
+/// ```
+pub struct SumMap<K, V> {
+    group: QueueGroup<K, V>,
+}
+
+impl<K: Ord, V> SumMap<K, V> {
+    /// Creates an empty map, where the values of each
+    /// key will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> SumMap<K, V> {
+        SumMap {
+            group: QueueGroup::new(max_age_duration),
+        }
+    }
+
+    /// Pushes a value at the back of the queue of `key`, creating the
+    /// queue if it doesn't exist, and returns the length of the queue.
+    pub fn push(&mut self, key: K, value: V) -> usize {
+        self.group.push(key, value)
+    }
+
+    /// Returns the queue of `key`, or `None` if the key is not in the map.
+    pub fn get(&mut self, key: &K) -> Option<&mut SumQueue<V>> {
+        self.group.member(key)
+    }
+
+    /// Removes `key` from the map, and returns its queue.
+    pub fn remove(&mut self, key: &K) -> Option<SumQueue<V>> {
+        self.group.remove(key)
+    }
+
+    /// Drops the expired values of all the keys, and then
+    /// the keys without values.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumMap;
+    /// let mut sessions = SumMap::new(Duration::from_millis(100));
+    /// sessions.push("alice", 1);
+    /// thread::sleep(Duration::from_millis(150));
+    /// sessions.push("bob", 1);
+    /// sessions.prune();
+    /// assert_eq!(sessions.keys().collect::<Vec<_>>(), vec![&"bob"]);
+    /// ```
+    pub fn prune(&mut self) {
+        self.group.prune_all();
+        self.group.retain(|_, queue| queue.len_unpruned() > 0);
+    }
+
+    /// Returns an iterator over the keys, in order. It may include
+    /// keys without values within the window, see [`SumMap::prune()`].
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.group.keys()
+    }
+
+    /// Returns the number of keys. It may include keys
+    /// without values within the window, see [`SumMap::prune()`].
+    pub fn len(&self) -> usize {
+        self.group.len()
+    }
+
+    /// Checks if the map has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.group.is_empty()
+    }
+
+    /// Returns the max time the values will live in the map.
+    pub fn max_age(&self) -> Duration {
+        self.group.max_age()
+    }
+
+    /// Drops all the keys and their values.
+    pub fn clear(&mut self) {
+        self.group = QueueGroup::new(self.group.max_age());
+    }
+}
+
+impl<K: Ord + Clone, V: Summable> SumMap<K, V> {
+    /// Get the stats of the values of `key`, or `None`
+    /// if the key has no values within the window.
+    pub fn stats(&mut self, key: &K) -> Option<QueueStats<V>> {
+        let stats = self.group.member(key)?.stats();
+        if stats.len == 0 {
+            return None;
+        }
+        Some(stats)
+    }
+
+    /// Get the stats of the values of each key, dropping the
+    /// keys without values within the window before.
+    pub fn stats_all(&mut self) -> BTreeMap<K, QueueStats<V>> {
+        self.prune();
+        self.group.stats().members
+    }
+}
+
+impl<K: Ord, V> Default for SumMap<K, V> {
+    fn default() -> Self {
+        SumMap::new(crate::DEFAULT_MAX_AGE)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SumMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SumMap").field(&self.group).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumMap;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn expire_keys() {
+        let mut map = SumMap::new(Duration::from_millis(100));
+        assert!(map.is_empty());
+        assert_eq!(map.push('a', 1), 1);
+        assert_eq!(map.push('a', 2), 2);
+        map.push('b', 5);
+        assert_eq!(map.stats(&'a').unwrap().sum, Some(3));
+        thread::sleep(Duration::from_millis(150));
+        map.push('c', 10);
+        // expired keys are kept until pruned
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.stats(&'a'), None);
+        let stats = map.stats_all();
+        assert_eq!(stats.keys().collect::<Vec<_>>(), vec![&'c']);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&'c').unwrap().pop(), Some(10));
+        map.push('d', 1);
+        assert!(map.remove(&'d').is_some());
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.max_age(), Duration::from_millis(100));
+    }
+}
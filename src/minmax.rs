@@ -0,0 +1,425 @@
+use crate::{now, QueueElement, SumQueue};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+/// Min and max of the values pushed within a window of time, in O(1)
+/// amortized time per push and per query, eg. to check a threshold
+/// on each push without computing all the [`SumQueue::stats()`].
+///
+/// Instead of all the values, it keeps two monotonic queues: the values
+/// that can still be the min, in increasing order, and the values that
+/// can still be the max, in decreasing order. A value that is larger than
+/// a newer value can't be the min anymore, because it expires before.
+///
+/// To keep the values as well, see [`SumQueue::with_min_max()`].
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::MovingMinMax;
+///
+/// let mut latency = MovingMinMax::new(Duration::from_secs(60));
+/// latency.push(120);
+/// latency.push(80);
+/// latency.push(95);
+/// assert_eq!(latency.min(), Some(80));
+/// assert_eq!(latency.max(), Some(120));
+/// ```
+pub struct MovingMinMax<T> {
+    window: Duration,
+    /// values that can be the min, in increasing order
+    mins: VecDeque<(Instant, T)>,
+    /// values that can be the max, in decreasing order
+    maxs: VecDeque<(Instant, T)>,
+}
+
+impl<T: Copy + Ord> MovingMinMax<T> {
+    /// Creates an empty min and max of the values pushed within the last `window` of time.
+    pub fn new(window: Duration) -> MovingMinMax<T> {
+        MovingMinMax {
+            window,
+            mins: VecDeque::new(),
+            maxs: VecDeque::new(),
+        }
+    }
+
+    /// Pushes a value.
+    pub fn push(&mut self, item: T) {
+        let now = now();
+        self.clear_oldest(now);
+        while self.mins.back().is_some_and(|&(_, value)| value >= item) {
+            self.mins.pop_back();
+        }
+        self.mins.push_back((now, item));
+        while self.maxs.back().is_some_and(|&(_, value)| value <= item) {
+            self.maxs.pop_back();
+        }
+        self.maxs.push_back((now, item));
+    }
+
+    /// Returns the min value within the window, or `None` if there is no value.
+    pub fn min(&mut self) -> Option<T> {
+        self.clear_oldest(now());
+        self.mins.front().map(|&(_, value)| value)
+    }
+
+    /// Returns the max value within the window, or `None` if there is no value.
+    pub fn max(&mut self) -> Option<T> {
+        self.clear_oldest(now());
+        self.maxs.front().map(|&(_, value)| value)
+    }
+
+    /// Returns the window of time of the values.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.mins.clear();
+        self.maxs.clear();
+    }
+
+    /// Drops the values pushed before the window at `now`.
+    fn clear_oldest(&mut self, now: Instant) {
+        let window = self.window;
+        for values in [&mut self.mins, &mut self.maxs] {
+            while values
+                .front()
+                .is_some_and(|&(at, _)| now.saturating_duration_since(at) > window)
+            {
+                values.pop_front();
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MovingMinMax<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MovingMinMax")
+            .field("window", &self.window)
+            .field("min", &self.mins.front().map(|(_, value)| value))
+            .field("max", &self.maxs.front().map(|(_, value)| value))
+            .finish()
+    }
+}
+
+/// Monotonic queues with the positions of the elements of a [`SumQueue`]
+/// that can be its min and max, see [`SumQueue::with_min_max()`].
+///
+/// The elements are numbered in push order, so they are only kept up
+/// to date on the pushes at the back and the removals at the front of the
+/// queue. Any other change marks them as stale, and they are rebuilt
+/// from the elements the next time the min or max is read.
+#[derive(Clone)]
+pub(crate) struct MinMaxIndex<T> {
+    /// checks if a value is less than another
+    less: fn(&T, &T) -> bool,
+    /// number of the first element of the queue
+    first: u64,
+    /// numbers of the elements that can be the min, in increasing order
+    mins: VecDeque<u64>,
+    /// numbers of the elements that can be the max, in decreasing order
+    maxs: VecDeque<u64>,
+    /// whether the queue changed other than at its ends
+    stale: bool,
+}
+
+impl<T> MinMaxIndex<T> {
+    /// Returns an empty index, to be rebuilt before it's read.
+    pub(crate) fn empty_like(&self) -> MinMaxIndex<T> {
+        MinMaxIndex {
+            less: self.less,
+            first: 0,
+            mins: VecDeque::new(),
+            maxs: VecDeque::new(),
+            stale: true,
+        }
+    }
+
+    fn value<'a>(&self, queue: &'a VecDeque<QueueElement<T>>, number: u64) -> &'a T {
+        &queue[(number - self.first) as usize].value
+    }
+
+    /// Adds the element `number` of `queue`, the newest one in the index.
+    fn push(&mut self, queue: &VecDeque<QueueElement<T>>, number: u64) {
+        let item = self.value(queue, number);
+        while let Some(&back) = self.mins.back() {
+            if (self.less)(self.value(queue, back), item) {
+                break;
+            }
+            self.mins.pop_back();
+        }
+        self.mins.push_back(number);
+        while let Some(&back) = self.maxs.back() {
+            if (self.less)(item, self.value(queue, back)) {
+                break;
+            }
+            self.maxs.pop_back();
+        }
+        self.maxs.push_back(number);
+    }
+
+    /// Removes the first element of the queue, just removed from it.
+    fn pop_front(&mut self) {
+        if self.mins.front() == Some(&self.first) {
+            self.mins.pop_front();
+        }
+        if self.maxs.front() == Some(&self.first) {
+            self.maxs.pop_front();
+        }
+        self.first += 1;
+    }
+
+    /// Rebuilds the index from the elements of `queue`, if stale.
+    fn refresh(&mut self, queue: &VecDeque<QueueElement<T>>) {
+        if self.stale {
+            self.first = 0;
+            self.mins.clear();
+            self.maxs.clear();
+            for number in 0..queue.len() as u64 {
+                self.push(queue, number);
+            }
+            self.stale = false;
+        }
+    }
+
+    /// Checks that the index is the same as if rebuilt from `queue`, if not stale.
+    pub(crate) fn assert_rebuilt(&self, queue: &VecDeque<QueueElement<T>>) {
+        if !self.stale {
+            let mut rebuilt = self.empty_like();
+            rebuilt.refresh(queue);
+            let numbers = |numbers: &VecDeque<u64>| {
+                numbers
+                    .iter()
+                    .map(|number| number.wrapping_sub(self.first))
+                    .collect::<VecDeque<_>>()
+            };
+            assert!(numbers(&self.mins) == rebuilt.mins, "min index out of date");
+            assert!(numbers(&self.maxs) == rebuilt.maxs, "max index out of date");
+        }
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Starts to keep the min and max of the values in the queue up to
+    /// date on each push and pop, so [`SumQueue::min()`] and
+    /// [`SumQueue::max()`] take O(1) amortized time instead of
+    /// comparing all the values, like a [`MovingMinMax`] that also
+    /// keeps the values.
+    ///
+    /// The pushes and pops still take O(1) amortized time. Other changes
+    /// of the elements, like [`SumQueue::push_at()`] an old instant,
+    /// [`SumQueue::pop_min()`] or [`SumQueue::iter_mut()`], make the next
+    /// read of the min or max compare all the values once.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_min_max();
+    /// queue.push(120);
+    /// queue.push(80);
+    /// queue.push(95);
+    /// assert_eq!(queue.min(), Some(&80));
+    /// assert_eq!(queue.max(), Some(&120));
+    /// queue.pop();
+    /// assert_eq!(queue.max(), Some(&95));
+    /// ```
+    pub fn with_min_max(mut self) -> SumQueue<T>
+    where
+        T: Ord,
+    {
+        self.min_max = Some(MinMaxIndex {
+            less: |a, b| a < b,
+            first: 0,
+            mins: VecDeque::new(),
+            maxs: VecDeque::new(),
+            stale: true,
+        });
+        self
+    }
+
+    /// Returns the smallest value in the queue, or `None` if it's empty.
+    ///
+    /// It takes O(1) amortized time if the queue keeps the min and max
+    /// with [`SumQueue::with_min_max()`], otherwise it compares all the
+    /// values. The values are compared as pushed, without the decay, if any.
+    ///
+    /// Before the value is returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.min(), None);
+    /// queue.push(5);
+    /// queue.push(1);
+    /// queue.push(9);
+    /// assert_eq!(queue.min(), Some(&1));
+    /// assert_eq!(queue.max(), Some(&9));
+    /// ```
+    pub fn min(&mut self) -> Option<&T>
+    where
+        T: Ord,
+    {
+        self.clear_oldest(self.now());
+        match self.min_max.as_mut() {
+            Some(index) => {
+                index.refresh(&self.queue);
+                let number = *index.mins.front()?;
+                Some(index.value(&self.queue, number))
+            }
+            None => self.queue.iter().map(|el| &el.value).min(),
+        }
+    }
+
+    /// Returns the largest value in the queue, or `None` if it's empty.
+    ///
+    /// Like [`SumQueue::min()`], it takes O(1) amortized time if the queue
+    /// keeps the min and max with [`SumQueue::with_min_max()`].
+    ///
+    /// Before the value is returned, it also drops all expired elements.
+    pub fn max(&mut self) -> Option<&T>
+    where
+        T: Ord,
+    {
+        self.clear_oldest(self.now());
+        match self.min_max.as_mut() {
+            Some(index) => {
+                index.refresh(&self.queue);
+                let number = *index.maxs.front()?;
+                Some(index.value(&self.queue, number))
+            }
+            None => self.queue.iter().map(|el| &el.value).max(),
+        }
+    }
+
+    /// Adds the element just pushed at the back to the min and max, if kept.
+    pub(crate) fn min_max_pushed(&mut self) {
+        if let Some(index) = self.min_max.as_mut().filter(|index| !index.stale) {
+            let number = index.first + self.queue.len() as u64 - 1;
+            index.push(&self.queue, number);
+        }
+    }
+
+    /// Removes the element about to be removed from the front from the min and max, if kept.
+    pub(crate) fn min_max_popping(&mut self) {
+        if let Some(index) = self.min_max.as_mut().filter(|index| !index.stale) {
+            index.pop_front();
+        }
+    }
+
+    /// Marks the min and max as stale, if kept, after the elements
+    /// were changed other than at the ends of the queue.
+    pub(crate) fn min_max_changed(&mut self) {
+        if let Some(index) = self.min_max.as_mut() {
+            index.stale = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, MovingMinMax, SumQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn min_max() {
+        let mut window = MovingMinMax::new(Duration::from_millis(100));
+        assert_eq!((window.min(), window.max()), (None, None));
+        window.push(3);
+        window.push(1);
+        window.push(5);
+        thread::sleep(Duration::from_millis(60));
+        window.push(2);
+        window.push(4);
+        assert_eq!((window.min(), window.max()), (Some(1), Some(5)));
+        thread::sleep(Duration::from_millis(60));
+        // the values of the first push expired
+        assert_eq!((window.min(), window.max()), (Some(2), Some(4)));
+        window.push(4);
+        window.push(0);
+        assert_eq!((window.min(), window.max()), (Some(0), Some(4)));
+        assert_eq!(window.window(), Duration::from_millis(100));
+        window.clear();
+        assert_eq!((window.min(), window.max()), (None, None));
+    }
+
+    #[test]
+    fn queue_min_max() {
+        let clock = Clock::manual();
+        let mut queue = SumQueue::new(Duration::from_secs(10))
+            .with_min_max()
+            .with_clock(clock.clone());
+        assert_eq!((queue.min().copied(), queue.max().copied()), (None, None));
+        queue.push(3);
+        queue.push(1);
+        queue.push(5);
+        clock.advance(Duration::from_secs(6));
+        queue.push(2);
+        queue.push(4);
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(1), Some(5))
+        );
+        clock.advance(Duration::from_secs(6));
+        // the values of the first push expired
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(2), Some(4))
+        );
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(4), Some(4))
+        );
+        queue.push(7);
+        queue.push(0);
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(0), Some(7))
+        );
+        // changes in the middle of the queue rebuild the min and max
+        assert_eq!(queue.pop_max(), Some(7));
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(0), Some(4))
+        );
+        queue.iter_mut().for_each(|value| *value *= 10);
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(0), Some(40))
+        );
+        queue.push(-1);
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(-1), Some(40))
+        );
+        queue.clear();
+        assert_eq!((queue.min().copied(), queue.max().copied()), (None, None));
+        queue.push(8);
+        assert_eq!(
+            (queue.min().copied(), queue.max().copied()),
+            (Some(8), Some(8))
+        );
+        // the same as comparing all the values
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(60))
+            .max_len(8)
+            .build()
+            .with_min_max();
+        for i in 0..200u32 {
+            queue.push(i * 7919 % 31);
+            if i % 3 == 0 {
+                queue.pop();
+            }
+            let expected = (queue.iter().min().copied(), queue.iter().max().copied());
+            assert_eq!((queue.min().copied(), queue.max().copied()), expected);
+        }
+    }
+}
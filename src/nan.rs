@@ -0,0 +1,155 @@
+use crate::{QueueStats, SumQueue, Summable};
+use std::error::Error;
+use std::fmt;
+use std::ops::Add;
+
+/// How the values that are not comparable with themselves, like the float
+/// `NaN`, affect the stats of a queue, see [`SumQueue::with_nan_policy()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// `NaN` values are skipped, the **min**, **max** and
+    /// **sum** are computed with the rest of the values.
+    Ignore,
+    /// A `NaN` value makes the **min**, **max** and **sum** `NaN`.
+    #[default]
+    Propagate,
+    /// Like `Propagate`, but [`SumQueue::try_stats()`]
+    /// returns a [`NanError`] instead of the stats.
+    Error,
+}
+
+/// Error returned by [`SumQueue::try_stats()`] when there are `NaN`
+/// values in a queue with the [`NanPolicy::Error`] policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NanError;
+
+impl fmt::Display for NanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NaN value in the queue")
+    }
+}
+
+impl Error for NanError {}
+
+/// Checks if the value is not comparable with itself, like the float `NaN`.
+pub(crate) fn is_nan<T: PartialOrd>(value: &T) -> bool {
+    value.partial_cmp(value).is_none()
+}
+
+impl<T: Copy + PartialOrd, A: From<T> + Add<Output = A>> QueueStats<T, A> {
+    /// Updates the **min**, **max** and **sum** with the value, skipping
+    /// it or making them `NaN` if it's a `NaN` value, as the `policy` says.
+    pub(crate) fn push_value_with(&mut self, value: T, policy: NanPolicy) {
+        if is_nan(&value) {
+            if policy != NanPolicy::Ignore {
+                self.min = Some(value);
+                self.max = Some(value);
+                self.sum = Some(match self.sum.take() {
+                    Some(s) => s + A::from(value),
+                    None => A::from(value),
+                });
+            }
+        } else {
+            self.push_value(value);
+        }
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Sets how the `NaN` values affect the stats of the queue, by
+    /// default they make the **min**, **max** and **sum** `NaN`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "num-traits")]
+    /// # {
+    /// use std::time::Duration;
+    /// use sum_queue::{NanPolicy, SumQueue};
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_nan_policy(NanPolicy::Ignore);
+    /// queue.push(2.0);
+    /// queue.push(f64::NAN);
+    /// queue.push(1.0);
+    /// let stats = queue.stats();
+    /// assert_eq!(stats.min, Some(1.0));
+    /// assert_eq!(stats.sum, Some(3.0));
+    /// assert_eq!(stats.len, 3);
+    /// # }
+    /// ```
+    pub fn with_nan_policy(mut self, policy: NanPolicy) -> SumQueue<T> {
+        self.nan_policy = policy;
+        self
+    }
+
+    /// Returns how the `NaN` values affect the stats of the queue.
+    pub fn nan_policy(&self) -> NanPolicy {
+        self.nan_policy
+    }
+}
+
+impl<T: Summable> SumQueue<T> {
+    /// Get statistics of the queue like [`SumQueue::stats()`], but
+    /// returns a [`NanError`] if the queue has the [`NanPolicy::Error`]
+    /// policy and the stats are `NaN`, eg. because there are `NaN` values in it.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// # #[cfg(feature = "num-traits")]
+    /// # {
+    /// use std::time::Duration;
+    /// use sum_queue::{NanError, NanPolicy, SumQueue};
+    /// let mut queue = SumQueue::new(Duration::from_secs(60)).with_nan_policy(NanPolicy::Error);
+    /// queue.push(2.0);
+    /// assert_eq!(queue.try_stats().unwrap().sum, Some(2.0));
+    /// queue.push(f64::NAN);
+    /// assert_eq!(queue.try_stats(), Err(NanError));
+    /// # }
+    /// ```
+    pub fn try_stats(&mut self) -> Result<QueueStats<T>, NanError> {
+        let stats = self.stats();
+        if self.nan_policy == NanPolicy::Error && stats.sum.as_ref().is_some_and(is_nan) {
+            return Err(NanError);
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(all(test, feature = "num-traits"))]
+mod tests {
+    use crate::{NanError, NanPolicy, SumQueue};
+    use std::time::Duration;
+
+    #[test]
+    fn nan_policy() {
+        let mut queue = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.nan_policy(), NanPolicy::Propagate);
+        queue.push(f32::NAN);
+        queue.push(1.0);
+        let stats = queue.stats();
+        assert!(stats.min.unwrap().is_nan());
+        assert!(stats.max.unwrap().is_nan());
+        assert!(stats.sum.unwrap().is_nan());
+        assert!(queue.try_stats().is_ok());
+
+        let mut queue = queue.with_nan_policy(NanPolicy::Ignore);
+        queue.push(-1.0);
+        let stats = queue.stats();
+        assert_eq!(
+            (stats.min, stats.max, stats.sum, stats.len),
+            (Some(-1.0), Some(1.0), Some(0.0), 3)
+        );
+
+        let mut queue = queue.with_nan_policy(NanPolicy::Error);
+        assert_eq!(queue.try_stats(), Err(NanError));
+        assert_eq!(NanError.to_string(), "NaN value in the queue");
+        queue.pop();
+        assert_eq!(queue.try_stats().unwrap().sum, Some(0.0));
+    }
+
+    #[test]
+    fn ignore_all() {
+        let mut queue = SumQueue::new(Duration::from_secs(60)).with_nan_policy(NanPolicy::Ignore);
+        queue.push(f64::NAN);
+        let stats = queue.stats();
+        assert_eq!((stats.min, stats.sum, stats.len), (None, None, 1));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::SumQueue;
+use std::sync::Arc;
+
+/// Observer of the elements pushed, popped and expired from a queue, eg. to
+/// count them or to log them, attached once with [`SumQueue::subscribe()`]
+/// instead of wrapping each call to the queue.
+///
+/// All the methods do nothing by default, so only the events
+/// of interest need to be implemented. It's implemented for the
+/// observers in an [`Arc`] as well, so they can be read while
+/// they are attached to the queue.
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use sum_queue::{QueueObserver, SumQueue};
+///
+/// #[derive(Default)]
+/// struct Counters {
+///     pushed: AtomicUsize,
+///     popped: AtomicUsize,
+/// }
+///
+/// impl QueueObserver<i32> for Counters {
+///     fn pushed(&self, _value: &i32) {
+///         self.pushed.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn popped(&self, _value: &i32) {
+///         self.popped.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counters = Arc::new(Counters::default());
+/// let mut queue = SumQueue::new(Duration::from_secs(60));
+/// queue.subscribe(counters.clone());
+/// queue.push(1);
+/// queue.push(2);
+/// queue.pop();
+/// assert_eq!(counters.pushed.load(Ordering::Relaxed), 2);
+/// assert_eq!(counters.popped.load(Ordering::Relaxed), 1);
+/// ```
+pub trait QueueObserver<T>: Send + Sync {
+    /// Called with each element pushed into the queue, including the
+    /// ones moved from other queues, eg. with [`SumQueue::append()`].
+    fn pushed(&self, _value: &T) {}
+
+    /// Called with each element removed with [`SumQueue::pop()`]
+    /// and the other pop methods.
+    fn popped(&self, _value: &T) {}
+
+    /// Called with each element dropped by the queue itself, either because
+    /// it expired, or because it was the oldest element in a queue that
+    /// reached its max length, like the function set with
+    /// [`crate::SumQueueBuilder::on_expire()`], including the ones
+    /// dropped early with [`SumQueue::keep_only_last()`]
+    /// or [`SumQueue::truncate_to_newest()`].
+    fn expired(&self, _value: &T) {}
+}
+
+impl<T, O: QueueObserver<T> + ?Sized> QueueObserver<T> for Arc<O> {
+    fn pushed(&self, value: &T) {
+        (**self).pushed(value);
+    }
+
+    fn popped(&self, value: &T) {
+        (**self).popped(value);
+    }
+
+    fn expired(&self, value: &T) {
+        (**self).expired(value);
+    }
+}
+
+/// Observers attached to a queue.
+pub(crate) type Observers<T> = Vec<Arc<dyn QueueObserver<T>>>;
+
+impl<T> SumQueue<T> {
+    /// Attaches an observer that is called with each
+    /// element pushed, popped and expired, see [`QueueObserver`].
+    ///
+    /// The observers are kept by the clones of the queue,
+    /// but not by the queues created with [`SumQueue::map()`].
+    pub fn subscribe<O>(&mut self, observer: O)
+    where
+        O: QueueObserver<T> + 'static,
+    {
+        self.observers.push(Arc::new(observer));
+    }
+
+    pub(crate) fn notify_pushed(&self, value: &T) {
+        for observer in &self.observers {
+            observer.pushed(value);
+        }
+    }
+
+    pub(crate) fn notify_popped(&self, value: &T) {
+        for observer in &self.observers {
+            observer.popped(value);
+        }
+    }
+
+    /// Passes an element dropped by the queue itself
+    /// to the observers and the expiration callback.
+    pub(crate) fn expire(&self, value: T) {
+        for observer in &self.observers {
+            observer.expired(&value);
+        }
+        if let Some(on_expire) = &self.on_expire {
+            on_expire(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{QueueObserver, SumQueue};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct Log(Arc<Mutex<Vec<String>>>);
+
+    impl QueueObserver<i32> for Log {
+        fn pushed(&self, value: &i32) {
+            self.0.lock().unwrap().push(format!("+{}", value));
+        }
+
+        fn popped(&self, value: &i32) {
+            self.0.lock().unwrap().push(format!("-{}", value));
+        }
+
+        fn expired(&self, value: &i32) {
+            self.0.lock().unwrap().push(format!("x{}", value));
+        }
+    }
+
+    #[test]
+    fn subscribe() {
+        let log = Log::default();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_millis(100))
+            .max_len(3)
+            .build();
+        queue.subscribe(log.clone());
+        queue.push(1);
+        queue.push(2);
+        let mut other = SumQueue::new(Duration::from_secs(60));
+        other.push(3);
+        other.push(4);
+        queue.append(&mut other);
+        assert_eq!(queue.pop(), Some(2));
+        thread::sleep(Duration::from_millis(150));
+        assert!(queue.is_empty());
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            vec!["+1", "+2", "+3", "+4", "x1", "-2", "x3", "x4"]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{QueueElement, QueueStats, SumQueue, Summable};
+use rayon::collections::vec_deque;
+use rayon::iter::plumbing::{Consumer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, Map, ParallelIterator};
+
+impl<T: Sync> SumQueue<T> {
+    /// Returns a parallel iterator visiting all values in the queue,
+    /// so expensive computations over each element can be split
+    /// across the threads of the [`rayon`] thread pool.
+    ///
+    /// Before return the iterator, it also drops all expired elements.
+    /// The iterator is indexed, so methods like `collect()` keep the
+    /// same order the values were pushed.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rayon::prelude::*;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// for i in 1..=100 {
+    ///     queue.push(i);
+    /// }
+    /// let squares: u64 = queue.par_iter().map(|x| x * x).sum();
+    /// assert_eq!(squares, 338350);
+    /// ```
+    pub fn par_iter(&mut self) -> ParIter<'_, T> {
+        self.clear_oldest(self.now());
+        ParIter {
+            iter: self.queue.par_iter().map(QueueElement::value),
+        }
+    }
+}
+
+impl<T: Summable + Send + Sync> SumQueue<T> {
+    /// Get statistics of the queue like [`SumQueue::stats()`], but splitting
+    /// the scan of the elements across the threads of the [`rayon`] thread pool,
+    /// and merging the partial results with [`QueueStats::merge()`].
+    ///
+    /// Splitting the work has its own cost, so it only worth it on queues
+    /// with a huge number of elements.
+    ///
+    /// Before the stats are returned, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(60));
+    /// for i in 1..=100_000 {
+    ///     queue.push(i);
+    /// }
+    /// let stats = queue.stats_parallel();
+    /// assert_eq!(stats.min, Some(1));
+    /// assert_eq!(stats.max, Some(100_000));
+    /// assert_eq!(stats.sum, Some(5_000_050_000));
+    /// assert_eq!(stats.len, 100_000);
+    /// ```
+    pub fn stats_parallel(&mut self) -> QueueStats<T> {
+        self.par_iter()
+            .fold(QueueStats::default, |stats, &value| {
+                stats.merge(QueueStats {
+                    min: Some(value),
+                    max: Some(value),
+                    sum: Some(value),
+                    len: 1,
+                })
+            })
+            .reduce(QueueStats::default, QueueStats::merge)
+    }
+}
+
+impl<T> QueueElement<T> {
+    fn value(&self) -> &T {
+        &self.value
+    }
+}
+
+type ValuesIter<'a, T> = Map<vec_deque::Iter<'a, QueueElement<T>>, fn(&QueueElement<T>) -> &T>;
+
+/// Parallel iterator over the values of a [`SumQueue`].
+///
+/// This `struct` is created by [`SumQueue::par_iter()`].
+pub struct ParIter<'a, T: Sync> {
+    iter: ValuesIter<'a, T>,
+}
+
+impl<'a, T: Sync> ParallelIterator for ParIter<'a, T> {
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.iter.drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.iter.opt_len()
+    }
+}
+
+impl<T: Sync> IndexedParallelIterator for ParIter<'_, T> {
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.iter.drive(consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        self.iter.with_producer(callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use rayon::prelude::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn par_iter() {
+        let mut queue = SumQueue::new(Duration::from_millis(100));
+        queue.push(-1);
+        thread::sleep(Duration::from_millis(150));
+        for i in 0..1000 {
+            queue.push(i);
+        }
+        let iter = queue.par_iter();
+        assert_eq!(iter.len(), 1000);
+        let values: Vec<_> = iter.map(|x| x * 2).collect();
+        assert_eq!(values, (0..1000).map(|x| x * 2).collect::<Vec<_>>());
+        assert_eq!(queue.par_iter().filter(|&&x| x % 2 == 0).count(), 500);
+        assert_eq!(queue.par_iter().min(), Some(&0));
+    }
+
+    #[test]
+    fn stats_parallel() {
+        let mut queue: SumQueue<i64> = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.stats_parallel(), queue.stats());
+        for i in 0..10_000 {
+            queue.push((i * 7919) % 10_007 - 5_000);
+        }
+        assert_eq!(queue.stats_parallel(), queue.stats());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::SumQueue;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Queue where the elements are popped by priority, the highest first,
+/// but still expire by age like in a [`SumQueue`], eg. for a queue of jobs
+/// where the urgency orders the work, but a job too old is not worth doing.
+///
+/// The priority of each element is either the element itself, or a key
+/// computed from it with the function passed to [`PrioritySumQueue::with_key()`].
+/// Elements with the same priority are popped in the same order they were pushed.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::PrioritySumQueue;
+///
+/// let mut jobs = PrioritySumQueue::with_key(Duration::from_secs(60), |job: &(u8, &str)| job.0);
+/// jobs.push((1, "send newsletter"));
+/// jobs.push((9, "restart server"));
+/// jobs.push((5, "rotate logs"));
+/// assert_eq!(jobs.pop(), Some((9, "restart server")));
+/// assert_eq!(jobs.pop(), Some((5, "rotate logs")));
+/// assert_eq!(jobs.len(), 1);
+/// ```
+pub struct PrioritySumQueue<T, K = T> {
+    /// elements by priority, and by push order reversed,
+    /// so the last one is the next to be popped
+    priorities: BTreeMap<(K, Reverse<u64>), T>,
+    /// keys of the elements pushed, they are expired by hand to drop
+    /// the elements from `priorities`, that may be already popped
+    ages: SumQueue<(K, Reverse<u64>)>,
+    max_age: Duration,
+    key: fn(&T) -> K,
+    /// number of elements pushed
+    seq: u64,
+}
+
+impl<T: Ord + Clone> PrioritySumQueue<T> {
+    /// Creates an empty queue, where the elements are popped from the
+    /// largest to the smallest, and will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> PrioritySumQueue<T> {
+        PrioritySumQueue::with_key(max_age_duration, T::clone)
+    }
+}
+
+impl<T, K: Ord + Clone> PrioritySumQueue<T, K> {
+    /// Creates an empty queue, where the elements are popped from the
+    /// largest to the smallest key computed by `key`, and will live
+    /// `max_age_duration` at maximum.
+    pub fn with_key(max_age_duration: Duration, key: fn(&T) -> K) -> PrioritySumQueue<T, K> {
+        PrioritySumQueue {
+            priorities: BTreeMap::new(),
+            ages: SumQueue::new(Duration::MAX),
+            max_age: max_age_duration,
+            key,
+            seq: 0,
+        }
+    }
+
+    /// Drops the expired elements.
+    fn expire(&mut self) {
+        while let Some((_, age)) = self.ages.peek_with_age() {
+            if age <= self.max_age {
+                break;
+            }
+            if let Some(key) = self.ages.pop() {
+                self.priorities.remove(&key);
+            }
+        }
+    }
+
+    /// Pushes an element, and returns the size of the queue.
+    ///
+    /// Before the element is pushed, it also drops all expired elements.
+    pub fn push(&mut self, item: T) -> usize {
+        self.expire();
+        let key = ((self.key)(&item), Reverse(self.seq));
+        self.seq += 1;
+        self.priorities.insert(key.clone(), item);
+        self.ages.push(key);
+        self.priorities.len()
+    }
+
+    /// Removes the element with the highest priority and returns
+    /// it, or `None` if the queue is empty.
+    ///
+    /// Before the element is removed, it also drops all expired elements.
+    pub fn pop(&mut self) -> Option<T> {
+        self.expire();
+        self.priorities.pop_last().map(|(_, item)| item)
+    }
+
+    /// Returns the element with the highest priority, without removing
+    /// it, or `None` if the queue is empty.
+    ///
+    /// Before the element is returned, it also drops all expired elements.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.expire();
+        self.priorities.last_key_value().map(|(_, item)| item)
+    }
+
+    /// Returns the number of elements in the queue.
+    ///
+    /// Before return the size, it also drops all expired elements.
+    pub fn len(&mut self) -> usize {
+        self.expire();
+        self.priorities.len()
+    }
+
+    /// Checks if the queue is empty.
+    ///
+    /// Before the check, it also drops all expired elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Drops all the elements.
+    pub fn clear(&mut self) {
+        self.priorities.clear();
+        self.ages.clear();
+    }
+}
+
+impl<T: fmt::Debug, K> fmt::Debug for PrioritySumQueue<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrioritySumQueue")
+            .field(
+                "elements",
+                &self.priorities.values().rev().collect::<Vec<_>>(),
+            )
+            .field("max_age", &self.max_age)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PrioritySumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn pop_by_priority() {
+        let mut queue = PrioritySumQueue::new(Duration::from_millis(100));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.push(3), 1);
+        queue.push(8);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(5);
+        queue.push(1);
+        assert_eq!(queue.push(7), 3);
+        // the expired elements are not popped
+        assert_eq!(queue.peek(), Some(&7));
+        assert_eq!(queue.pop(), Some(7));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.len(), 1);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.max_age(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn same_priority() {
+        let mut queue = PrioritySumQueue::with_key(Duration::from_secs(60), |s: &&str| s.len());
+        queue.push("b");
+        queue.push("aaa");
+        queue.push("a");
+        queue.push("ccc");
+        assert_eq!(queue.pop(), Some("aaa"));
+        assert_eq!(queue.pop(), Some("ccc"));
+        assert_eq!(queue.pop(), Some("b"));
+        assert_eq!(queue.pop(), Some("a"));
+    }
+}
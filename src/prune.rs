@@ -0,0 +1,144 @@
+use crate::{Instant, SumQueue};
+use std::time::Duration;
+
+/// How often the expired elements are dropped, see
+/// [`SumQueue::with_prune_every()`] and [`SumQueue::with_prune_interval()`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PrunePolicy {
+    /// number of operations between prunes
+    every: Option<usize>,
+    /// min time between prunes
+    interval: Option<Duration>,
+    /// operations since the last prune
+    ops: usize,
+    /// time of the last prune
+    last: Option<Instant>,
+}
+
+impl PrunePolicy {
+    /// Counts an operation, and returns whether the
+    /// expired elements have to be dropped at `now`.
+    fn is_due(&mut self, now: Instant) -> bool {
+        self.ops += 1;
+        self.every.is_some_and(|every| self.ops >= every)
+            || self.interval.is_some_and(|interval| {
+                self.last
+                    .is_none_or(|last| now.saturating_duration_since(last) >= interval)
+            })
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.ops = 0;
+        self.last = Some(now);
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Drops the expired elements only once every `ops` operations,
+    /// instead of on each call to [`SumQueue::len()`], [`SumQueue::push()`]
+    /// and the other methods that take a mutable reference, so the hot
+    /// paths don't pay for scanning the queue each time.
+    ///
+    /// Until the next prune, the expired elements are still returned and
+    /// count by the queue, call [`SumQueue::prune()`] when they have to be
+    /// dropped right away. Elements are still dropped on each push
+    /// when the queue reaches its max length.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_millis(100)).with_prune_every(100);
+    /// queue.push(1);
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert_eq!(queue.len(), 1);
+    /// queue.prune();
+    /// assert_eq!(queue.len(), 0);
+    /// ```
+    pub fn with_prune_every(mut self, ops: usize) -> SumQueue<T> {
+        self.prune.get_or_insert_with(PrunePolicy::default).every = Some(ops);
+        self
+    }
+
+    /// Drops the expired elements at most once per `interval`, instead of
+    /// on each call to the methods that take a mutable reference, see
+    /// [`SumQueue::with_prune_every()`]. When both are set, the expired
+    /// elements are dropped as soon as any of them is due.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_millis(100))
+    ///     .with_prune_interval(Duration::from_secs(60));
+    /// queue.push(1);
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert_eq!(queue.len(), 1);
+    /// queue.prune();
+    /// assert_eq!(queue.len(), 0);
+    /// ```
+    pub fn with_prune_interval(mut self, interval: Duration) -> SumQueue<T> {
+        self.prune.get_or_insert_with(PrunePolicy::default).interval = Some(interval);
+        self
+    }
+
+    /// Drops all the expired elements now, even if the queue was configured
+    /// to do it less often, eg. with [`SumQueue::with_prune_every()`].
+    pub fn prune(&mut self) {
+        self.prune_at(self.now());
+    }
+
+    /// Counts an operation, and drops the expired
+    /// elements at `now` if the prune policy is due.
+    pub(crate) fn clear_oldest(&mut self, now: Instant) {
+        if self.prune.as_mut().is_none_or(|prune| prune.is_due(now)) {
+            self.prune_at(now);
+        }
+    }
+
+    /// Drops all the expired elements at `now`.
+    fn prune_at(&mut self, now: Instant) {
+        if let Some(prune) = &mut self.prune {
+            prune.reset(now);
+        }
+        self.drop_expired(now);
+        self.debug_assert_pruned(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn prune_every() {
+        let mut queue = SumQueue::new(Duration::from_millis(100)).with_prune_every(4);
+        queue.push(1);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(2);
+        assert_eq!(queue.len(), 2);
+        // the 4th operation drops the expired elements
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&2]);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.len(), 1);
+        queue.prune();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn prune_interval() {
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_millis(100))
+            .prune_interval(Duration::from_millis(200))
+            .build();
+        queue.push(1);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(2);
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Python bindings of the queue, built with [PyO3](https://pyo3.rs).
+//!
+//! The `sum_queue` Python module has a `SumQueue` class, a thread-safe
+//! queue of numbers that wraps a [`crate::sync::SumQueue`], and the
+//! `QueueStats` class returned by `SumQueue.stats()`. The module can
+//! be built and installed in the current Python environment with
+//! [maturin](https://www.maturin.rs), see the `pyproject.toml` file:
+//!
+//! ```shell
+//! maturin develop --release
+//! ```
+//!
+//! ```python
+//! from sum_queue import SumQueue
+//!
+//! queue = SumQueue(60)  # max age in seconds
+//! queue.push(1.5)
+//! queue.push(3)
+//! stats = queue.stats()
+//! print(stats.min, stats.max, stats.sum, stats.len)  # 1.5 3.0 4.5 2
+//! ```
+
+use crate::{sync, MAX_AGE_LIMIT};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::time::Duration;
+
+/// Python class of the queue, holding `float` values.
+#[pyclass(name = "SumQueue", module = "sum_queue", frozen)]
+pub struct PySumQueue {
+    queue: sync::SumQueue<f64>,
+}
+
+/// Python class of the stats of a queue.
+///
+/// Unlike [`crate::QueueStats`] the values are floats, and
+/// `min` and `max` skip the `NaN` values.
+#[pyclass(
+    name = "QueueStats",
+    module = "sum_queue",
+    frozen,
+    get_all,
+    skip_from_py_object
+)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PyQueueStats {
+    /// min value of the queue, or `None` if it's empty
+    pub min: Option<f64>,
+    /// max value of the queue, or `None` if it's empty
+    pub max: Option<f64>,
+    /// sum of all the values of the queue, or `None` if it's empty
+    pub sum: Option<f64>,
+    /// number of elements in the queue
+    pub len: usize,
+}
+
+#[pymethods]
+impl PySumQueue {
+    /// Creates an empty queue, where the elements inside
+    /// will live `max_age` seconds at maximum.
+    #[new]
+    fn new(max_age: f64) -> PyResult<Self> {
+        let max_age = Duration::try_from_secs_f64(max_age)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if max_age > MAX_AGE_LIMIT {
+            return Err(PyValueError::new_err(format!(
+                "max_age can't be longer than {} seconds",
+                MAX_AGE_LIMIT.as_secs_f64()
+            )));
+        }
+        Ok(PySumQueue {
+            queue: sync::SumQueue::new(max_age),
+        })
+    }
+
+    /// Max time in seconds the elements will live in the queue.
+    #[getter]
+    fn max_age(&self) -> f64 {
+        self.queue.max_age().as_secs_f64()
+    }
+
+    /// Pushes a value at the back of the queue, and
+    /// returns the length of the queue.
+    fn push(&self, value: f64) -> usize {
+        self.queue.push(value)
+    }
+
+    /// Removes the first value from the queue and returns
+    /// it, or `None` if it is empty.
+    fn pop(&self) -> Option<f64> {
+        self.queue.pop()
+    }
+
+    /// Returns the first value of the queue, or `None` if it is empty.
+    fn peek(&self) -> Option<f64> {
+        self.queue.peek()
+    }
+
+    /// Drops all values.
+    fn clear(&self) {
+        self.queue.clear()
+    }
+
+    /// Returns a list with all the values, in the same order they were pushed.
+    fn to_list(&self) -> Vec<f64> {
+        self.queue.to_vec()
+    }
+
+    /// Get statistics of the queue.
+    fn stats(&self) -> PyQueueStats {
+        let mut guard = self.queue.lock();
+        let mut stats = PyQueueStats {
+            min: None,
+            max: None,
+            sum: None,
+            len: 0,
+        };
+        for &value in guard.iter() {
+            stats.min = Some(stats.min.map_or(value, |min| min.min(value)));
+            stats.max = Some(stats.max.map_or(value, |max| max.max(value)));
+            stats.sum = Some(stats.sum.unwrap_or(0.0) + value);
+            stats.len += 1;
+        }
+        stats
+    }
+
+    fn __len__(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SumQueue({:?})", self.queue.to_vec())
+    }
+}
+
+#[pymethods]
+impl PyQueueStats {
+    fn __repr__(&self) -> String {
+        fn repr(value: Option<f64>) -> String {
+            value.map_or_else(|| "None".to_string(), |value| format!("{:?}", value))
+        }
+        format!(
+            "QueueStats(min={}, max={}, sum={}, len={})",
+            repr(self.min),
+            repr(self.max),
+            repr(self.sum),
+            self.len
+        )
+    }
+}
+
+/// The `sum_queue` Python module.
+#[pymodule]
+fn sum_queue(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySumQueue>()?;
+    m.add_class::<PyQueueStats>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::python::PySumQueue;
+    use std::thread;
+
+    #[test]
+    fn py_sum_queue() {
+        let queue = PySumQueue::new(0.1).unwrap();
+        assert!(PySumQueue::new(-1.0).is_err());
+        assert_eq!(queue.max_age(), 0.1);
+        queue.push(1.5);
+        queue.push(f64::NAN);
+        queue.push(-2.0);
+        let stats = queue.stats();
+        assert_eq!(stats.min, Some(-2.0));
+        assert_eq!(stats.max, Some(1.5));
+        assert!(stats.sum.unwrap().is_nan());
+        assert_eq!(stats.len, 3);
+        assert_eq!(queue.pop(), Some(1.5));
+        assert_eq!(queue.__len__(), 2);
+        thread::sleep(std::time::Duration::from_millis(150));
+        assert_eq!(queue.to_list(), Vec::<f64>::new());
+        assert_eq!(queue.stats().min, None);
+    }
+}
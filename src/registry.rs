@@ -0,0 +1,229 @@
+use crate::lock::{self, Mutex, MutexGuard};
+use crate::sync::SharedSumQueue;
+use crate::{QueueStats, Summable};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Thread-safe collection of queues identified by a label, eg. one
+/// queue for each metric of an application.
+///
+/// The queues are created the first time they are requested
+/// with [`QueueRegistry::queue()`], all with the same max age.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::QueueRegistry;
+///
+/// let registry = QueueRegistry::new(Duration::from_secs(60));
+/// registry.queue("api.latency").push(120);
+/// registry.queue("api.latency").push(80);
+/// registry.queue("db.latency").push(15);
+///
+/// let stats = registry.stats();
+/// assert_eq!(stats["api.latency"].sum, Some(200));
+/// assert_eq!(stats["db.latency"].sum, Some(15));
+/// assert_eq!(registry.aggregate_stats().max, Some(120));
+/// ```
+///
+/// Unused queues keep their expired elements in memory until they are accessed
+/// again, [`QueueRegistry::prune_all()`] drops the expired elements of all
+/// the queues at once, and [`QueueRegistry::spawn_cleanup()`] does the same
+/// periodically in a background thread.
+pub struct QueueRegistry<T> {
+    queues: Mutex<BTreeMap<String, SharedSumQueue<T>>>,
+    max_age: Duration,
+}
+
+impl<T> QueueRegistry<T> {
+    /// Creates an empty registry, where the elements of
+    /// the queues will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> QueueRegistry<T> {
+        QueueRegistry {
+            queues: Mutex::new(BTreeMap::new()),
+            max_age: max_age_duration,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, SharedSumQueue<T>>> {
+        lock::lock(&self.queues)
+    }
+
+    /// Returns a handle of the queue with the given `label`,
+    /// creating an empty queue if it doesn't exist.
+    pub fn queue(&self, label: &str) -> SharedSumQueue<T> {
+        let mut queues = self.lock();
+        match queues.get(label) {
+            Some(queue) => queue.clone(),
+            None => {
+                let queue = SharedSumQueue::new(self.max_age);
+                queues.insert(label.to_string(), queue.clone());
+                queue
+            }
+        }
+    }
+
+    /// Returns a handle of the queue with the given `label`,
+    /// or `None` if it doesn't exist.
+    pub fn get(&self, label: &str) -> Option<SharedSumQueue<T>> {
+        self.lock().get(label).cloned()
+    }
+
+    /// Removes the queue with the given `label` from the registry,
+    /// and returns it. Other handles of the queue are still valid.
+    pub fn remove(&self, label: &str) -> Option<SharedSumQueue<T>> {
+        self.lock().remove(label)
+    }
+
+    /// Returns the labels of all the queues, in alphabetical order.
+    pub fn labels(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Returns the number of queues.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks if there is no queue in the registry.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the max time the elements will live in the queues.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns a copy of the handles of all the queues, so they can be
+    /// visited without locking the registry.
+    fn queues(&self) -> Vec<(String, SharedSumQueue<T>)> {
+        self.lock()
+            .iter()
+            .map(|(label, queue)| (label.clone(), queue.clone()))
+            .collect()
+    }
+
+    /// Drops the expired elements of all the queues.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::QueueRegistry;
+    /// let registry = QueueRegistry::new(Duration::from_millis(100));
+    /// registry.queue("a").push(1);
+    /// thread::sleep(Duration::from_millis(150));
+    /// registry.prune_all();
+    /// assert_eq!(registry.stats()["a"].len, 0);
+    /// ```
+    pub fn prune_all(&self) {
+        for (_, queue) in self.queues() {
+            queue.lock().prune();
+        }
+    }
+
+    /// Spawns a thread that calls [`QueueRegistry::prune_all()`] every
+    /// `interval`. The thread ends after the registry is dropped.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use sum_queue::QueueRegistry;
+    /// let registry: Arc<QueueRegistry<i32>> = Arc::new(QueueRegistry::new(Duration::from_secs(60)));
+    /// let cleanup = registry.spawn_cleanup(Duration::from_millis(10));
+    /// drop(registry);
+    /// cleanup.join().unwrap();
+    /// ```
+    pub fn spawn_cleanup(self: &Arc<Self>, interval: Duration) -> JoinHandle<()>
+    where
+        T: Send + 'static,
+    {
+        let registry: Weak<Self> = Arc::downgrade(self);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match registry.upgrade() {
+                Some(registry) => registry.prune_all(),
+                None => break,
+            }
+        })
+    }
+}
+
+impl<T: Summable> QueueRegistry<T> {
+    /// Get the stats of each queue, by label.
+    ///
+    /// The queues are locked one at a time, so the result
+    /// is not a snapshot of all the queues at a single instant.
+    pub fn stats(&self) -> BTreeMap<String, QueueStats<T>> {
+        self.queues()
+            .into_iter()
+            .map(|(label, queue)| (label, queue.stats()))
+            .collect()
+    }
+
+    /// Get the stats of all the elements of all the queues together,
+    /// see [`QueueStats::merge()`].
+    pub fn aggregate_stats(&self) -> QueueStats<T> {
+        self.queues()
+            .into_iter()
+            .map(|(_, queue)| queue.stats())
+            .fold(QueueStats::default(), QueueStats::merge)
+    }
+}
+
+impl<T> Default for QueueRegistry<T> {
+    fn default() -> Self {
+        QueueRegistry::new(crate::DEFAULT_MAX_AGE)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for QueueRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.queues()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QueueRegistry;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn registry() {
+        let registry = QueueRegistry::new(Duration::from_secs(60));
+        assert!(registry.is_empty());
+        let queue = registry.queue("b");
+        queue.push(1);
+        registry.queue("b").push(2);
+        registry.queue("a").push(-5);
+        assert!(queue.ptr_eq(&registry.get("b").unwrap()));
+        assert!(registry.get("c").is_none());
+        assert_eq!(registry.labels(), vec!["a", "b"]);
+        assert_eq!(registry.stats()["b"].len, 2);
+        let stats = registry.aggregate_stats();
+        assert_eq!(stats.min, Some(-5));
+        assert_eq!(stats.sum, Some(-2));
+        assert_eq!(stats.len, 3);
+        assert!(registry.remove("b").unwrap().ptr_eq(&queue));
+        assert_eq!(registry.len(), 1);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn cleanup() {
+        let registry = Arc::new(QueueRegistry::new(Duration::from_millis(100)));
+        registry.queue("a").push(1);
+        registry.queue("b").push(2);
+        let cleanup = registry.spawn_cleanup(Duration::from_millis(20));
+        thread::sleep(Duration::from_millis(200));
+        for label in registry.labels() {
+            assert_eq!(registry.queue(&label).lock().queue.len(), 0);
+        }
+        drop(registry);
+        cleanup.join().unwrap();
+    }
+}
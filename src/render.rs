@@ -0,0 +1,185 @@
+use crate::SumQueue;
+use std::time::Duration;
+
+/// Bars of the sparkline, from the lowest to the highest value.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Width of the longest bar of the histogram.
+const BAR_WIDTH: usize = 40;
+
+/// Rounds the start of a bucket, so it's printed without float noise.
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+impl<T: Copy + Into<f64>> SumQueue<T> {
+    /// Returns a unicode sparkline of `width` characters of the values over
+    /// time, like `▁▃█ ▅`, from the oldest element in the queue to now.
+    ///
+    /// The time is split in `width` slots of the same length, each one
+    /// drawn with the average of the values pushed within it, or with a
+    /// space if there is none. The bars are scaled from the lowest to the
+    /// highest average.
+    ///
+    /// Before the sparkline is drawn, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// let now = Instant::now();
+    /// queue.push_at(now - Duration::from_secs(4), 1).unwrap();
+    /// queue.push_at(now - Duration::from_millis(2500), 8).unwrap();
+    /// queue.push(4);
+    /// assert_eq!(queue.sparkline(4), "▁█ ▄");
+    /// ```
+    pub fn sparkline(&mut self, width: usize) -> String {
+        if width == 0 {
+            return String::new();
+        }
+        let ages = self.iter_with_age().collect::<Vec<_>>();
+        let span = ages.first().map_or(Duration::ZERO, |(_, age)| *age);
+        let mut slots = vec![(0.0, 0); width];
+        for (value, age) in ages {
+            let i = match span.as_nanos() {
+                0 => width - 1,
+                nanos => ((span - age).as_nanos() * width as u128 / nanos) as usize,
+            };
+            let slot = &mut slots[i.min(width - 1)];
+            slot.0 += (*value).into();
+            slot.1 += 1;
+        }
+        let averages = slots
+            .iter()
+            .map(|&(sum, len)| (len > 0).then(|| sum / len as f64))
+            .collect::<Vec<_>>();
+        let min = averages
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        let max = averages
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        averages
+            .iter()
+            .map(|average| match average {
+                None => ' ',
+                Some(_) if min == max => SPARKS[SPARKS.len() / 2 - 1],
+                Some(average) => {
+                    let level = (average - min) / (max - min) * (SPARKS.len() - 1) as f64;
+                    SPARKS[level.round() as usize]
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a histogram of the values drawn with ASCII characters, a line
+    /// per bucket with the start of its range, a bar and the number of values.
+    ///
+    /// The range from the lowest to the highest value is split in
+    /// `buckets` ranges of the same width, or in one if all the
+    /// values are equal. An empty queue returns an empty string.
+    ///
+    /// Before the histogram is drawn, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// for value in [1, 2, 2, 3, 6, 8] {
+    ///     queue.push(value);
+    /// }
+    /// print!("{}", queue.ascii_histogram(4));
+    /// // 1    | ######################################## 3
+    /// // 2.75 | ############# 1
+    /// // 4.5  | ############# 1
+    /// // 6.25 | ############# 1
+    /// assert_eq!(queue.ascii_histogram(4).lines().count(), 4);
+    /// ```
+    pub fn ascii_histogram(&mut self, buckets: usize) -> String {
+        let values = self.iter().map(|&value| value.into()).collect::<Vec<f64>>();
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let buckets = match values.len() {
+            0 => 0,
+            _ if min == max => 1,
+            _ => buckets.max(1),
+        };
+        let width = (max - min) / buckets as f64;
+        let mut counts = vec![0; buckets];
+        for value in values {
+            let i = ((value - min) / width) as usize;
+            counts[i.min(buckets - 1)] += 1;
+        }
+        let labels = (0..buckets)
+            .map(|i| round(min + width * i as f64).to_string())
+            .collect::<Vec<_>>();
+        let label_width = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+        let highest = counts.iter().copied().max().unwrap_or(0);
+        labels
+            .iter()
+            .zip(counts)
+            .map(|(label, count)| {
+                let bar = "#".repeat(count * BAR_WIDTH / highest);
+                format!(
+                    "{:<width$} | {} {}\n",
+                    label,
+                    bar,
+                    count,
+                    width = label_width
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, SumQueue};
+    use std::time::Duration;
+
+    #[test]
+    fn sparkline() {
+        let clock = Clock::manual();
+        let mut queue = SumQueue::new(Duration::from_secs(10)).with_clock(clock.clone());
+        assert_eq!(queue.sparkline(3), "   ");
+        assert_eq!(queue.sparkline(0), "");
+        queue.push(5u32);
+        queue.push(7);
+        assert_eq!(queue.sparkline(3), "  ▄");
+        for value in [0, 10, 20, 30, 40, 50, 60, 70] {
+            clock.advance(Duration::from_secs(1));
+            queue.push(value);
+        }
+        assert_eq!(queue.sparkline(9), "▂▁▂▃▄▅▆▇█");
+        clock.advance(Duration::from_millis(4500));
+        assert_eq!(queue.len(), 6);
+        assert_eq!(queue.sparkline(3), "▁█ ");
+    }
+
+    #[test]
+    fn ascii_histogram() {
+        let mut queue = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.ascii_histogram(4), "");
+        queue.push(2.5f32);
+        queue.push(2.5);
+        assert_eq!(
+            queue.ascii_histogram(4),
+            format!("2.5 | {} 2\n", "#".repeat(40))
+        );
+        queue.push(0.0);
+        queue.push(0.1);
+        queue.push(0.9);
+        assert_eq!(
+            queue.ascii_histogram(3).lines().collect::<Vec<_>>(),
+            vec![
+                format!("0     | {} 2", "#".repeat(40)),
+                format!("0.833 | {} 1", "#".repeat(20)),
+                format!("1.667 | {} 2", "#".repeat(40)),
+            ]
+        );
+    }
+}
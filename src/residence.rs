@@ -0,0 +1,156 @@
+use crate::sliding::Buckets;
+use crate::SumQueue;
+use std::fmt;
+use std::time::Duration;
+
+/// Stats of the time the elements popped within a window of time
+/// were in the queue, see [`SumQueue::with_residence_history()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResidenceStats {
+    /// min time an element was in the queue
+    pub min: Duration,
+    /// max time an element was in the queue
+    pub max: Duration,
+    /// average time the elements were in the queue
+    pub avg: Duration,
+    /// number of elements popped
+    pub count: usize,
+}
+
+/// Aggregate of the times of the elements popped within a bucket.
+#[derive(Clone)]
+struct Residences {
+    min: Duration,
+    max: Duration,
+    sum: Duration,
+    count: usize,
+}
+
+/// Windowed record of the time the elements popped were in a queue.
+#[derive(Clone)]
+pub(crate) struct ResidenceHistory {
+    buckets: Buckets<Residences>,
+}
+
+impl ResidenceHistory {
+    pub(crate) fn new(window: Duration, buckets: usize) -> ResidenceHistory {
+        ResidenceHistory {
+            buckets: Buckets::new(window, buckets),
+        }
+    }
+
+    /// Returns an empty history with the same window.
+    pub(crate) fn empty_like(&self) -> ResidenceHistory {
+        let mut history = self.clone();
+        history.buckets.clear();
+        history
+    }
+
+    pub(crate) fn record(&mut self, age: Duration) {
+        let residences = self.buckets.current(|| Residences {
+            min: age,
+            max: age,
+            sum: Duration::ZERO,
+            count: 0,
+        });
+        residences.min = residences.min.min(age);
+        residences.max = residences.max.max(age);
+        residences.sum += age;
+        residences.count += 1;
+    }
+
+    fn stats(&mut self) -> Option<ResidenceStats> {
+        let mut buckets = self.buckets.iter();
+        let first = buckets.next()?.clone();
+        let residences = buckets.fold(first, |acc, residences| Residences {
+            min: acc.min.min(residences.min),
+            max: acc.max.max(residences.max),
+            sum: acc.sum + residences.sum,
+            count: acc.count + residences.count,
+        });
+        Some(ResidenceStats {
+            min: residences.min,
+            max: residences.max,
+            avg: residences.sum / residences.count as u32,
+            count: residences.count,
+        })
+    }
+}
+
+impl fmt::Debug for ResidenceHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResidenceHistory")
+            .field("buckets", &self.buckets.len())
+            .field("bucket_width", &self.buckets.width())
+            .finish()
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Starts to record the time each element popped was in the queue,
+    /// keeping the times of the last `window` of time split in `buckets`
+    /// buckets (at least one), like a [`crate::SlidingSum`], so
+    /// [`SumQueue::residence_stats()`] can tell how far behind
+    /// the consumers of the queue are running.
+    ///
+    /// Only the elements removed with [`SumQueue::pop()`] and the other
+    /// pop methods are recorded, not the ones that expire.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60))
+    ///     .with_residence_history(Duration::from_secs(300), 10);
+    /// queue.push("job-1");
+    /// queue.push("job-2");
+    /// thread::sleep(Duration::from_millis(100));
+    /// queue.pop_n(2);
+    /// let residence = queue.residence_stats().unwrap();
+    /// assert_eq!(residence.count, 2);
+    /// assert!(residence.min >= Duration::from_millis(100));
+    /// ```
+    pub fn with_residence_history(mut self, window: Duration, buckets: usize) -> SumQueue<T> {
+        self.residence = Some(ResidenceHistory::new(window, buckets));
+        self
+    }
+
+    /// Returns the stats of the times recorded within the window
+    /// set with [`SumQueue::with_residence_history()`], or `None` if the
+    /// times are not recorded or no elements were popped within the window.
+    pub fn residence_stats(&mut self) -> Option<ResidenceStats> {
+        self.residence.as_mut()?.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn residence_stats() {
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_millis(300))
+            .residence_history(Duration::from_millis(200), 2)
+            .build();
+        assert_eq!(queue.residence_stats(), None);
+        queue.push(1);
+        thread::sleep(Duration::from_millis(50));
+        queue.push(2);
+        queue.push(3);
+        queue.push(4);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop_while(|&x| x < 3), vec![2]);
+        let residence = queue.residence_stats().unwrap();
+        assert_eq!(residence.count, 2);
+        assert!(residence.max >= Duration::from_millis(50));
+        assert!(residence.min < Duration::from_millis(50));
+        assert_eq!(residence.avg, (residence.min + residence.max) / 2);
+        thread::sleep(Duration::from_millis(350));
+        // the expired elements are not recorded
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.residence_stats(), None);
+    }
+}
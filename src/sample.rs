@@ -0,0 +1,99 @@
+use crate::SumQueue;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Small pseudo-random number generator (SplitMix64), randomly seeded
+/// with the keys of the standard library hasher, good enough for sampling.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new() -> Rng {
+        Rng(RandomState::new().build_hasher().finish())
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the range `0..n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Returns a uniform random sample of `n` values of the queue, or
+    /// all of them if the queue has `n` values or less, so large windows can
+    /// be summarized or shipped for offline analysis without exporting
+    /// everything. The values are returned in the same order they were pushed.
+    ///
+    /// Each value has the same probability to be chosen, and it takes
+    /// linear time on the length of the queue. Before the sample is
+    /// taken, it also drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// for i in 0..1000 {
+    ///     queue.push(i);
+    /// }
+    /// let sample = queue.sample(10);
+    /// assert_eq!(sample.len(), 10);
+    /// assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+    /// ```
+    pub fn sample(&mut self, n: usize) -> Vec<&T> {
+        self.clear_oldest(self.now());
+        let len = self.queue.len();
+        let mut rng = Rng::new();
+        let mut sample = Vec::with_capacity(n.min(len));
+        // selection sampling: each value is chosen with a probability of
+        // the values still needed divided by the values still to visit
+        for (i, q_element) in self.queue.iter().enumerate() {
+            if sample.len() == n {
+                break;
+            }
+            if rng.below(len - i) < n - sample.len() {
+                sample.push(&q_element.value);
+            }
+        }
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::time::Duration;
+
+    #[test]
+    fn sample() {
+        let mut queue = SumQueue::new(Duration::from_secs(60));
+        assert!(queue.sample(3).is_empty());
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.sample(3), vec![&1, &2]);
+        assert!(queue.sample(0).is_empty());
+        for i in 3..=10 {
+            queue.push(i);
+        }
+        // all the values are sampled at a similar rate
+        let mut hits = [0; 10];
+        for _ in 0..10_000 {
+            let sample = queue.sample(3);
+            assert_eq!(sample.len(), 3);
+            for value in sample {
+                hits[*value as usize - 1] += 1;
+            }
+        }
+        assert!(
+            hits.iter().all(|&h| h > 2500 && h < 3500),
+            "hits: {:?}",
+            hits
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::SumQueue;
+
+impl<T: Copy + Into<f64>> SumQueue<T> {
+    /// Pushes an item at the back of the queue, and returns its z-score
+    /// against the values that were in the queue, that is how many standard
+    /// deviations the new value is away from their mean, so simple
+    /// online anomaly detection can be done in one call.
+    ///
+    /// The score is zero if the queue has less than two values, or if
+    /// all of them are equal. Before the score is computed, it also
+    /// drops all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut latencies = SumQueue::new(Duration::from_secs(60));
+    /// for latency in [98, 102, 99, 101, 100] {
+    ///     latencies.push(latency);
+    /// }
+    /// assert!(latencies.push_and_score(101) < 1.0);
+    /// assert!(latencies.push_and_score(250) > 3.0);
+    /// ```
+    ///
+    /// See [`SumQueue::push_and_score_with()`] to use another metric.
+    pub fn push_and_score(&mut self, item: T) -> f64 {
+        self.push_and_score_with(item, |value, mean, std_dev| {
+            if std_dev == 0.0 {
+                0.0
+            } else {
+                (value - mean) / std_dev
+            }
+        })
+    }
+
+    /// Pushes an item at the back of the queue, and returns the score
+    /// computed by `metric` with the new value, and the mean and the
+    /// (population) standard deviation of the values that were in the queue.
+    ///
+    /// If the queue has less than two values the score is zero, and
+    /// `metric` is not called. Before the score is computed, it also drops
+    /// all expired elements.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(10.0);
+    /// queue.push(30.0);
+    /// // relative deviation from the mean
+    /// let score = queue.push_and_score_with(25.0, |value, mean, _| (value - mean) / mean);
+    /// assert_eq!(score, 0.25);
+    /// ```
+    pub fn push_and_score_with<F>(&mut self, item: T, metric: F) -> f64
+    where
+        F: FnOnce(f64, f64, f64) -> f64,
+    {
+        let len = self.len();
+        let score = if len < 2 {
+            0.0
+        } else {
+            let values = || self.queue.iter().map(|q_element| q_element.value.into());
+            let mean = values().sum::<f64>() / len as f64;
+            let variance = values().map(|v| (v - mean) * (v - mean)).sum::<f64>() / len as f64;
+            metric(item.into(), mean, variance.sqrt())
+        };
+        self.push(item);
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SumQueue;
+    use std::time::Duration;
+
+    #[test]
+    fn push_and_score() {
+        let mut queue: SumQueue<i32> = SumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.push_and_score(5), 0.0);
+        assert_eq!(queue.push_and_score(5), 0.0);
+        // all equal values
+        assert_eq!(queue.push_and_score(8), 0.0);
+        // mean 6, std dev 1.41
+        let score = queue.push_and_score(3);
+        assert!((score + 2.1213).abs() < 0.001, "score: {}", score);
+        assert_eq!(queue.len(), 4);
+        let mut calls = 0;
+        let mut single = SumQueue::new(Duration::from_secs(60));
+        single.push(1u8);
+        single.push_and_score_with(2, |_, _, _| {
+            calls += 1;
+            0.0
+        });
+        assert_eq!(calls, 0);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::lock::{self, Mutex, MutexGuard};
+use crate::{now, Instant, QueueStats, SumQueue, Summable};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Thread-safe queue that splits the elements across
+/// many internal queues ("shards"), each one guarded by its own lock.
+///
+/// Pushes are distributed across the shards, so many producer
+/// threads can push elements at the same time without waiting for each
+/// other, while the methods that read the queue, like [`ShardedSumQueue::stats()`],
+/// merge the results of all the shards.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::ShardedSumQueue;
+///
+/// let queue = Arc::new(ShardedSumQueue::new(Duration::from_secs(60), 4));
+/// let producers = (0..8)
+///     .map(|_| {
+///         let queue = queue.clone();
+///         thread::spawn(move || {
+///             for i in 1..=100 {
+///                 queue.push(i);
+///             }
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for producer in producers {
+///     producer.join().unwrap();
+/// }
+/// let stats = queue.stats();
+/// assert_eq!(stats.len, 800);
+/// assert_eq!(stats.sum, Some(8 * 5050));
+/// ```
+pub struct ShardedSumQueue<T> {
+    shards: Box<[Mutex<SumQueue<T>>]>,
+    next: AtomicUsize,
+    /// shard after the last one popped
+    next_pop: AtomicUsize,
+}
+
+impl<T> ShardedSumQueue<T> {
+    /// Creates an empty `ShardedSumQueue` with `shards` internal
+    /// queues (at least one), where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration, shards: usize) -> ShardedSumQueue<T> {
+        // the same epoch in all the shards, so their times can be compared
+        let epoch = now();
+        ShardedSumQueue {
+            shards: (0..shards.max(1))
+                .map(|_| Mutex::new(SumQueue::new(max_age_duration).with_epoch(epoch)))
+                .collect(),
+            next: AtomicUsize::new(0),
+            next_pop: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of internal queues.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn lock(shard: &Mutex<SumQueue<T>>) -> MutexGuard<'_, SumQueue<T>> {
+        lock::lock(shard)
+    }
+
+    /// Locks all the shards, always in the same order.
+    fn lock_all(&self) -> Vec<MutexGuard<'_, SumQueue<T>>> {
+        self.shards.iter().map(Self::lock).collect()
+    }
+
+    /// Returns the index of the shard with the oldest element, given the time
+    /// of the first element of each shard. The times are stored in milliseconds,
+    /// so the elements pushed within the same millisecond are taken in the same
+    /// round-robin order they were pushed, starting at the shard `first`.
+    fn oldest<I>(fronts: I, first: usize) -> Option<usize>
+    where
+        I: ExactSizeIterator<Item = Option<Instant>>,
+    {
+        let len = fronts.len();
+        fronts
+            .enumerate()
+            .filter_map(|(i, time)| time.map(|time| (time, (i + len - first % len) % len, i)))
+            .min()
+            .map(|(_, _, i)| i)
+    }
+
+    /// Pushes an item into the next shard, see [`SumQueue::push()`].
+    ///
+    /// Unlike [`SumQueue::push()`] it doesn't return the length of the
+    /// queue, because it would require to lock all the shards.
+    pub fn push(&self, item: T) {
+        let shard = self.next.fetch_add(1, Ordering::Relaxed) % self.shards.len();
+        Self::lock(&self.shards[shard]).push(item);
+    }
+
+    /// Drops all items.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            Self::lock(shard).clear();
+        }
+    }
+
+    /// Returns the length of the queue, that is the sum
+    /// of the lengths of all the shards.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| Self::lock(shard).len())
+            .sum()
+    }
+
+    /// Checks if all the shards are empty.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| Self::lock(shard).is_empty())
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        Self::lock(&self.shards[0]).max_age()
+    }
+
+    /// Removes the oldest item across all the shards and returns it,
+    /// or `None` if the queue is empty.
+    ///
+    /// It needs to lock all the shards at once to find the oldest
+    /// element, so it's slower than [`ShardedSumQueue::push()`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::ShardedSumQueue;
+    /// let queue = ShardedSumQueue::new(Duration::from_secs(60), 2);
+    /// queue.push('a');
+    /// queue.push('b');
+    /// queue.push('c');
+    /// assert_eq!(queue.pop(), Some('a'));
+    /// assert_eq!(queue.pop(), Some('b'));
+    /// assert_eq!(queue.pop(), Some('c'));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    pub fn pop(&self) -> Option<T> {
+        let now = now();
+        let mut shards = self.lock_all();
+        for shard in shards.iter_mut() {
+            shard.clear_oldest(now);
+        }
+        let first = self.next_pop.load(Ordering::Relaxed);
+        let i = Self::oldest(shards.iter().map(|shard| shard.front_time()), first)?;
+        self.next_pop.store(i + 1, Ordering::Relaxed);
+        shards[i].pop()
+    }
+
+    /// Returns a vector with a copy of all the values of all
+    /// the shards, in the same order they were pushed.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::ShardedSumQueue;
+    /// let queue = ShardedSumQueue::new(Duration::from_secs(60), 3);
+    /// for i in 0..5 {
+    ///     queue.push(i);
+    /// }
+    /// assert_eq!(queue.to_vec(), vec![0, 1, 2, 3, 4]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let now = now();
+        let mut shards = self.lock_all();
+        for shard in shards.iter_mut() {
+            shard.clear_oldest(now);
+        }
+        let mut iters = shards
+            .iter()
+            .map(|shard| {
+                let epoch = shard.epoch;
+                shard
+                    .queue
+                    .iter()
+                    .map(move |el| (epoch.instant(el.time), &el.value))
+                    .peekable()
+            })
+            .collect::<Vec<_>>();
+        let mut items = Vec::new();
+        let mut first = self.next_pop.load(Ordering::Relaxed);
+        while let Some(i) = Self::oldest(
+            iters.iter_mut().map(|iter| iter.peek().map(|el| el.0)),
+            first,
+        ) {
+            if let Some((_, value)) = iters[i].next() {
+                items.push(value.clone());
+            }
+            first = i + 1;
+        }
+        items
+    }
+}
+
+impl<T: Summable> ShardedSumQueue<T> {
+    /// Get statistics of the queue, merging the stats of
+    /// all the shards, see [`SumQueue::stats()`] and [`QueueStats::merge()`].
+    ///
+    /// The shards are locked one at a time, so the result
+    /// is not a snapshot of the whole queue at a single instant.
+    pub fn stats(&self) -> QueueStats<T> {
+        self.shards
+            .iter()
+            .map(|shard| Self::lock(shard).stats())
+            .fold(QueueStats::default(), QueueStats::merge)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ShardedSumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedSumQueue")
+            .field("shards", &self.lock_all())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ShardedSumQueue;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn push_pop_threads() {
+        let queue = Arc::new(ShardedSumQueue::new(Duration::from_secs(60), 3));
+        assert_eq!(queue.shards(), 3);
+        let producers = (0..6)
+            .map(|t| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        queue.push(t * 50 + i);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(queue.len(), 300);
+        let stats = queue.stats();
+        assert_eq!(stats.min, Some(0));
+        assert_eq!(stats.max, Some(299));
+        assert_eq!(stats.sum, Some((0..300).sum()));
+        let mut popped = Vec::new();
+        while let Some(value) = queue.pop() {
+            popped.push(value);
+        }
+        popped.sort_unstable();
+        assert_eq!(popped, (0..300).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn expire() {
+        let queue = ShardedSumQueue::new(Duration::from_millis(100), 0);
+        assert_eq!(queue.shards(), 1);
+        queue.push(1);
+        thread::sleep(Duration::from_millis(150));
+        queue.push(2);
+        assert_eq!(queue.to_vec(), vec![2]);
+        assert_eq!(queue.stats().len, 1);
+        queue.clear();
+        assert_eq!(queue.stats().sum, None);
+    }
+}
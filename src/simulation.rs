@@ -0,0 +1,188 @@
+use crate::{Clock, QueueStats, SumQueue, Summable};
+use std::time::Duration;
+
+/// Step of the script replayed by [`SimulatedQueue::run()`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step<T> {
+    /// moves the time of the queue forward
+    Advance(Duration),
+    /// pushes a value into the queue, see [`SumQueue::push()`]
+    Push(T),
+    /// pops a value from the queue, see [`SumQueue::pop()`]
+    Pop,
+}
+
+/// State of a [`SimulatedQueue`] after a [`Step`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepResult<T> {
+    /// simulated time elapsed since the simulation started
+    pub elapsed: Duration,
+    /// value popped by a [`Step::Pop`] step
+    pub popped: Option<T>,
+    /// stats of the queue after the step
+    pub stats: QueueStats<T>,
+}
+
+/// Queue where the time only moves when it's advanced by hand, with a
+/// [`Clock::manual()`], so the windowing logic can be tested deterministically
+/// and without waiting for the elements to expire.
+///
+/// The steps can be run one by one, or replayed from a script
+/// with [`SimulatedQueue::run()`], that records the stats after each step.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::{SimulatedQueue, Step, SumQueue};
+/// let mut sim = SimulatedQueue::new(SumQueue::new(Duration::from_secs(60)));
+/// let results = sim.run(vec![
+///     Step::Push(10),
+///     Step::Advance(Duration::from_secs(40)),
+///     Step::Push(5),
+///     Step::Advance(Duration::from_secs(30)),
+/// ]);
+/// let sums = results.iter().map(|r| r.stats.sum).collect::<Vec<_>>();
+/// assert_eq!(sums, vec![Some(10), Some(10), Some(15), Some(5)]);
+/// assert_eq!(results[3].elapsed, Duration::from_secs(70));
+/// ```
+pub struct SimulatedQueue<T> {
+    queue: SumQueue<T>,
+    clock: Clock,
+    elapsed: Duration,
+}
+
+impl<T> SimulatedQueue<T> {
+    /// Creates a simulation of `queue`, replacing its clock with
+    /// a [`Clock::manual()`] one that starts at the current time.
+    pub fn new(queue: SumQueue<T>) -> SimulatedQueue<T> {
+        let clock = Clock::manual();
+        SimulatedQueue {
+            queue: queue.with_clock(clock.clone()),
+            clock,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Moves the time of the queue forward by `by`.
+    pub fn advance(&mut self, by: Duration) {
+        self.clock.advance(by);
+        self.elapsed += by;
+    }
+
+    /// Returns the simulated time elapsed since the simulation started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the queue, to run any operation at the current simulated time.
+    pub fn queue(&mut self) -> &mut SumQueue<T> {
+        &mut self.queue
+    }
+
+    /// Consumes the simulation and returns the queue, with the manual clock.
+    pub fn into_queue(self) -> SumQueue<T> {
+        self.queue
+    }
+}
+
+impl<T: Summable> SimulatedQueue<T> {
+    /// Runs a step, and returns the state of the queue after it.
+    pub fn step(&mut self, step: Step<T>) -> StepResult<T> {
+        let popped = match step {
+            Step::Advance(by) => {
+                self.advance(by);
+                None
+            }
+            Step::Push(value) => {
+                self.queue.push(value);
+                None
+            }
+            Step::Pop => self.queue.pop(),
+        };
+        StepResult {
+            elapsed: self.elapsed,
+            popped,
+            stats: self.queue.stats(),
+        }
+    }
+
+    /// Runs all the steps of `script` in order, and
+    /// returns the state of the queue after each one.
+    pub fn run<I>(&mut self, script: I) -> Vec<StepResult<T>>
+    where
+        I: IntoIterator<Item = Step<T>>,
+    {
+        script.into_iter().map(|step| self.step(step)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SimulatedQueue, Step, SumQueue};
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    #[test]
+    fn run() {
+        let queue = SumQueue::builder()
+            .max_age(Duration::from_millis(100))
+            .max_len(2)
+            .build();
+        let mut sim = SimulatedQueue::new(queue);
+        let results = sim.run(vec![
+            Step::Push(1),
+            Step::Push(2),
+            Step::Push(3),
+            Step::Pop,
+            Step::Advance(Duration::from_millis(101)),
+            Step::Pop,
+        ]);
+        assert_eq!(results[2].stats.sum, Some(5));
+        assert_eq!(results[3].popped, Some(2));
+        assert_eq!(results[4].stats.len, 0);
+        assert_eq!(results[5].popped, None);
+        assert_eq!(sim.elapsed(), Duration::from_millis(101));
+    }
+
+    /// Replays pseudo-random scripts and compares the
+    /// stats with a naive model of the window.
+    #[test]
+    fn model() {
+        let mut seed = 7u64;
+        let mut next = move |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        for _ in 0..20 {
+            let max_age = Duration::from_millis(50);
+            let mut sim = SimulatedQueue::new(SumQueue::new(max_age));
+            let mut model: VecDeque<(Duration, u64)> = VecDeque::new();
+            for _ in 0..200 {
+                let step = match next(3) {
+                    0 => Step::Advance(Duration::from_millis(next(30))),
+                    1 => Step::Push(next(100)),
+                    _ => Step::Pop,
+                };
+                let elapsed = sim.elapsed();
+                match &step {
+                    Step::Advance(by) => {
+                        let now = elapsed + *by;
+                        model.retain(|(at, _)| now - *at <= max_age);
+                    }
+                    Step::Push(value) => model.push_back((elapsed, *value)),
+                    Step::Pop => {}
+                }
+                let result = sim.step(step.clone());
+                if step == Step::Pop {
+                    assert_eq!(result.popped, model.pop_front().map(|(_, value)| value));
+                }
+                let values = model.iter().map(|(_, value)| *value);
+                assert_eq!(result.stats.len, model.len());
+                assert_eq!(result.stats.sum, values.clone().reduce(|a, b| a + b));
+                assert_eq!(result.stats.min, values.clone().min());
+                assert_eq!(result.stats.max, values.max());
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{now, Instant, QueueStats, Summable};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// Sliding window split into a fixed number of time slots ("buckets"),
+/// each one holding an aggregate of the values of its slot.
+///
+/// The window keeps the buckets of the last `len` slots, including
+/// the current one, so the memory used is bounded by the number of
+/// buckets, not by the number of values.
+#[derive(Clone)]
+pub(crate) struct Buckets<A> {
+    /// aggregates by slot number, oldest first
+    buckets: VecDeque<(u64, A)>,
+    len: u64,
+    width: Duration,
+    start: Instant,
+}
+
+impl<A> Buckets<A> {
+    /// Creates a window of `len` buckets (at least one) covering `window`.
+    pub(crate) fn new(window: Duration, len: usize) -> Buckets<A> {
+        let len = len.max(1);
+        Buckets {
+            buckets: VecDeque::with_capacity(len),
+            len: len as u64,
+            width: (window / len as u32).max(Duration::from_nanos(1)),
+            start: now(),
+        }
+    }
+
+    /// Returns the duration covered by each bucket.
+    pub(crate) fn width(&self) -> Duration {
+        self.width
+    }
+
+    /// Returns the number of buckets of the window.
+    pub(crate) fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    fn slot(&self, now: Instant) -> u64 {
+        (now.saturating_duration_since(self.start).as_nanos() / self.width.as_nanos()) as u64
+    }
+
+    /// Drops the buckets of the slots that are out of the window at `now`,
+    /// and returns the current slot.
+    fn rotate(&mut self, now: Instant) -> u64 {
+        let slot = self.slot(now);
+        while let Some((oldest, _)) = self.buckets.front() {
+            if slot - oldest >= self.len {
+                self.buckets.pop_front();
+            } else {
+                break;
+            }
+        }
+        slot
+    }
+
+    /// Returns the aggregate of the current slot, creating it with `init` if needed.
+    pub(crate) fn current(&mut self, init: impl FnOnce() -> A) -> &mut A {
+        let slot = self.rotate(now());
+        if self.buckets.back().is_none_or(|(last, _)| *last != slot) {
+            self.buckets.push_back((slot, init()));
+        }
+        &mut self.buckets.back_mut().unwrap().1
+    }
+
+    /// Returns an iterator over the aggregates of the
+    /// buckets within the window, oldest first.
+    pub(crate) fn iter(&mut self) -> impl DoubleEndedIterator<Item = &A> {
+        self.rotate(now());
+        self.buckets.iter().map(|(_, aggregate)| aggregate)
+    }
+
+    /// Drops all the buckets.
+    pub(crate) fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+/// Sliding window aggregator that keeps the stats of the
+/// values pushed within the window, using constant memory.
+///
+/// Instead of keeping every value like a [`crate::SumQueue`], the window
+/// is split into a fixed number of buckets of the same duration, and
+/// only the stats of the values of each bucket are kept. That allows
+/// to window very high rates of values, at the cost of precision: the values
+/// expire all together when their bucket leaves the window, so the stats
+/// may include values up to one bucket older than the window.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::SlidingSum;
+///
+/// // one minute window, split in buckets of one second
+/// let mut window = SlidingSum::new(Duration::from_secs(60), 60);
+/// for i in 1..=100_000 {
+///     window.push(i % 10);
+/// }
+/// let stats = window.stats();
+/// assert_eq!(stats.len, 100_000);
+/// assert_eq!(stats.min, Some(0));
+/// assert_eq!(stats.max, Some(9));
+/// assert_eq!(stats.sum, Some(450_000));
+/// ```
+pub struct SlidingSum<T> {
+    buckets: Buckets<QueueStats<T>>,
+}
+
+impl<T: Summable> SlidingSum<T> {
+    /// Creates an empty aggregator of the values pushed within
+    /// the last `window` of time, split in `buckets` buckets (at least one).
+    pub fn new(window: Duration, buckets: usize) -> SlidingSum<T> {
+        SlidingSum {
+            buckets: Buckets::new(window, buckets),
+        }
+    }
+
+    /// Returns the number of buckets the window is split in.
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the duration covered by each bucket.
+    pub fn bucket_width(&self) -> Duration {
+        self.buckets.width()
+    }
+
+    /// Adds a value to the current bucket.
+    pub fn push(&mut self, item: T) {
+        let stats = self.buckets.current(QueueStats::default);
+        let merged = std::mem::take(stats).merge(QueueStats {
+            min: Some(item),
+            max: Some(item),
+            sum: Some(item),
+            len: 1,
+        });
+        *stats = merged;
+    }
+
+    /// Get the stats of the values of all the buckets within the window.
+    pub fn stats(&mut self) -> QueueStats<T> {
+        self.buckets
+            .iter()
+            .cloned()
+            .fold(QueueStats::default(), QueueStats::merge)
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.buckets.clear()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SlidingSum<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlidingSum")
+            .field("bucket_width", &self.buckets.width)
+            .field("buckets", &self.buckets.buckets)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SlidingSum;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn expire_buckets() {
+        let mut window = SlidingSum::new(Duration::from_millis(100), 4);
+        assert_eq!(window.buckets(), 4);
+        assert_eq!(window.bucket_width(), Duration::from_millis(25));
+        window.push(5);
+        window.push(-1);
+        assert_eq!(window.stats().sum, Some(4));
+        thread::sleep(Duration::from_millis(60));
+        window.push(10);
+        let stats = window.stats();
+        assert_eq!(stats.len, 3);
+        assert_eq!(stats.max, Some(10));
+        thread::sleep(Duration::from_millis(60));
+        let stats = window.stats();
+        assert_eq!(stats.len, 1);
+        assert_eq!(stats.min, Some(10));
+        window.clear();
+        assert_eq!(window.stats().sum, None);
+        assert!(window.buckets.buckets.len() <= 4);
+    }
+
+    #[test]
+    fn zero_window() {
+        let mut window = SlidingSum::new(Duration::ZERO, 0);
+        assert_eq!(window.buckets(), 1);
+        window.push(1);
+        assert!(window.stats().len <= 1);
+    }
+}
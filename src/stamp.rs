@@ -0,0 +1,220 @@
+use crate::{Instant, QueueElement, SumQueue};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// Time an element was pushed at, as the milliseconds elapsed since the
+/// [`Epoch`] of its queue, so it takes 4 bytes instead of the 16 bytes an
+/// `Instant` takes on most platforms.
+pub(crate) type Stamp = u32;
+
+/// Max time a [`Stamp`] can be after the epoch, about 49.7 days.
+const MAX_SPAN: Duration = Duration::from_millis(u32::MAX as u64);
+
+/// Longest time the elements can be kept in a [`SumQueue`], adding up its
+/// max age and its grace period, about 24.8 days.
+///
+/// The times of the elements are stored as milliseconds in 4 bytes, that span
+/// about 49.7 days. When a new element doesn't fit, the times are counted from
+/// a newer instant, that is half the span before the new element at most, so
+/// older elements would look younger than they are and outlive the max age.
+///
+/// A queue can be created with a max age of `Duration::MAX` as well, so the
+/// elements never expire, but then the ages of the elements older than
+/// this limit are not accurate.
+pub const MAX_AGE_LIMIT: Duration = Duration::from_millis(u32::MAX as u64 / 2);
+
+/// Panics if the elements would be kept longer than [`MAX_AGE_LIMIT`]
+/// with `max_age` and `grace`, unless they never expire.
+pub(crate) const fn check_max_age(max_age: Duration, grace: Duration) {
+    let never = max_age.as_secs() == u64::MAX && max_age.subsec_nanos() == 999_999_999;
+    assert!(
+        never || max_age.saturating_add(grace).as_millis() <= MAX_AGE_LIMIT.as_millis(),
+        "the max age plus the grace period of a queue can't be longer than MAX_AGE_LIMIT"
+    );
+}
+
+/// Instant the times of the elements of a queue are counted from,
+/// not set until the first element is pushed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Epoch(Option<Instant>);
+
+impl Epoch {
+    pub(crate) const fn new() -> Epoch {
+        Epoch(None)
+    }
+
+    /// Returns the instant of `stamp`.
+    ///
+    /// The epoch is always set before an element is stamped,
+    /// so it panics only if there is a bug in the crate.
+    pub(crate) fn instant(self, stamp: Stamp) -> Instant {
+        let epoch = self.0.expect("elements are stamped after the epoch is set");
+        epoch + Duration::from_millis(stamp.into())
+    }
+
+    /// Returns the time elapsed from `stamp` to `now`,
+    /// or zero if `now` is before.
+    pub(crate) fn age(self, stamp: Stamp, now: Instant) -> Duration {
+        match self.0 {
+            Some(epoch) => now
+                .saturating_duration_since(epoch)
+                .saturating_sub(Duration::from_millis(stamp.into())),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Returns the stamp of `at`, or `None` if it's
+    /// before the epoch or too far after it.
+    fn stamp(self, at: Instant) -> Option<Stamp> {
+        let elapsed = at.checked_duration_since(self.0?)?;
+        Stamp::try_from(elapsed.as_millis()).ok()
+    }
+
+    /// Returns the stamp of `at`, clamped to the range of the epoch.
+    fn stamp_saturating(self, at: Instant) -> Stamp {
+        self.stamp(at).unwrap_or(match self.0 {
+            Some(epoch) if at > epoch => Stamp::MAX,
+            _ => 0,
+        })
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Returns the stamp of the instant `at`, moving the epoch of
+    /// the queue first if `at` doesn't fit in its range.
+    pub(crate) fn stamp(&mut self, at: Instant) -> Stamp {
+        if let Some(stamp) = self.epoch.stamp(at) {
+            return stamp;
+        }
+        // the elements kept are within MAX_AGE_LIMIT, half the span, unless
+        // they never expire, so only the ones not dropped yet may not fit
+        self.drop_expired(at);
+        let oldest = self.expired.front().or_else(|| self.queue.front());
+        let mut start = oldest.map_or(at, |el| self.epoch.instant(el.time).min(at));
+        if let Some(floor) = at.checked_sub(MAX_SPAN) {
+            if start < floor {
+                // the oldest elements can't fit anyway, so leave room
+                // to not move the epoch again on the next push
+                start = floor + MAX_SPAN / 2;
+            }
+        }
+        self.rebase(Epoch(Some(start)));
+        self.epoch.stamp_saturating(at)
+    }
+
+    /// Sets the epoch of an empty queue, so queues with the same
+    /// epoch store the same times for the elements pushed at once.
+    pub(crate) fn with_epoch(mut self, epoch: Instant) -> SumQueue<T> {
+        self.epoch = Epoch(Some(epoch));
+        self
+    }
+
+    /// Returns the time the oldest element was pushed at, if any.
+    pub(crate) fn front_time(&self) -> Option<Instant> {
+        self.queue.front().map(|el| self.epoch.instant(el.time))
+    }
+
+    /// Stamps the items, sorted by their instants, moving
+    /// the epoch of the queue at most once to fit all of them.
+    pub(crate) fn stamp_sorted(&mut self, items: Vec<(Instant, T)>) -> VecDeque<QueueElement<T>> {
+        if let (Some(first), Some(last)) = (items.first(), items.last()) {
+            let (first, last) = (first.0, last.0);
+            self.stamp(first);
+            self.stamp(last);
+        }
+        let epoch = self.epoch;
+        items
+            .into_iter()
+            .map(|(at, value)| QueueElement {
+                time: epoch.stamp_saturating(at),
+                value,
+            })
+            .collect()
+    }
+
+    /// Moves the epoch of the queue, stamping again all the elements.
+    fn rebase(&mut self, epoch: Epoch) {
+        let old = self.epoch;
+        for el in self.expired.iter_mut().chain(self.queue.iter_mut()) {
+            el.time = epoch.stamp_saturating(old.instant(el.time));
+        }
+        self.epoch = epoch;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stamp::{Epoch, MAX_SPAN};
+    use crate::{Clock, Instant, QueueElement, SumQueue, MAX_AGE_LIMIT};
+    use std::mem::size_of;
+    use std::panic;
+    use std::time::Duration;
+
+    #[test]
+    fn element_size() {
+        assert_eq!(size_of::<QueueElement<u32>>(), 8);
+        assert_eq!(size_of::<QueueElement<u64>>(), 16);
+    }
+
+    #[test]
+    fn stamp() {
+        let now = Instant::now();
+        // elements that never expire, so they aren't dropped to fit the span
+        let mut queue = SumQueue::new(Duration::MAX);
+        assert_eq!(queue.stamp(now), 0);
+        assert_eq!(queue.stamp(now + Duration::from_micros(2500)), 2);
+        assert_eq!(
+            queue.epoch.age(2, now + Duration::from_millis(10)),
+            Duration::from_millis(8)
+        );
+        assert_eq!(queue.epoch.age(2, now), Duration::ZERO);
+        queue.push_at(now, 1).unwrap();
+        // instants before the epoch move it back
+        let before = now - Duration::from_millis(5);
+        assert_eq!(queue.stamp(before), 0);
+        assert_eq!(queue.queue[0].time, 5);
+        // and instants too far after the epoch move it to the oldest element
+        assert_eq!(queue.stamp(now + MAX_SPAN), u32::MAX);
+        assert_eq!(queue.queue[0].time, 0);
+        let far = now + MAX_SPAN + MAX_SPAN;
+        assert_eq!(queue.stamp(far), u32::MAX / 2);
+        assert_eq!(queue.queue[0].time, 0);
+        assert_eq!(Epoch::new().age(0, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn max_age_limit() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let clock = Clock::manual();
+        let mut queue = SumQueue::builder()
+            .max_age(MAX_AGE_LIMIT)
+            .prune_every(100)
+            .clock(clock.clone())
+            .build();
+        queue.push(1);
+        clock.advance(DAY * 30);
+        queue.push(2);
+        clock.advance(DAY * 20);
+        // the first element doesn't fit in the span, but
+        // the ones within the limit keep their ages
+        queue.push(3);
+        let ages = queue.to_vec_with_ages();
+        assert_eq!(ages.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(ages[0].0 - DAY * 20 < Duration::from_millis(1));
+        // elements that never expire are accepted
+        SumQueue::<i32>::new(Duration::MAX);
+        for longer in [
+            panic::catch_unwind(|| SumQueue::<i32>::new(MAX_AGE_LIMIT + Duration::from_millis(1))),
+            panic::catch_unwind(|| SumQueue::<i32>::new(DAY * 10).with_grace(DAY * 20)),
+            panic::catch_unwind(|| {
+                SumQueue::<i32>::builder()
+                    .grace(DAY * 20)
+                    .max_age(DAY * 10)
+                    .build()
+            }),
+        ] {
+            assert!(longer.is_err());
+        }
+    }
+}
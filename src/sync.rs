@@ -0,0 +1,646 @@
+//! Thread-safe version of the queue.
+//!
+//! The [`SumQueue`] of this module wraps a [`crate::SumQueue`] guarded
+//! by a lock, and offers the same methods but taking a shared reference
+//! of `self`, so it can be shared between producer and consumer threads
+//! behind an [`Arc`]:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use std::time::Duration;
+//! use sum_queue::sync::SumQueue;
+//!
+//! let queue = Arc::new(SumQueue::new(Duration::from_secs(60)));
+//! let producer = {
+//!     let queue = queue.clone();
+//!     thread::spawn(move || {
+//!         for i in 0..10 {
+//!             queue.push(i);
+//!         }
+//!     })
+//! };
+//! producer.join().unwrap();
+//! assert_eq!(queue.len(), 10);
+//! assert_eq!(queue.pop(), Some(0));
+//! assert_eq!(queue.stats().sum, Some(45));
+//! ```
+//!
+//! Or use a [`SharedSumQueue`], a handle that can be cloned
+//! and moved to other threads or closures, all the clones
+//! pointing to the same queue. [`SharedSumQueue::feed()`] pushes into the
+//! queue the items received from a channel in a background thread.
+//!
+//! With the `arc-swap` feature enabled, the `SnapshotSumQueue` type
+//! keeps a copy of the latest stats that can be read without locking the queue.
+//!
+//! With the `async` feature enabled, `SumQueue::pop_async()` allows
+//! async tasks to wait until an element is available to pop, and
+//! `SharedSumQueue::into_stream()` returns a stream of the popped elements,
+//! while `SharedSumQueue` can also be used as a `Sink` to push elements.
+//!
+//! With the `watch` feature enabled, `SumQueue::stats_watch()` returns
+//! a Tokio watch channel that receives the stats each time they change.
+
+#[cfg(feature = "async")]
+mod future;
+#[cfg(feature = "arc-swap")]
+mod snapshot;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "async")]
+pub use future::{Pop, PopStream};
+#[cfg(feature = "arc-swap")]
+pub use snapshot::SnapshotSumQueue;
+
+use crate::lock::{self, Mutex, MutexGuard};
+use crate::{QueueStats, Summable};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::task::Waker;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Thread-safe queue, see the [module documentation](self) for more.
+pub struct SumQueue<T> {
+    inner: Mutex<Inner<T>>,
+}
+
+/// State of a [`SumQueue`] guarded by its lock.
+struct Inner<T> {
+    queue: crate::SumQueue<T>,
+    /// tasks waiting for an element to pop
+    #[cfg(feature = "async")]
+    pop_wakers: Vec<Waker>,
+    /// tasks waiting for room to push an element
+    #[cfg(feature = "async")]
+    push_wakers: Vec<Waker>,
+    /// channel where the stats are sent
+    #[cfg(feature = "watch")]
+    stats_tx: Option<Box<dyn watch::StatsSender<T>>>,
+}
+
+impl<T> Inner<T> {
+    const fn new(queue: crate::SumQueue<T>) -> Inner<T> {
+        Inner {
+            queue,
+            #[cfg(feature = "async")]
+            pop_wakers: Vec::new(),
+            #[cfg(feature = "async")]
+            push_wakers: Vec::new(),
+            #[cfg(feature = "watch")]
+            stats_tx: None,
+        }
+    }
+}
+
+/// Guard with access to the queue locked by [`SumQueue::lock()`].
+///
+/// It derefs to the inner [`crate::SumQueue`], and releases
+/// the lock when dropped.
+pub struct SumQueueGuard<'a, T> {
+    guard: MutexGuard<'a, Inner<T>>,
+}
+
+impl<T> Deref for SumQueueGuard<'_, T> {
+    type Target = crate::SumQueue<T>;
+
+    fn deref(&self) -> &crate::SumQueue<T> {
+        &self.guard.queue
+    }
+}
+
+impl<T> DerefMut for SumQueueGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut crate::SumQueue<T> {
+        &mut self.guard.queue
+    }
+}
+
+/// Sends the stats to the watch channel if they changed,
+/// wakes up the tasks waiting to pop if there is an element ready
+/// to pop, or when there will be one, and the tasks waiting to push if there is room
+/// for more elements, before releasing the lock.
+#[cfg(any(feature = "async", feature = "watch"))]
+impl<T> Drop for SumQueueGuard<'_, T> {
+    fn drop(&mut self) {
+        let inner = &mut *self.guard;
+        #[cfg(feature = "watch")]
+        if let Some(stats_tx) = &inner.stats_tx {
+            stats_tx.update(&mut inner.queue);
+        }
+        #[cfg(feature = "async")]
+        if !inner.pop_wakers.is_empty() {
+            // with a min age the first element may not be ready to pop
+            // yet, so the tasks are woken up when it's ready instead
+            match inner.queue.ready_in() {
+                Some(Duration::ZERO) => inner.pop_wakers.drain(..).for_each(Waker::wake),
+                Some(delay) => future::wake_after(&mut inner.pop_wakers, delay),
+                None => {}
+            }
+        }
+        #[cfg(feature = "async")]
+        if !inner.push_wakers.is_empty() && !inner.queue.is_full() {
+            inner.push_wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Creates an empty `SumQueue`, where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub const fn new(max_age_duration: Duration) -> SumQueue<T> {
+        SumQueue {
+            inner: Mutex::new(Inner::new(crate::SumQueue::new(max_age_duration))),
+        }
+    }
+
+    /// Creates an empty `SumQueue` with a specific initial capacity,
+    /// see [`crate::SumQueue::with_capacity()`].
+    pub fn with_capacity(max_age_duration: Duration, capacity: usize) -> SumQueue<T> {
+        SumQueue::from(crate::SumQueue::with_capacity(max_age_duration, capacity))
+    }
+
+    /// Acquires the lock of the queue, blocking the current thread
+    /// until it's able to do so, and returns a guard with
+    /// access to all the methods of the inner [`crate::SumQueue`].
+    ///
+    /// Useful to call many methods in a row without other
+    /// threads modifying the queue in the meantime:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SumQueue;
+    /// let queue = SumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(5);
+    /// let mut guard = queue.lock();
+    /// assert_eq!(guard.iter().collect::<Vec<_>>(), vec![&1, &5]);
+    /// ```
+    ///
+    /// A panic in another thread while holding the lock doesn't poison
+    /// the queue, the queue is still accessible after that.
+    pub fn lock(&self) -> SumQueueGuard<'_, T> {
+        SumQueueGuard {
+            guard: self.lock_inner(),
+        }
+    }
+
+    fn lock_inner(&self) -> MutexGuard<'_, Inner<T>> {
+        lock::lock(&self.inner)
+    }
+
+    /// Consumes the lock, returning the inner queue.
+    pub fn into_inner(self) -> crate::SumQueue<T> {
+        lock::into_inner(self.inner).queue
+    }
+
+    /// Pushes an item at the back of the queue, see [`crate::SumQueue::push()`].
+    pub fn push(&self, item: T) -> usize {
+        self.lock().push(item)
+    }
+
+    /// Drops all items.
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    /// Returns the length of the queue, see [`crate::SumQueue::len()`].
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks if the queue is empty, see [`crate::SumQueue::is_empty()`].
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the number of elements the queue can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity()
+    }
+
+    /// Returns the max number of elements the queue can hold, if configured.
+    pub fn max_len(&self) -> Option<usize> {
+        self.lock().max_len()
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        self.lock().max_age()
+    }
+
+    /// Returns a copy of the first item in the queue, or `None` if it is empty,
+    /// see [`crate::SumQueue::peek()`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SumQueue;
+    /// let queue = SumQueue::new(Duration::from_secs(60));
+    /// assert_eq!(queue.peek(), None);
+    /// queue.push("Hello");
+    /// queue.push("World");
+    /// assert_eq!(queue.peek(), Some("Hello"));
+    /// ```
+    pub fn peek(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.lock().peek().cloned()
+    }
+
+    /// Removes the first item from the queue and returns it, or `None` if it
+    /// is empty, see [`crate::SumQueue::pop()`].
+    pub fn pop(&self) -> Option<T> {
+        self.lock().pop()
+    }
+
+    /// Removes the first item from the queue and returns it, waiting
+    /// until an element is pushed if the queue is empty, or until the
+    /// first element reaches the [min age](crate::SumQueue::with_min_age()).
+    ///
+    /// The future returned doesn't depend on a specific async runtime,
+    /// it's woken up by the thread that pushes the element, or by a
+    /// timer thread shared by all the queues when it has to wait for
+    /// the min age. With the `wasm` feature there is no timer thread,
+    /// so it's woken up by the first operation on the queue, like a
+    /// push, after the min age.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SharedSumQueue;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let queue = SharedSumQueue::new(Duration::from_secs(60));
+    /// let producer = queue.clone();
+    /// tokio::spawn(async move {
+    ///     producer.push("Hello");
+    /// });
+    /// assert_eq!(queue.pop_async().await, "Hello");
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn pop_async(&self) -> Pop<'_, T> {
+        Pop::new(self)
+    }
+
+    /// Removes the first item from the queue and returns it with its age,
+    /// see [`crate::SumQueue::pop_with_age()`].
+    pub fn pop_with_age(&self) -> Option<(T, Duration)> {
+        self.lock().pop_with_age()
+    }
+
+    /// Removes up to `n` items from the queue, see [`crate::SumQueue::pop_n()`].
+    pub fn pop_n(&self, n: usize) -> Vec<T> {
+        self.lock().pop_n(n)
+    }
+
+    /// Removes the items from the queue while the `predicate` returns `true`
+    /// for them, see [`crate::SumQueue::pop_while()`].
+    pub fn pop_while<P>(&self, predicate: P) -> Vec<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.lock().pop_while(predicate)
+    }
+
+    /// Returns `true` if the queue contains an element equal
+    /// to the given value, see [`crate::SumQueue::contains()`].
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.lock().contains(item)
+    }
+
+    /// Returns a vector with a copy of all the values,
+    /// see [`crate::SumQueue::to_vec()`].
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock().to_vec()
+    }
+
+    /// Returns a vector with a copy of all the values alongside their ages,
+    /// see [`crate::SumQueue::to_vec_with_ages()`].
+    pub fn to_vec_with_ages(&self) -> Vec<(Duration, T)>
+    where
+        T: Clone,
+    {
+        self.lock().to_vec_with_ages()
+    }
+}
+
+impl<T: Summable> SumQueue<T> {
+    /// Get statistics of the queue, see [`crate::SumQueue::stats()`].
+    pub fn stats(&self) -> QueueStats<T> {
+        self.lock().stats()
+    }
+
+    /// Pushes an item at the back of the queue, and returns
+    /// the stats of the queue, see [`crate::SumQueue::push_and_stats()`].
+    pub fn push_and_stats(&self, item: T) -> QueueStats<T> {
+        self.lock().push_and_stats(item)
+    }
+}
+
+/// Wraps an already configured queue, eg. created with
+/// [`crate::SumQueue::builder()`]:
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::sync;
+/// let queue: sync::SumQueue<i32> = sync::SumQueue::from(
+///     sum_queue::SumQueue::builder()
+///         .max_age(Duration::from_secs(60))
+///         .max_len(100)
+///         .build(),
+/// );
+/// assert_eq!(queue.max_len(), Some(100));
+/// ```
+impl<T> From<crate::SumQueue<T>> for SumQueue<T> {
+    fn from(queue: crate::SumQueue<T>) -> Self {
+        SumQueue {
+            inner: Mutex::new(Inner::new(queue)),
+        }
+    }
+}
+
+impl<T> Default for SumQueue<T> {
+    fn default() -> Self {
+        SumQueue::from(crate::SumQueue::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SumQueue").field(&*self.lock()).finish()
+    }
+}
+
+/// Cloneable handle of a thread-safe [`SumQueue`].
+///
+/// All the clones of the handle point to the same queue, so it can
+/// be captured by many closures or moved to other threads without
+/// passing `&mut` references around. All the methods of [`SumQueue`]
+/// are available through the handle:
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::sync::SharedSumQueue;
+///
+/// let queue = SharedSumQueue::new(Duration::from_secs(60));
+/// let producer = queue.clone();
+/// let on_event = move |value| {
+///     producer.push(value);
+/// };
+/// thread::spawn(move || on_event(10)).join().unwrap();
+/// assert_eq!(queue.len(), 1);
+/// assert_eq!(queue.pop(), Some(10));
+/// ```
+pub struct SharedSumQueue<T> {
+    inner: Arc<SumQueue<T>>,
+}
+
+impl<T> SharedSumQueue<T> {
+    /// Creates an empty queue, where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> SharedSumQueue<T> {
+        SharedSumQueue::from(SumQueue::new(max_age_duration))
+    }
+
+    /// Converts the handle into a [`Stream`](futures_core::Stream)
+    /// that pops the elements from the queue as soon as they are pushed.
+    ///
+    /// The stream never ends, when the queue is empty it waits until
+    /// an element is pushed through another handle of the same queue.
+    ///
+    /// ```
+    /// use std::future::poll_fn;
+    /// use std::pin::Pin;
+    /// use std::time::Duration;
+    /// use futures_core::Stream;
+    /// use sum_queue::sync::SharedSumQueue;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let queue = SharedSumQueue::new(Duration::from_secs(60));
+    /// queue.push(1);
+    /// queue.push(2);
+    /// let mut stream = queue.into_stream();
+    /// let next = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+    /// assert_eq!(next, Some(1));
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn into_stream(self) -> PopStream<T> {
+        PopStream::new(self)
+    }
+
+    /// Spawns a thread that pushes into the queue all the items received from
+    /// `receiver`, so the producers only need to send the items through
+    /// a channel, without waiting for the lock of the queue nor the
+    /// work of dropping the expired elements.
+    ///
+    /// `receiver` can be anything that can be iterated in a blocking way, like
+    /// the [`std::sync::mpsc::Receiver`] or the `crossbeam_channel::Receiver`.
+    /// The thread ends when the iterator ends, that is when all the senders
+    /// of the channel are dropped.
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SharedSumQueue;
+    ///
+    /// let queue = SharedSumQueue::new(Duration::from_secs(60));
+    /// let (sender, receiver) = mpsc::channel();
+    /// let feeder = queue.feed(receiver);
+    /// let producer = thread::spawn(move || {
+    ///     for i in 1..=10 {
+    ///         sender.send(i).unwrap();
+    ///     }
+    /// });
+    /// producer.join().unwrap();
+    /// feeder.join().unwrap();
+    /// assert_eq!(queue.stats().sum, Some(55));
+    /// ```
+    pub fn feed<I>(&self, receiver: I) -> JoinHandle<()>
+    where
+        I: IntoIterator<Item = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let queue = self.clone();
+        thread::spawn(move || {
+            for item in receiver {
+                queue.push(item);
+            }
+        })
+    }
+
+    /// Returns `true` if both handles point to the same queue.
+    pub fn ptr_eq(&self, other: &SharedSumQueue<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for SharedSumQueue<T> {
+    fn clone(&self) -> Self {
+        SharedSumQueue {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Deref for SharedSumQueue<T> {
+    type Target = SumQueue<T>;
+
+    fn deref(&self) -> &SumQueue<T> {
+        &self.inner
+    }
+}
+
+impl<T> From<SumQueue<T>> for SharedSumQueue<T> {
+    fn from(queue: SumQueue<T>) -> Self {
+        SharedSumQueue {
+            inner: Arc::new(queue),
+        }
+    }
+}
+
+impl<T> From<crate::SumQueue<T>> for SharedSumQueue<T> {
+    fn from(queue: crate::SumQueue<T>) -> Self {
+        SharedSumQueue::from(SumQueue::from(queue))
+    }
+}
+
+impl<T> Default for SharedSumQueue<T> {
+    fn default() -> Self {
+        SharedSumQueue::from(SumQueue::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedSumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedSumQueue")
+            .field(&*self.lock())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sync::{SharedSumQueue, SumQueue};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn push_pop_threads() {
+        let queue = Arc::new(SumQueue::new(Duration::from_secs(60)));
+        let producers = (0..4)
+            .map(|t| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        queue.push(t * 100 + i);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(queue.len(), 400);
+        let stats = queue.stats();
+        assert_eq!(stats.min, Some(0));
+        assert_eq!(stats.max, Some(399));
+        assert_eq!(stats.sum, Some((0..400).sum()));
+        let mut popped = queue.pop_n(400);
+        popped.sort_unstable();
+        assert_eq!(popped, (0..400).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn expire() {
+        let queue = SumQueue::new(Duration::from_millis(100));
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.peek(), Some(1));
+        assert!(queue.contains(&2));
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn shared() {
+        let queue: SharedSumQueue<i32> = SharedSumQueue::new(Duration::from_secs(60));
+        let other = queue.clone();
+        assert!(queue.ptr_eq(&other));
+        assert!(!queue.ptr_eq(&SharedSumQueue::default()));
+        let consumers = (0..3)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    queue.push(1);
+                    queue.len()
+                })
+            })
+            .collect::<Vec<_>>();
+        for consumer in consumers {
+            assert!(consumer.join().unwrap() > 0);
+        }
+        assert_eq!(other.stats().sum, Some(3));
+    }
+
+    #[test]
+    fn feed() {
+        let queue: SharedSumQueue<i32> = SharedSumQueue::new(Duration::from_secs(60));
+        let (sender, receiver) = mpsc::channel();
+        let feeder = queue.feed(receiver);
+        let producers = (0..4)
+            .map(|t| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for i in 0..25 {
+                        sender.send(t * 25 + i).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(sender);
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        feeder.join().unwrap();
+        assert_eq!(queue.len(), 100);
+        assert_eq!(queue.stats().sum, Some((0..100).sum()));
+        // the feeder ends with an empty channel too
+        let (sender, receiver) = mpsc::channel::<i32>();
+        drop(sender);
+        queue.feed(receiver).join().unwrap();
+        assert_eq!(queue.len(), 100);
+    }
+
+    #[test]
+    fn not_poisoned() {
+        let queue = Arc::new(SumQueue::new(Duration::from_secs(60)));
+        queue.push(1);
+        let queue_ref = queue.clone();
+        let result = thread::spawn(move || {
+            let _guard = queue_ref.lock();
+            panic!("panic while holding the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert_eq!(queue.push(2), 2);
+        assert_eq!(Arc::try_unwrap(queue).unwrap().into_inner().len(), 2);
+    }
+}
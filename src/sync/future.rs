@@ -0,0 +1,399 @@
+use crate::sync::{SharedSumQueue, SumQueue};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError},
+    thread,
+    time::Instant,
+};
+
+/// Task to wake up at a deadline.
+#[cfg(not(feature = "wasm"))]
+struct Deadline {
+    at: Instant,
+    waker: Waker,
+}
+
+#[cfg(not(feature = "wasm"))]
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Deadline) -> bool {
+        self.at == other.at
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+impl Eq for Deadline {}
+
+#[cfg(not(feature = "wasm"))]
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Deadline) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+impl Ord for Deadline {
+    fn cmp(&self, other: &Deadline) -> Ordering {
+        self.at.cmp(&other.at)
+    }
+}
+
+/// Timer shared by all the queues, with a thread that wakes
+/// up the tasks waiting for a deadline, the closest first.
+#[cfg(not(feature = "wasm"))]
+struct Timer {
+    deadlines: Mutex<BinaryHeap<Reverse<Deadline>>>,
+    condvar: Condvar,
+}
+
+#[cfg(not(feature = "wasm"))]
+impl Timer {
+    /// Returns the timer, starting its thread the first time.
+    fn get() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        TIMER.get_or_init(|| {
+            thread::spawn(|| Timer::get().run());
+            Timer {
+                deadlines: Mutex::new(BinaryHeap::new()),
+                condvar: Condvar::new(),
+            }
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BinaryHeap<Reverse<Deadline>>> {
+        self.deadlines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn add(&self, at: Instant, wakers: impl Iterator<Item = Waker>) {
+        let mut deadlines = self.lock();
+        deadlines.extend(wakers.map(|waker| Reverse(Deadline { at, waker })));
+        self.condvar.notify_one();
+    }
+
+    fn run(&self) {
+        let mut deadlines = self.lock();
+        loop {
+            let now = Instant::now();
+            let mut due = Vec::new();
+            while deadlines.peek().is_some_and(|Reverse(d)| d.at <= now) {
+                due.extend(deadlines.pop().map(|Reverse(d)| d.waker));
+            }
+            if !due.is_empty() {
+                // woken up without the lock, so the tasks
+                // polled in other threads can add deadlines
+                drop(deadlines);
+                due.into_iter().for_each(Waker::wake);
+                deadlines = self.lock();
+                continue;
+            }
+            deadlines = match deadlines.peek() {
+                Some(Reverse(d)) => {
+                    let timeout = d.at - now;
+                    let (deadlines, _) = self
+                        .condvar
+                        .wait_timeout(deadlines, timeout)
+                        .unwrap_or_else(PoisonError::into_inner);
+                    deadlines
+                }
+                None => self
+                    .condvar
+                    .wait(deadlines)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+/// Wakes up the tasks once `delay` has elapsed, from the timer thread
+/// shared by all the queues.
+#[cfg(not(feature = "wasm"))]
+pub(super) fn wake_after(wakers: &mut Vec<Waker>, delay: Duration) {
+    Timer::get().add(Instant::now() + delay, wakers.drain(..));
+}
+
+/// Keeps the tasks registered, without threads to wait for the delay,
+/// so they are woken up by the next operation on the queue after it.
+#[cfg(feature = "wasm")]
+pub(super) fn wake_after(_wakers: &mut Vec<Waker>, _delay: Duration) {}
+
+impl<T> SumQueue<T> {
+    /// Pops the first element, or registers the task to be woken up
+    /// when an element is ready to pop, see the `Drop` of the guard.
+    fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut guard = self.lock();
+        match guard.pop() {
+            Some(item) => Poll::Ready(item),
+            None => {
+                // registered while holding the lock, so
+                // a push can't happen before the registration
+                let wakers = &mut guard.guard.pop_wakers;
+                if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Future that resolves with the first element of a [`SumQueue`]
+/// as soon as there is one.
+///
+/// This `struct` is created by [`SumQueue::pop_async()`]. See its
+/// documentation for more.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Pop<'a, T> {
+    queue: &'a SumQueue<T>,
+}
+
+impl<'a, T> Pop<'a, T> {
+    pub(super) fn new(queue: &'a SumQueue<T>) -> Pop<'a, T> {
+        Pop { queue }
+    }
+}
+
+impl<T> Future for Pop<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        self.queue.poll_pop(cx)
+    }
+}
+
+impl<T> fmt::Debug for Pop<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pop").finish_non_exhaustive()
+    }
+}
+
+/// Stream of the elements popped from a [`SharedSumQueue`].
+///
+/// This `struct` is created by [`SharedSumQueue::into_stream()`]. See its
+/// documentation for more.
+#[must_use = "streams do nothing unless polled"]
+pub struct PopStream<T> {
+    queue: SharedSumQueue<T>,
+}
+
+impl<T> PopStream<T> {
+    pub(super) fn new(queue: SharedSumQueue<T>) -> PopStream<T> {
+        PopStream { queue }
+    }
+
+    /// Returns the handle of the queue the elements are popped from.
+    pub fn queue(&self) -> &SharedSumQueue<T> {
+        &self.queue
+    }
+}
+
+impl<T> Stream for PopStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.queue.poll_pop(cx).map(Some)
+    }
+}
+
+impl<T> fmt::Debug for PopStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PopStream").finish_non_exhaustive()
+    }
+}
+
+/// Pushes the elements sent into the queue, so a [`SharedSumQueue`]
+/// can be the end of a stream pipeline.
+///
+/// If the queue was configured with a max length, the sink waits
+/// until there is room in the queue before accept a new element,
+/// instead of dropping the oldest elements to make room. Waiting tasks
+/// are woken up by the operations on the queue that free room,
+/// like [`SumQueue::pop()`], or any method that drops expired elements.
+impl<T> Sink<T> for SharedSumQueue<T> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        let mut guard = self.lock();
+        guard.prune();
+        if guard.is_full() {
+            let wakers = &mut guard.guard.push_wakers;
+            if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Infallible> {
+        self.push(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sync::SharedSumQueue;
+    use futures_core::Stream;
+    use futures_sink::Sink;
+    use std::future::poll_fn;
+    use std::pin::Pin;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn pop_async_ready() {
+        let queue = SharedSumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.pop_async().await, 1);
+        assert_eq!(queue.pop_async().await, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pop_async_wait() {
+        let queue = SharedSumQueue::new(Duration::from_secs(60));
+        let consumers = (0..3)
+            .map(|_| {
+                let queue = queue.clone();
+                tokio::spawn(async move { queue.pop_async().await })
+            })
+            .collect::<Vec<_>>();
+        let producer = queue.clone();
+        thread::spawn(move || {
+            for i in 1..=3 {
+                thread::sleep(Duration::from_millis(50));
+                producer.push(i);
+            }
+        });
+        let mut popped = Vec::new();
+        for consumer in consumers {
+            popped.push(consumer.await.unwrap());
+        }
+        popped.sort_unstable();
+        assert_eq!(popped, vec![1, 2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[tokio::test]
+    async fn pop_async_min_age() {
+        let queue = SharedSumQueue::from(
+            crate::SumQueue::new(Duration::from_secs(60)).with_min_age(Duration::from_millis(100)),
+        );
+        queue.push(1);
+        let start = Instant::now();
+        let popped = tokio::time::timeout(Duration::from_secs(5), queue.pop_async()).await;
+        assert_eq!(popped, Ok(1));
+        // woken up by the timer, not by the timeout
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(90) && elapsed < Duration::from_secs(1));
+        assert!(queue.is_empty());
+        // many tasks waiting share the same timer thread
+        let consumers = (0..10)
+            .map(|i| {
+                queue.push(i);
+                let queue = queue.clone();
+                tokio::spawn(async move { queue.pop_async().await })
+            })
+            .collect::<Vec<_>>();
+        let mut popped = Vec::new();
+        for consumer in consumers {
+            popped.push(consumer.await.unwrap());
+        }
+        popped.sort_unstable();
+        assert_eq!(popped, (0..10).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pop_async_min_age() {
+        let queue = SharedSumQueue::from(
+            crate::SumQueue::new(Duration::from_secs(60)).with_min_age(Duration::from_millis(100)),
+        );
+        queue.push(1);
+        let producer = queue.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(150));
+            producer.push(2);
+        });
+        let start = Instant::now();
+        let popped = tokio::time::timeout(Duration::from_secs(5), queue.pop_async()).await;
+        assert_eq!(popped, Ok(1));
+        // woken up by the push after the min age
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(140) && elapsed < Duration::from_secs(1));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn into_stream() {
+        let queue = SharedSumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        let producer = queue.clone();
+        let mut stream = queue.into_stream();
+        thread::spawn(move || {
+            for i in 2..=4 {
+                thread::sleep(Duration::from_millis(20));
+                producer.push(i);
+            }
+        });
+        let mut popped = Vec::new();
+        while popped.len() < 4 {
+            let item = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+            popped.push(item.unwrap());
+        }
+        assert_eq!(popped, vec![1, 2, 3, 4]);
+        assert!(stream.queue().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sink_backpressure() {
+        let queue = SharedSumQueue::from(
+            crate::SumQueue::builder()
+                .max_age(Duration::from_secs(60))
+                .max_len(2)
+                .build(),
+        );
+        let consumer = queue.clone();
+        let mut sink = queue.clone();
+        let producer = tokio::spawn(async move {
+            for i in 1..=4 {
+                poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+                    .await
+                    .unwrap();
+                Pin::new(&mut sink).start_send(i).unwrap();
+            }
+            poll_fn(|cx| Pin::new(&mut sink).poll_close(cx))
+                .await
+                .unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // the producer waits for room instead of dropping elements
+        assert_eq!(queue.to_vec(), vec![1, 2]);
+        let mut popped = Vec::new();
+        while popped.len() < 4 {
+            popped.push(consumer.pop_async().await);
+        }
+        producer.await.unwrap();
+        assert_eq!(popped, vec![1, 2, 3, 4]);
+    }
+}
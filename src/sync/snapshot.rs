@@ -0,0 +1,217 @@
+use crate::sync::SumQueue;
+use crate::{QueueStats, Summable};
+use arc_swap::ArcSwap;
+use std::fmt;
+use std::ops::Add;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Thread-safe queue that keeps a snapshot of its latest stats.
+///
+/// The stats are computed after each method call that modifies
+/// the queue, and stored in an atomic cell, so other threads can read
+/// them with [`SnapshotSumQueue::stats_snapshot()`] without locking the
+/// queue, and so without blocking the threads that push elements.
+///
+/// Computing the stats has a cost of `O(n)` on each modification, so
+/// this type is worth it when the stats are read very often by
+/// other threads, eg. to monitor the queue.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+/// use sum_queue::sync::SnapshotSumQueue;
+///
+/// let queue = Arc::new(SnapshotSumQueue::new(Duration::from_secs(60)));
+/// let producer = {
+///     let queue = queue.clone();
+///     thread::spawn(move || {
+///         for i in 1..=10 {
+///             queue.push(i);
+///         }
+///     })
+/// };
+/// producer.join().unwrap();
+/// let stats = queue.stats_snapshot();
+/// assert_eq!(stats.sum, Some(55));
+/// assert_eq!(stats.len, 10);
+/// ```
+///
+/// Elements that expired after the last modification are still included in the
+/// snapshot, call [`SnapshotSumQueue::refresh()`] periodically if
+/// the queue is not modified often.
+pub struct SnapshotSumQueue<T: PartialOrd + Add<Output = T>> {
+    queue: SumQueue<T>,
+    snapshot: ArcSwap<QueueStats<T>>,
+}
+
+impl<T: Summable> SnapshotSumQueue<T> {
+    /// Creates an empty queue, where the elements inside
+    /// will live `max_age_duration` at maximum.
+    pub fn new(max_age_duration: Duration) -> SnapshotSumQueue<T> {
+        SnapshotSumQueue::from(crate::SumQueue::new(max_age_duration))
+    }
+
+    /// Runs `f` with the inner queue locked, and then
+    /// updates the stats snapshot.
+    fn update<R>(&self, f: impl FnOnce(&mut crate::SumQueue<T>) -> R) -> R {
+        let mut queue = self.queue.lock();
+        let result = f(&mut queue);
+        self.snapshot.store(Arc::new(queue.stats()));
+        result
+    }
+
+    /// Returns the stats of the queue computed after the last modification,
+    /// without locking the queue.
+    pub fn stats_snapshot(&self) -> Arc<QueueStats<T>> {
+        self.snapshot.load_full()
+    }
+
+    /// Drops the expired elements and updates the stats snapshot.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SnapshotSumQueue;
+    /// let queue = SnapshotSumQueue::new(Duration::from_millis(100));
+    /// queue.push(1);
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert_eq!(queue.stats_snapshot().len, 1);
+    /// queue.refresh();
+    /// assert_eq!(queue.stats_snapshot().len, 0);
+    /// ```
+    pub fn refresh(&self) {
+        self.update(|_| ());
+    }
+
+    /// Pushes an item at the back of the queue, see [`crate::SumQueue::push()`].
+    pub fn push(&self, item: T) -> usize {
+        self.update(|queue| queue.push(item))
+    }
+
+    /// Removes the first item from the queue and returns it, or `None` if it
+    /// is empty, see [`crate::SumQueue::pop()`].
+    pub fn pop(&self) -> Option<T> {
+        self.update(|queue| queue.pop())
+    }
+
+    /// Removes up to `n` items from the queue, see [`crate::SumQueue::pop_n()`].
+    pub fn pop_n(&self, n: usize) -> Vec<T> {
+        self.update(|queue| queue.pop_n(n))
+    }
+
+    /// Removes the items from the queue while the `predicate` returns `true`
+    /// for them, see [`crate::SumQueue::pop_while()`].
+    pub fn pop_while<P>(&self, predicate: P) -> Vec<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.update(|queue| queue.pop_while(predicate))
+    }
+
+    /// Drops all items.
+    pub fn clear(&self) {
+        self.update(|queue| queue.clear())
+    }
+
+    /// Returns the length of the queue, see [`crate::SumQueue::len()`].
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty, see [`crate::SumQueue::is_empty()`].
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns the max time the elements will live in the queue.
+    pub fn max_age(&self) -> Duration {
+        self.queue.max_age()
+    }
+
+    /// Get the current statistics of the queue locking it,
+    /// see [`crate::SumQueue::stats()`]. It also updates the snapshot.
+    pub fn stats(&self) -> QueueStats<T> {
+        self.update(|queue| queue.stats())
+    }
+}
+
+impl<T: Summable> From<crate::SumQueue<T>> for SnapshotSumQueue<T> {
+    fn from(mut queue: crate::SumQueue<T>) -> Self {
+        let stats = queue.stats();
+        SnapshotSumQueue {
+            queue: SumQueue::from(queue),
+            snapshot: ArcSwap::from_pointee(stats),
+        }
+    }
+}
+
+impl<T: fmt::Debug + PartialOrd + Add<Output = T>> fmt::Debug for SnapshotSumQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotSumQueue")
+            .field("queue", &self.queue)
+            .field("snapshot", &self.snapshot.load())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sync::SnapshotSumQueue;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn snapshot() {
+        let queue: SnapshotSumQueue<i32> = SnapshotSumQueue::new(Duration::from_secs(60));
+        assert_eq!(queue.stats_snapshot().len, 0);
+        assert_eq!(queue.stats_snapshot().sum, None);
+        queue.push(5);
+        queue.push(-1);
+        queue.push(3);
+        let stats = queue.stats_snapshot();
+        assert_eq!(stats.min, Some(-1));
+        assert_eq!(stats.max, Some(5));
+        assert_eq!(stats.sum, Some(7));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.stats_snapshot().sum, Some(2));
+        assert_eq!(queue.pop_while(|&x| x < 0), vec![-1]);
+        assert_eq!(queue.stats_snapshot().len, 1);
+        queue.clear();
+        assert_eq!(queue.stats_snapshot().len, 0);
+    }
+
+    #[test]
+    fn snapshot_threads() {
+        let queue = Arc::new(SnapshotSumQueue::new(Duration::from_secs(60)));
+        let producers = (0..4)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        queue.push(i);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let monitor = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut last_len = 0;
+                for _ in 0..100 {
+                    let len = queue.stats_snapshot().len;
+                    assert!(len >= last_len);
+                    last_len = len;
+                }
+            })
+        };
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        monitor.join().unwrap();
+        assert_eq!(queue.stats_snapshot().len, 400);
+        assert_eq!(queue.stats_snapshot().sum, Some(4 * 4950));
+    }
+}
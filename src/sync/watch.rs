@@ -0,0 +1,105 @@
+use crate::sync::SumQueue;
+use crate::{QueueStats, Summable};
+use tokio::sync::watch;
+
+impl<T> SumQueue<T>
+where
+    T: Summable + Send + Sync + 'static,
+{
+    /// Returns a [`watch::Receiver`] that receives the stats of the queue
+    /// each time they change, so other tasks can react to the changes
+    /// without polling the queue.
+    ///
+    /// The stats are computed again each time the lock of the queue
+    /// is released, that is after each method call, so it has a cost
+    /// of `O(n)` on every call once the channel is created. Elements
+    /// that expire don't update the stats until the queue is accessed again.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::sync::SharedSumQueue;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let queue = SharedSumQueue::new(Duration::from_secs(60));
+    /// let mut stats = queue.stats_watch();
+    /// let producer = queue.clone();
+    /// tokio::spawn(async move {
+    ///     producer.push(10);
+    /// });
+    /// stats.changed().await.unwrap();
+    /// assert_eq!(stats.borrow().sum, Some(10));
+    /// # }
+    /// ```
+    pub fn stats_watch(&self) -> watch::Receiver<QueueStats<T>> {
+        let mut inner = self.lock_inner();
+        if let Some(stats_tx) = &inner.stats_tx {
+            return stats_tx.subscribe();
+        }
+        let (stats_tx, stats_rx) = watch::channel(inner.queue.stats());
+        inner.stats_tx = Some(Box::new(stats_tx));
+        stats_rx
+    }
+}
+
+/// Channel where the stats of a queue are sent.
+///
+/// The trait hides the bounds needed to compute the stats, so
+/// the channel can be stored within the state of any queue.
+pub(super) trait StatsSender<T>: Send {
+    /// Sends the stats of `queue` to the channel if they changed.
+    fn update(&self, queue: &mut crate::SumQueue<T>);
+
+    /// Creates a new receiver of the channel.
+    fn subscribe(&self) -> watch::Receiver<QueueStats<T>>;
+}
+
+impl<T> StatsSender<T> for watch::Sender<QueueStats<T>>
+where
+    T: Summable + Send + Sync,
+{
+    fn update(&self, queue: &mut crate::SumQueue<T>) {
+        let stats = queue.stats();
+        self.send_if_modified(|current| {
+            if *current == stats {
+                false
+            } else {
+                *current = stats;
+                true
+            }
+        });
+    }
+
+    fn subscribe(&self) -> watch::Receiver<QueueStats<T>> {
+        watch::Sender::subscribe(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sync::SharedSumQueue;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stats_watch() {
+        let queue: SharedSumQueue<i32> = SharedSumQueue::new(Duration::from_secs(60));
+        queue.push(1);
+        let mut stats = queue.stats_watch();
+        let mut other = queue.stats_watch();
+        assert_eq!(stats.borrow_and_update().sum, Some(1));
+        let producer = queue.clone();
+        tokio::spawn(async move {
+            producer.push(2);
+        });
+        stats.changed().await.unwrap();
+        assert_eq!(stats.borrow_and_update().sum, Some(3));
+        other.changed().await.unwrap();
+        assert_eq!(other.borrow_and_update().len, 2);
+        // reading the queue doesn't change the stats
+        assert_eq!(queue.len(), 2);
+        assert!(!stats.has_changed().unwrap());
+        queue.pop();
+        assert!(stats.has_changed().unwrap());
+        assert_eq!(stats.borrow_and_update().min, Some(2));
+    }
+}
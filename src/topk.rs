@@ -0,0 +1,159 @@
+use crate::SumQueue;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Tracker of the most frequent values ("heavy hitters") seen within a
+/// sliding window of time, eg. the top 10 IPs in the last 5 minutes.
+///
+/// Every value pushed is kept in a [`SumQueue`] until it leaves the window,
+/// and the count of each distinct value is updated as the values are pushed
+/// and expire, so the counts only take into account the values within the window.
+///
+/// ```
+/// use std::time::Duration;
+/// use sum_queue::TopK;
+///
+/// let mut ips = TopK::new(Duration::from_secs(300));
+/// for ip in ["10.0.0.1", "10.0.0.2", "10.0.0.1", "10.0.0.3", "10.0.0.1", "10.0.0.2"] {
+///     ips.push(ip);
+/// }
+/// assert_eq!(ips.top(2), vec![(&"10.0.0.1", 3), (&"10.0.0.2", 2)]);
+/// assert_eq!(ips.count(&"10.0.0.3"), 1);
+/// ```
+pub struct TopK<T> {
+    /// the values pushed, they are expired by hand
+    /// to keep the counts up to date
+    queue: SumQueue<T>,
+    counts: BTreeMap<T, usize>,
+    window: Duration,
+}
+
+impl<T: Ord + Clone> TopK<T> {
+    /// Creates an empty tracker of the values pushed within the last `window` of time.
+    pub fn new(window: Duration) -> TopK<T> {
+        TopK {
+            queue: SumQueue::new(Duration::MAX),
+            counts: BTreeMap::new(),
+            window,
+        }
+    }
+
+    /// Drops the values that left the window, updating their counts.
+    fn expire(&mut self) {
+        while let Some((_, age)) = self.queue.peek_with_age() {
+            if age <= self.window {
+                break;
+            }
+            let value = self.queue.pop().unwrap();
+            if let Some(count) = self.counts.get_mut(&value) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&value);
+                }
+            }
+        }
+    }
+
+    /// Pushes a value, and returns the number of times
+    /// the value was seen within the window.
+    pub fn push(&mut self, item: T) -> usize {
+        self.expire();
+        let count = self.counts.entry(item.clone()).or_insert(0);
+        *count += 1;
+        let count = *count;
+        self.queue.push(item);
+        count
+    }
+
+    /// Returns the number of times `item` was seen within the window.
+    pub fn count(&mut self, item: &T) -> usize {
+        self.expire();
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Returns up to `k` values seen within the window with their
+    /// counts, the most frequent first. Values with the same count
+    /// are sorted by their own order.
+    pub fn top(&mut self, k: usize) -> Vec<(&T, usize)> {
+        self.expire();
+        let mut top = self
+            .counts
+            .iter()
+            .map(|(value, count)| (value, *count))
+            .collect::<Vec<_>>();
+        top.sort_by(|(_, a), (_, b)| b.cmp(a));
+        top.truncate(k);
+        top
+    }
+
+    /// Returns the number of values pushed within the window.
+    pub fn len(&mut self) -> usize {
+        self.expire();
+        self.queue.len()
+    }
+
+    /// Checks if no value was pushed within the window.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of distinct values pushed within the window.
+    pub fn distinct(&mut self) -> usize {
+        self.expire();
+        self.counts.len()
+    }
+
+    /// Returns the length of the window.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Drops all the values.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.counts.clear();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TopK<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopK")
+            .field("window", &self.window)
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TopK;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn top() {
+        let mut top = TopK::new(Duration::from_millis(100));
+        assert_eq!(top.window(), Duration::from_millis(100));
+        assert!(top.top(3).is_empty());
+        assert_eq!(top.push('b'), 1);
+        assert_eq!(top.push('a'), 1);
+        assert_eq!(top.push('b'), 2);
+        thread::sleep(Duration::from_millis(60));
+        top.push('c');
+        top.push('c');
+        top.push('c');
+        assert_eq!(top.top(5), vec![(&'c', 3), (&'b', 2), (&'a', 1)]);
+        assert_eq!(top.len(), 6);
+        thread::sleep(Duration::from_millis(60));
+        // the first values left the window
+        assert_eq!(top.top(5), vec![(&'c', 3)]);
+        assert_eq!(top.count(&'b'), 0);
+        assert_eq!(top.distinct(), 1);
+        assert_eq!(top.push('a'), 1);
+        assert_eq!(top.top(1), vec![(&'c', 3)]);
+        top.clear();
+        assert!(top.is_empty());
+        assert_eq!(top.distinct(), 0);
+    }
+}
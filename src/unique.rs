@@ -0,0 +1,231 @@
+use crate::{QueueElement, SumQueue};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash};
+
+/// Positions of the elements of a [`SumQueue`] by the hash of their
+/// values, to find an element equal to a value in O(1) expected time,
+/// see [`SumQueue::push_unique()`].
+///
+/// Like the [`crate::minmax::MinMaxIndex`], the elements are numbered in
+/// push order, so they are only kept up to date on the pushes at the back
+/// and the removals at the front of the queue. Any other change marks
+/// them as stale, and they are rebuilt the next time they are searched.
+#[derive(Clone)]
+pub(crate) struct UniqueIndex<T> {
+    hasher: RandomState,
+    /// hashes a value
+    hash: fn(&RandomState, &T) -> u64,
+    /// checks if a value is equal to another
+    eq: fn(&T, &T) -> bool,
+    /// number of the first element of the queue
+    first: u64,
+    /// numbers of the elements by the hash of their values, in push order
+    numbers: HashMap<u64, VecDeque<u64>>,
+    /// whether the queue changed other than at its ends
+    stale: bool,
+}
+
+impl<T> UniqueIndex<T> {
+    fn new() -> UniqueIndex<T>
+    where
+        T: Eq + Hash,
+    {
+        UniqueIndex {
+            hasher: RandomState::new(),
+            hash: |hasher, value| hasher.hash_one(value),
+            eq: |a, b| a == b,
+            first: 0,
+            numbers: HashMap::new(),
+            stale: true,
+        }
+    }
+
+    /// Returns an empty index, to be rebuilt before it's searched.
+    pub(crate) fn empty_like(&self) -> UniqueIndex<T> {
+        UniqueIndex {
+            hasher: self.hasher.clone(),
+            hash: self.hash,
+            eq: self.eq,
+            first: 0,
+            numbers: HashMap::new(),
+            stale: true,
+        }
+    }
+
+    fn value<'a>(&self, queue: &'a VecDeque<QueueElement<T>>, number: u64) -> &'a T {
+        &queue[(number - self.first) as usize].value
+    }
+
+    /// Adds the element `number` of `queue`, the newest one in the index.
+    fn push(&mut self, queue: &VecDeque<QueueElement<T>>, number: u64) {
+        let hash = (self.hash)(&self.hasher, self.value(queue, number));
+        self.numbers.entry(hash).or_default().push_back(number);
+    }
+
+    /// Removes the first element of `queue`, about to be removed from it.
+    fn pop_front(&mut self, queue: &VecDeque<QueueElement<T>>) {
+        if let Some(el) = queue.front() {
+            let hash = (self.hash)(&self.hasher, &el.value);
+            if let Some(numbers) = self.numbers.get_mut(&hash) {
+                numbers.pop_front();
+                if numbers.is_empty() {
+                    self.numbers.remove(&hash);
+                }
+            }
+            self.first += 1;
+        }
+    }
+
+    /// Checks if there is an element in `queue` equal to `item`.
+    fn contains(&self, queue: &VecDeque<QueueElement<T>>, item: &T) -> bool {
+        self.numbers
+            .get(&(self.hash)(&self.hasher, item))
+            .is_some_and(|numbers| {
+                numbers
+                    .iter()
+                    .any(|&number| (self.eq)(self.value(queue, number), item))
+            })
+    }
+
+    /// Rebuilds the index from the elements of `queue`, if stale.
+    fn refresh(&mut self, queue: &VecDeque<QueueElement<T>>) {
+        if self.stale {
+            self.first = 0;
+            self.numbers.clear();
+            for number in 0..queue.len() as u64 {
+                self.push(queue, number);
+            }
+            self.stale = false;
+        }
+    }
+
+    /// Checks that the index is the same as if rebuilt from `queue`, if not stale.
+    pub(crate) fn assert_rebuilt(&self, queue: &VecDeque<QueueElement<T>>) {
+        if !self.stale {
+            let mut rebuilt = self.empty_like();
+            rebuilt.refresh(queue);
+            let same = self.numbers.len() == rebuilt.numbers.len()
+                && self.numbers.iter().all(|(hash, numbers)| {
+                    rebuilt.numbers.get(hash).is_some_and(|rebuilt| {
+                        numbers
+                            .iter()
+                            .map(|number| number.wrapping_sub(self.first))
+                            .eq(rebuilt.iter().copied())
+                    })
+                });
+            assert!(same, "unique index out of date");
+        }
+    }
+}
+
+impl<T> SumQueue<T> {
+    /// Pushes an item at the back of the queue only if there is no equal
+    /// element in the queue, and returns whether it was pushed, eg. to
+    /// suppress duplicated alerts within the max age of the queue.
+    ///
+    /// Before the search is done, it also drops all expired elements,
+    /// so an item can be pushed again once the equal element expired.
+    ///
+    /// The search takes O(1) expected time: the first call builds an index
+    /// of the values by their hash, that is kept up to date on each push,
+    /// pop and expiration. Other changes of the elements, like
+    /// [`SumQueue::iter_mut()`], make the next call rebuild it once.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sum_queue::SumQueue;
+    /// let mut alerts = SumQueue::new(Duration::from_secs(300));
+    /// assert!(alerts.push_unique("disk full"));
+    /// assert!(alerts.push_unique("high cpu"));
+    /// assert!(!alerts.push_unique("disk full"));
+    /// assert_eq!(alerts.len(), 2);
+    /// ```
+    pub fn push_unique(&mut self, item: T) -> bool
+    where
+        T: Eq + Hash,
+    {
+        self.clear_oldest(self.now());
+        let index = self.unique.get_or_insert_with(UniqueIndex::new);
+        index.refresh(&self.queue);
+        if index.contains(&self.queue, &item) {
+            return false;
+        }
+        self.push(item);
+        true
+    }
+
+    /// Adds the element just pushed at the back to the index, if any.
+    pub(crate) fn unique_pushed(&mut self) {
+        if let Some(index) = self.unique.as_mut().filter(|index| !index.stale) {
+            let number = index.first + self.queue.len() as u64 - 1;
+            index.push(&self.queue, number);
+        }
+    }
+
+    /// Removes the element about to be removed from the front from the index, if any.
+    pub(crate) fn unique_popping(&mut self) {
+        if let Some(index) = self.unique.as_mut().filter(|index| !index.stale) {
+            index.pop_front(&self.queue);
+        }
+    }
+
+    /// Marks the index as stale, if any, after the elements
+    /// were changed other than at the ends of the queue.
+    pub(crate) fn unique_changed(&mut self) {
+        if let Some(index) = self.unique.as_mut() {
+            index.stale = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Clock, SumQueue};
+    use std::time::Duration;
+
+    #[test]
+    fn push_unique_index() {
+        let clock = Clock::manual();
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(10))
+            .max_len(4)
+            .clock(clock.clone())
+            .build();
+        // the values pushed before the index is built are indexed
+        queue.push(1);
+        queue.push(1);
+        assert!(!queue.push_unique(1));
+        assert!(queue.push_unique(2));
+        // pushed and evicted by the max length
+        queue.push(3);
+        queue.push(4);
+        assert_eq!(queue.to_vec(), vec![1, 2, 3, 4]);
+        assert!(!queue.push_unique(1));
+        queue.pop();
+        assert!(queue.push_unique(1));
+        assert!(!queue.push_unique(4));
+        // expired
+        clock.advance(Duration::from_secs(11));
+        assert!(queue.push_unique(4));
+        // changed in the middle of the queue
+        queue.push(5);
+        queue.iter_mut().for_each(|value| *value *= 10);
+        assert!(!queue.push_unique(50));
+        assert!(queue.push_unique(5));
+        assert_eq!(queue.to_vec(), vec![40, 50, 5]);
+        // the same as comparing all the values
+        let mut queue = SumQueue::builder()
+            .max_age(Duration::from_secs(60))
+            .max_len(8)
+            .build();
+        for i in 0..200u32 {
+            let item = i * 7919 % 13;
+            let expected = !queue.contains(&item);
+            assert_eq!(queue.push_unique(item), expected);
+            if i % 3 == 0 {
+                queue.pop();
+            }
+        }
+    }
+}